@@ -1,3 +1,37 @@
+/// Public key of the test network descriptors, whose signatures are in
+/// `tests/test_provide_network_cmd.py`.
+const TEST_NETWORK_DESCRIPTOR_KEY: &str =
+    "04ef931422c0b054f16930b53714bb5d215dccf043edc8768c4022d08313233b64f89fb2b09660d7242fbcec321a2b3dfa4d706006c90e262d623ca409d0362064";
+
 fn main() {
     println!("cargo:rerun-if-changed=script.ld");
+    println!("cargo:rerun-if-env-changed=NETWORK_DESCRIPTOR_KEY");
+
+    // Only the app checks network descriptors, not the host crates that use
+    // the library
+    if std::env::var_os("CARGO_FEATURE_DEVICE").is_none() {
+        return;
+    }
+    // The app cannot be built without knowing which key to trust, and the
+    // test key, whose signatures are public, must be asked for explicitly
+    let test_key = std::env::var_os("CARGO_FEATURE_TEST_NETWORK_KEY").is_some();
+    let key = match std::env::var("NETWORK_DESCRIPTOR_KEY") {
+        Ok(_) if test_key => {
+            panic!("NETWORK_DESCRIPTOR_KEY cannot be set with the test_network_key feature")
+        }
+        Ok(key) if key == TEST_NETWORK_DESCRIPTOR_KEY => {
+            panic!("the test network descriptor key is only trusted with the test_network_key feature")
+        }
+        Ok(key) => key,
+        Err(_) if test_key => TEST_NETWORK_DESCRIPTOR_KEY.into(),
+        Err(_) => panic!(
+            "NETWORK_DESCRIPTOR_KEY must be set to the trusted key of the network descriptors, \
+             or the test_network_key feature enabled for Speculos"
+        ),
+    };
+    // Refuse a malformed key, rather than an app that refuses every network
+    if key.len() != 130 || !key.starts_with("04") || !key.chars().all(|c| c.is_ascii_hexdigit()) {
+        panic!("NETWORK_DESCRIPTOR_KEY must be an uncompressed secp256k1 public key in hex");
+    }
+    println!("cargo:rustc-env=NETWORK_DESCRIPTOR_KEY={key}");
 }
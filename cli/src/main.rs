@@ -0,0 +1,292 @@
+//! Command-line tool to drive the Aeternity Ledger app through a TCP APDU
+//! endpoint, such as the APDU port of Speculos.
+//!
+//! Signatures are printed as `sg_...`, signed transactions as `tx_...` and
+//! their hashes as `th_...`, so that they can be used with the Aeternity APIs.
+
+use std::error::Error;
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+
+use app_aeternity::config::{AppConfiguration, SETTING_BLIND_SIGNING, SETTING_EXPERT_MODE};
+use app_aeternity::encoding::{from_ae_string, to_ae_base64_string, to_ae_string, AeEncoding};
+use app_aeternity::tx::{encode_signed_tx, MAX_SIGNED_TX_LEN};
+use app_aeternity_client::{Client, SignTx, SignTxBatch, Signature, TcpTransport};
+
+#[derive(Parser)]
+#[command(version, about)]
+struct Cli {
+    /// Address of the TCP APDU endpoint
+    #[arg(long, env = "AETERNITY_LEDGER_APDU", default_value = "127.0.0.1:9999")]
+    apdu: String,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print the version of the app
+    Version,
+    /// Print the configuration of the app
+    Configuration,
+    /// Print the address of an account
+    Address {
+        #[arg(long, default_value_t = 0)]
+        account: u32,
+        /// Show the address on the device for the user to verify it
+        #[arg(long)]
+        verify: bool,
+    },
+    /// Add an `ak_` or `ct_` address to the address book of the app, after
+    /// confirming it on the device
+    RegisterAddress {
+        address: String,
+        /// Label shown instead of the address when reviewing transactions
+        label: String,
+    },
+    /// Describe a network that the app does not know, like a Hyperchain,
+    /// with a descriptor signed by the trusted key of the app
+    ProvideNetwork {
+        /// Descriptor in hex
+        descriptor: String,
+    },
+    /// Sign a transaction, and print the signature, the signed transaction
+    /// and its hash, as computed by the app when it can keep the transaction
+    SignTx {
+        /// Transaction, as `tx_...` or as RLP in hex
+        tx: String,
+        #[arg(long, default_value_t = 0)]
+        account: u32,
+        #[arg(long, default_value = "ae_mainnet")]
+        network_id: String,
+        /// Sign it as the inner transaction of a PayingForTx
+        #[arg(long)]
+        inner: bool,
+        /// AENS name of an `nm_` recipient, shown on the device instead of
+        /// the name hash
+        #[arg(long)]
+        recipient_name: Option<String>,
+    },
+    /// Sign a batch of SpendTx after a single review, and print each signed
+    /// transaction
+    SignTxBatch {
+        /// Transactions, as `tx_...` or as RLP in hex
+        #[arg(required = true)]
+        txs: Vec<String>,
+        #[arg(long, default_value_t = 0)]
+        account: u32,
+        #[arg(long, default_value = "ae_mainnet")]
+        network_id: String,
+    },
+    /// Sign a message, and print the signature
+    SignMsg {
+        message: String,
+        #[arg(long, default_value_t = 0)]
+        account: u32,
+    },
+    /// Sign data, and print the signature. Blind signing must be enabled in
+    /// the settings of the app.
+    SignData {
+        /// Data in hex
+        data: String,
+        #[arg(long, default_value_t = 0)]
+        account: u32,
+        /// Sign the Blake2b-256 hash of the data instead of the data itself
+        #[arg(long)]
+        hash: bool,
+    },
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<(), Box<dyn Error>> {
+    let apdu = cli.apdu;
+    let connect = || {
+        TcpTransport::connect(&apdu)
+            .map(Client::new)
+            .map_err(|err| format!("cannot connect to {}: {}", apdu, err))
+    };
+
+    match cli.command {
+        Command::Version => println!("{}", connect()?.get_version()?),
+        Command::Configuration => print_configuration(&connect()?.get_app_configuration()?),
+        Command::Address { account, verify } => {
+            println!("{}", connect()?.get_address(account, verify)?)
+        }
+        Command::RegisterAddress { address, label } => {
+            connect()?.register_address(&label, &address)?
+        }
+        Command::ProvideNetwork { descriptor } => {
+            connect()?.provide_network(&parse_hex(&descriptor)?)?
+        }
+        Command::SignTx {
+            tx,
+            account,
+            network_id,
+            inner,
+            recipient_name,
+        } => {
+            let tx = parse_tx(&tx)?;
+            let request = SignTx {
+                account_number: account,
+                network_id: &network_id,
+                inner_tx: inner,
+                recipient_name: recipient_name.as_deref(),
+                tx: &tx,
+            };
+            // The app only computes the hash of transactions it can keep
+            let (signature, tx_hash) = if tx.len() <= MAX_SIGNED_TX_LEN {
+                let signature = connect()?.sign_tx_with_hash(request)?;
+                (signature.signature, Some(signature.tx_hash))
+            } else {
+                (connect()?.sign_tx(&request)?, None)
+            };
+            println!("{}", encode_signature(&signature));
+            let signed_tx = encode_signed_tx(&[signature.0], &tx);
+            println!(
+                "{}",
+                to_ae_base64_string(&signed_tx, AeEncoding::Transaction)
+            );
+            if let Some(tx_hash) = tx_hash {
+                println!("{}", tx_hash);
+            }
+        }
+        Command::SignTxBatch {
+            txs,
+            account,
+            network_id,
+        } => {
+            let txs = txs
+                .iter()
+                .map(|tx| parse_tx(tx))
+                .collect::<Result<Vec<_>, _>>()?;
+            let tx_refs = txs.iter().map(Vec::as_slice).collect::<Vec<_>>();
+            let signatures = connect()?.sign_tx_batch(&SignTxBatch {
+                account_number: account,
+                network_id: &network_id,
+                txs: &tx_refs,
+            })?;
+            for (signature, tx) in signatures.iter().zip(&txs) {
+                let signed_tx = encode_signed_tx(&[signature.0], tx);
+                println!(
+                    "{}",
+                    to_ae_base64_string(&signed_tx, AeEncoding::Transaction)
+                );
+            }
+        }
+        Command::SignMsg { message, account } => {
+            let signature = connect()?.sign_msg(account, message.as_bytes())?;
+            println!("{}", encode_signature(&signature));
+        }
+        Command::SignData {
+            data,
+            account,
+            hash,
+        } => {
+            let data = parse_hex(&data)?;
+            let signature = connect()?.sign_data(account, &data, hash)?;
+            println!("{}", encode_signature(&signature));
+        }
+    }
+
+    Ok(())
+}
+
+/// Decode a transaction given as `tx_...` or as RLP in hex.
+fn parse_tx(input: &str) -> Result<Vec<u8>, String> {
+    if input.starts_with("tx_") {
+        from_ae_string(input, AeEncoding::Transaction)
+            .ok_or_else(|| "invalid tx_ string".to_owned())
+    } else {
+        parse_hex(input)
+    }
+}
+
+fn parse_hex(input: &str) -> Result<Vec<u8>, String> {
+    let input = input.strip_prefix("0x").unwrap_or(input);
+    hex::decode(input).map_err(|err| format!("invalid hex: {}", err))
+}
+
+fn print_configuration(config: &AppConfiguration) {
+    let (major, minor, patch) = config.version;
+    println!("name: {}", config.name);
+    println!("version: {}.{}.{}", major, minor, patch);
+    println!("model: {}", config.model);
+    println!(
+        "blind signing: {}",
+        config.is_enabled(SETTING_BLIND_SIGNING)
+    );
+    println!("expert mode: {}", config.is_enabled(SETTING_EXPERT_MODE));
+    println!("features: {:#06x}", config.features);
+    println!("max message length: {}", config.max_message_len);
+    println!("max raw data length: {}", config.max_raw_data_len);
+    println!("max typed data length: {}", config.max_typed_data_len);
+    let tags = config
+        .tx_tags
+        .iter()
+        .map(|tag| format!("{:#04x}", tag))
+        .collect::<Vec<_>>();
+    println!("tx tags: {}", tags.join(", "));
+}
+
+fn encode_signature(signature: &Signature) -> String {
+    to_ae_string(&signature.0, AeEncoding::Signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn cli() {
+        Cli::command().debug_assert();
+
+        let cli = Cli::try_parse_from([
+            "aeternity-ledger",
+            "sign-tx",
+            "0xc20c01",
+            "--account",
+            "2",
+            "--network-id",
+            "ae_uat",
+        ])
+        .unwrap();
+        assert!(matches!(
+            cli.command,
+            Command::SignTx { account: 2, ref network_id, inner: false, .. } if network_id == "ae_uat"
+        ));
+    }
+
+    #[test]
+    fn tx_inputs() {
+        let rlp = [0xc2, 0x0c, 0x01];
+        let encoded = to_ae_base64_string(&rlp, AeEncoding::Transaction);
+        assert_eq!(parse_tx(&encoded).unwrap(), rlp);
+        assert_eq!(parse_tx("c20c01").unwrap(), rlp);
+        assert_eq!(parse_tx("0xc20c01").unwrap(), rlp);
+
+        assert!(parse_tx("tx_AAAA").is_err());
+        assert!(parse_tx("c20c0").is_err());
+    }
+
+    #[test]
+    fn signature_encoding() {
+        let encoded = encode_signature(&Signature([0; 64]));
+        assert!(encoded.starts_with("sg_"));
+        assert_eq!(
+            from_ae_string(&encoded, AeEncoding::Signature),
+            Some(vec![0; 64])
+        );
+    }
+}
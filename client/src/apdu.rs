@@ -0,0 +1,99 @@
+//! APDUs exchanged with the app.
+
+use app_aeternity::instruction::CLA;
+use app_aeternity::AppSW;
+
+use crate::Error;
+
+/// Maximum length of the data of an APDU.
+pub const MAX_DATA_LEN: usize = 255;
+
+/// Command APDU sent to the app, with the class of the app.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Command {
+    pub ins: u8,
+    pub p1: u8,
+    pub p2: u8,
+    pub data: Vec<u8>,
+}
+
+impl Command {
+    pub fn new(ins: u8, p1: u8, p2: u8, data: Vec<u8>) -> Self {
+        Command { ins, p1, p2, data }
+    }
+
+    /// Serialize the command, failing if its data does not fit in an APDU.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let len = u8::try_from(self.data.len())
+            .map_err(|_| Error::InvalidRequest("APDU data longer than 255 bytes"))?;
+        Ok([&[CLA, self.ins, self.p1, self.p2, len][..], &self.data].concat())
+    }
+}
+
+/// Response APDU of the app: its data followed by a status word.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Response {
+    pub data: Vec<u8>,
+    pub sw: u16,
+}
+
+impl Response {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let (data, sw) = bytes
+            .split_last_chunk::<2>()
+            .ok_or(Error::InvalidResponse("response without a status word"))?;
+        Ok(Response {
+            data: data.to_vec(),
+            sw: u16::from_be_bytes(*sw),
+        })
+    }
+
+    /// The data if the app succeeded, or its status word otherwise.
+    pub fn into_result(self) -> Result<Vec<u8>, Error> {
+        match AppSW::try_from(self.sw) {
+            Ok(AppSW::Ok) => Ok(self.data),
+            Ok(sw) => Err(Error::App(sw)),
+            Err(sw) => Err(Error::UnknownStatus(sw)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_bytes() {
+        let command = Command::new(0x02, 0x01, 0x00, vec![0, 0, 0, 5]);
+        assert_eq!(
+            command.to_bytes().unwrap(),
+            [0xe0, 0x02, 0x01, 0x00, 0x04, 0, 0, 0, 5]
+        );
+
+        let command = Command::new(0x04, 0x00, 0x00, vec![0; MAX_DATA_LEN + 1]);
+        assert!(matches!(command.to_bytes(), Err(Error::InvalidRequest(_))));
+    }
+
+    #[test]
+    fn response_status() {
+        let response = Response::from_bytes(&[1, 2, 0x90, 0x00]).unwrap();
+        assert_eq!(response.into_result().unwrap(), [1, 2]);
+
+        let response = Response::from_bytes(&[0x69, 0x85]).unwrap();
+        assert!(matches!(
+            response.into_result(),
+            Err(Error::App(AppSW::Deny))
+        ));
+
+        let response = Response::from_bytes(&[0x12, 0x34]).unwrap();
+        assert!(matches!(
+            response.into_result(),
+            Err(Error::UnknownStatus(0x1234))
+        ));
+
+        assert!(matches!(
+            Response::from_bytes(&[0x90]),
+            Err(Error::InvalidResponse(_))
+        ));
+    }
+}
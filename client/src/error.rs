@@ -0,0 +1,46 @@
+use std::{fmt, io};
+
+use app_aeternity::AppSW;
+
+/// Errors of the client.
+#[derive(Debug)]
+pub enum Error {
+    /// The transport failed to exchange an APDU
+    Transport(io::Error),
+    /// The app answered with one of its status words
+    App(AppSW),
+    /// The app answered with a status word that it does not define
+    UnknownStatus(u16),
+    /// The request cannot be encoded in the APDUs of the app
+    InvalidRequest(&'static str),
+    /// The response of the app cannot be decoded
+    InvalidResponse(&'static str),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Transport(err) => write!(f, "transport error: {}", err),
+            Error::App(sw) => write!(f, "app error: {}", sw),
+            Error::UnknownStatus(sw) => write!(f, "unknown status word 0x{:04X}", sw),
+            Error::InvalidRequest(reason) => write!(f, "invalid request: {}", reason),
+            Error::InvalidResponse(reason) => write!(f, "invalid response: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Transport(err) => Some(err),
+            Error::App(sw) => Some(sw),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Self {
+        Error::Transport(err)
+    }
+}
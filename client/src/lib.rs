@@ -0,0 +1,258 @@
+//! Host client for the APDU protocol of the Aeternity Ledger app.
+//!
+//! Requests are encoded into the APDUs expected by the app and sent through a
+//! [`Transport`], such as [`TcpTransport`] to talk to Speculos. Failures of
+//! the app are decoded into the same [`AppSW`] status words that the app
+//! uses.
+//!
+//! ```no_run
+//! use app_aeternity_client::{Client, TcpTransport, SPECULOS_APDU_PORT};
+//!
+//! let transport = TcpTransport::connect(("127.0.0.1", SPECULOS_APDU_PORT))?;
+//! let mut client = Client::new(transport);
+//! println!("{}", client.get_address(0, false)?);
+//! # Ok::<(), Box<dyn std::error::Error>>(())
+//! ```
+
+pub mod apdu;
+mod error;
+pub mod requests;
+pub mod transport;
+
+pub use app_aeternity::config::AppConfiguration;
+pub use app_aeternity::AppSW;
+pub use error::Error;
+pub use requests::{
+    Abort, GetAddress, GetAppConfiguration, GetSignedTx, GetVersion, ProvideNetwork,
+    RegisterAddress, Request, SignData, SignMsg, SignTx, SignTxBatch, SignTxWithHash, Signature,
+    SignedTx, TxHash, TxSignature, Version,
+};
+pub use transport::{TcpTransport, Transport, SPECULOS_APDU_PORT};
+
+use apdu::Response;
+
+/// Client of the app, over any transport.
+pub struct Client<T> {
+    transport: T,
+}
+
+impl<T: Transport> Client<T> {
+    pub fn new(transport: T) -> Self {
+        Client { transport }
+    }
+
+    /// Send all the APDUs of a request, stopping at the first failure, and
+    /// decode the response to the last one.
+    pub fn send<R: Request>(&mut self, request: &R) -> Result<R::Response, Error> {
+        let mut data = Vec::new();
+        for command in request.commands()? {
+            let response = self.transport.exchange(&command.to_bytes()?)?;
+            data = Response::from_bytes(&response)?.into_result()?;
+        }
+        while let Some(command) = request.next_command(&data) {
+            let response = self.transport.exchange(&command.to_bytes()?)?;
+            data.extend(Response::from_bytes(&response)?.into_result()?);
+        }
+        request.parse_response(&data)
+    }
+
+    pub fn get_version(&mut self) -> Result<Version, Error> {
+        self.send(&GetVersion)
+    }
+
+    pub fn get_app_configuration(&mut self) -> Result<AppConfiguration, Error> {
+        self.send(&GetAppConfiguration)
+    }
+
+    pub fn get_address(&mut self, account_number: u32, confirm: bool) -> Result<String, Error> {
+        self.send(&GetAddress {
+            account_number,
+            confirm,
+        })
+    }
+
+    pub fn sign_tx(&mut self, request: &SignTx) -> Result<Signature, Error> {
+        self.send(request)
+    }
+
+    /// Sign a transaction, and get the signature with the hash of the
+    /// SignedTx computed by the app.
+    pub fn sign_tx_with_hash(&mut self, request: SignTx) -> Result<TxSignature, Error> {
+        self.send(&SignTxWithHash(request))
+    }
+
+    /// Sign a transaction, and get the RLP-encoded SignedTx built by the app,
+    /// with its hash.
+    pub fn get_signed_tx(&mut self, request: SignTx) -> Result<SignedTx, Error> {
+        self.send(&GetSignedTx(request))
+    }
+
+    /// Sign a batch of SpendTx after a single review, and get one signature
+    /// per transaction.
+    pub fn sign_tx_batch(&mut self, request: &SignTxBatch) -> Result<Vec<Signature>, Error> {
+        self.send(request)
+    }
+
+    /// Add an address to the address book, after the user confirmed it.
+    pub fn register_address(&mut self, label: &str, address: &str) -> Result<(), Error> {
+        self.send(&RegisterAddress { label, address })
+    }
+
+    pub fn abort(&mut self) -> Result<(), Error> {
+        self.send(&Abort)
+    }
+
+    pub fn provide_network(&mut self, descriptor: &[u8]) -> Result<(), Error> {
+        self.send(&ProvideNetwork { descriptor })
+    }
+
+    pub fn sign_msg(&mut self, account_number: u32, message: &[u8]) -> Result<Signature, Error> {
+        self.send(&SignMsg {
+            account_number,
+            message,
+        })
+    }
+
+    pub fn sign_data(
+        &mut self,
+        account_number: u32,
+        data: &[u8],
+        hash_mode: bool,
+    ) -> Result<Signature, Error> {
+        self.send(&SignData {
+            account_number,
+            data,
+            hash_mode,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use std::io;
+
+    /// Transport answering with canned responses, and keeping the commands.
+    #[derive(Default)]
+    struct MockTransport {
+        responses: VecDeque<Vec<u8>>,
+        commands: Vec<Vec<u8>>,
+    }
+
+    impl Transport for MockTransport {
+        fn exchange(&mut self, command: &[u8]) -> io::Result<Vec<u8>> {
+            self.commands.push(command.to_vec());
+            self.responses
+                .pop_front()
+                .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))
+        }
+    }
+
+    fn client(responses: &[&[u8]]) -> Client<MockTransport> {
+        Client::new(MockTransport {
+            responses: responses.iter().map(|r| r.to_vec()).collect(),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn get_version() {
+        let mut client = client(&[&[1, 2, 3, 0x90, 0x00]]);
+        let version = client.get_version().unwrap();
+        assert_eq!(version.to_string(), "1.2.3");
+        assert_eq!(client.transport.commands, [[0xe0, 0x06, 0x00, 0x00, 0x00]]);
+    }
+
+    #[test]
+    fn get_app_configuration() {
+        let config = AppConfiguration::new("flex", 0);
+        let mut client = client(&[&[&config.encode()[..], &[0x90, 0x00]].concat()]);
+        assert_eq!(client.get_app_configuration().unwrap(), config);
+        assert_eq!(client.transport.commands, [[0xe0, 0x10, 0x00, 0x00, 0x00]]);
+    }
+
+    #[test]
+    fn sign_tx_in_chunks() {
+        let mut client = client(&[&[0x90, 0x00], &[&[5; 64][..], &[0x90, 0x00]].concat()]);
+        let tx = [0; 300];
+        let signature = client
+            .sign_tx(&SignTx {
+                account_number: 0,
+                network_id: "ae_mainnet",
+                inner_tx: false,
+                recipient_name: None,
+                tx: &tx,
+            })
+            .unwrap();
+        assert_eq!(signature, Signature([5; 64]));
+        assert_eq!(client.transport.commands.len(), 2);
+        assert_eq!(client.transport.commands[1][2], 0x80);
+    }
+
+    #[test]
+    fn signed_tx_in_parts() {
+        let signed_tx = [9; 300];
+        let first = [&[0x01, 0x4c][..], &signed_tx[..253], &[0x90, 0x00]].concat();
+        let second = [&signed_tx[253..], &[1; 32], &[0x90, 0x00][..]].concat();
+        let mut client = client(&[&first, &second]);
+
+        let received = client
+            .get_signed_tx(SignTx {
+                account_number: 0,
+                network_id: "ae_mainnet",
+                inner_tx: false,
+                recipient_name: None,
+                tx: &[0xc2, 0x0c, 0x01],
+            })
+            .unwrap();
+        assert_eq!(received.signed_tx, signed_tx);
+        assert_eq!(received.tx_hash, TxHash([1; 32]));
+        assert_eq!(client.transport.commands[1], [0xe0, 0x04, 0x01, 0x00, 0x00]);
+    }
+
+    #[test]
+    fn stops_at_first_error() {
+        let mut client = client(&[&[0xb0, 0x10], &[0x90, 0x00]]);
+        let tx = [0; 300];
+        let result = client.sign_tx(&SignTx {
+            account_number: 0,
+            network_id: "ae_mainnet",
+            inner_tx: false,
+            recipient_name: None,
+            tx: &tx,
+        });
+        assert!(matches!(result, Err(Error::App(AppSW::TxUnsupportedTag))));
+        assert_eq!(client.transport.commands.len(), 1);
+    }
+
+    #[test]
+    fn register_address() {
+        let mut client = client(&[&[0x90, 0x00]]);
+        client.register_address("Ops", "ak_x").unwrap();
+        assert_eq!(
+            client.transport.commands,
+            [b"\xe0\x12\x00\x00\x09\x03Ops\x04ak_x"]
+        );
+    }
+
+    #[test]
+    fn abort() {
+        let mut client = client(&[&[0x90, 0x00]]);
+        client.abort().unwrap();
+        assert_eq!(client.transport.commands, [b"\xe0\x16\x00\x00\x00"]);
+    }
+
+    #[test]
+    fn provide_network() {
+        let mut client = client(&[&[0x90, 0x00]]);
+        client.provide_network(b"\x02hc").unwrap();
+        assert_eq!(client.transport.commands, [b"\xe0\x14\x00\x00\x03\x02hc"]);
+    }
+
+    #[test]
+    fn transport_error() {
+        let mut client = client(&[]);
+        assert!(matches!(client.get_version(), Err(Error::Transport(_))));
+    }
+}
@@ -0,0 +1,765 @@
+//! Requests to the app, with the APDUs that carry them and their responses.
+
+use std::fmt;
+
+use app_aeternity::batch::MAX_BATCH_LEN;
+use app_aeternity::config::AppConfiguration;
+use app_aeternity::encoding::{to_ae_string, AeEncoding};
+use app_aeternity::instruction::{
+    INS_ABORT, INS_GET_ADDRESS, INS_GET_APP_CONFIGURATION, INS_GET_VERSION, INS_PROVIDE_NETWORK,
+    INS_REGISTER_ADDRESS, INS_SIGN_DATA, INS_SIGN_MSG, INS_SIGN_TX, INS_SIGN_TX_BATCH,
+    P1_CONFIRM_NEEDED, P1_CONFIRM_NOT_NEEDED, P1_SIGN_TX_MORE, P1_SIGN_TX_NEXT_RESPONSE,
+    P1_SIGN_TX_START, P2_SIGN_DATA_HASH, P2_SIGN_DATA_RAW, P2_SIGN_TX_SIGNATURE,
+    P2_SIGN_TX_SIGNATURE_AND_HASH, P2_SIGN_TX_SIGNED_TX,
+};
+use app_aeternity::requests::{
+    printable_str, INNER_TX_TAG, NETWORK_ID_MAX_LENGTH, NON_INNER_TX_TAG, RECIPIENT_NAME_FLAG,
+};
+
+use crate::apdu::{Command, MAX_DATA_LEN};
+use crate::Error;
+
+/// A request, sent in one or more APDUs. Only the response to the last APDU
+/// carries data, which may be continued in the responses to more APDUs.
+pub trait Request {
+    type Response;
+
+    fn commands(&self) -> Result<Vec<Command>, Error>;
+
+    /// APDU to get the next part of the response, given the data received so
+    /// far, if it is not complete.
+    fn next_command(&self, _received: &[u8]) -> Option<Command> {
+        None
+    }
+
+    fn parse_response(&self, data: &[u8]) -> Result<Self::Response, Error>;
+}
+
+/// Version of the app.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Version {
+    pub major: u8,
+    pub minor: u8,
+    pub patch: u8,
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Ed25519 signature made by the app.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Signature(pub [u8; 64]);
+
+impl Signature {
+    fn parse(data: &[u8]) -> Result<Self, Error> {
+        data.try_into()
+            .map(Signature)
+            .map_err(|_| Error::InvalidResponse("signature is not 64 bytes long"))
+    }
+}
+
+/// Hash of a SignedTx, which identifies it on the chain.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TxHash(pub [u8; 32]);
+
+/// The hash as `th_...`, as shown by explorers.
+impl fmt::Display for TxHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&to_ae_string(&self.0, AeEncoding::TxHash))
+    }
+}
+
+/// Signature of a transaction, with the hash of the SignedTx computed by the
+/// app.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TxSignature {
+    pub signature: Signature,
+    pub tx_hash: TxHash,
+}
+
+/// RLP-encoded SignedTx built by the app, with its hash.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SignedTx {
+    pub signed_tx: Vec<u8>,
+    pub tx_hash: TxHash,
+}
+
+/// Get the version of the app.
+pub struct GetVersion;
+
+impl Request for GetVersion {
+    type Response = Version;
+
+    fn commands(&self) -> Result<Vec<Command>, Error> {
+        Ok(vec![Command::new(INS_GET_VERSION, 0, 0, Vec::new())])
+    }
+
+    fn parse_response(&self, data: &[u8]) -> Result<Version, Error> {
+        match *data {
+            [major, minor, patch] => Ok(Version {
+                major,
+                minor,
+                patch,
+            }),
+            _ => Err(Error::InvalidResponse("version is not 3 bytes long")),
+        }
+    }
+}
+
+/// Get the configuration of the app: its version, settings, limits and
+/// supported features.
+pub struct GetAppConfiguration;
+
+impl Request for GetAppConfiguration {
+    type Response = AppConfiguration;
+
+    fn commands(&self) -> Result<Vec<Command>, Error> {
+        Ok(vec![Command::new(
+            INS_GET_APP_CONFIGURATION,
+            0,
+            0,
+            Vec::new(),
+        )])
+    }
+
+    fn parse_response(&self, data: &[u8]) -> Result<AppConfiguration, Error> {
+        AppConfiguration::decode(data).ok_or(Error::InvalidResponse("invalid configuration"))
+    }
+}
+
+/// Get the `ak_` address of an account, optionally after showing it on the
+/// screen for the user to verify.
+pub struct GetAddress {
+    pub account_number: u32,
+    pub confirm: bool,
+}
+
+impl Request for GetAddress {
+    type Response = String;
+
+    fn commands(&self) -> Result<Vec<Command>, Error> {
+        let p1 = if self.confirm {
+            P1_CONFIRM_NEEDED
+        } else {
+            P1_CONFIRM_NOT_NEEDED
+        };
+        let data = self.account_number.to_be_bytes().to_vec();
+        Ok(vec![Command::new(INS_GET_ADDRESS, p1, 0, data)])
+    }
+
+    fn parse_response(&self, data: &[u8]) -> Result<String, Error> {
+        let address = match data.split_first() {
+            Some((len, address)) if address.len() == usize::from(*len) => address,
+            _ => return Err(Error::InvalidResponse("address length mismatch")),
+        };
+        printable_str(address)
+            .map(str::to_owned)
+            .ok_or(Error::InvalidResponse("address is not printable"))
+    }
+}
+
+/// Sign an RLP-encoded transaction for a network.
+pub struct SignTx<'a> {
+    pub account_number: u32,
+    pub network_id: &'a str,
+    /// Whether the transaction is an inner transaction of a paying-for
+    /// transaction
+    pub inner_tx: bool,
+    /// AENS name of an `nm_` recipient, which the app checks and shows
+    /// instead of the name hash
+    pub recipient_name: Option<&'a str>,
+    pub tx: &'a [u8],
+}
+
+impl SignTx<'_> {
+    fn commands_with_p2(&self, p2: u8) -> Result<Vec<Command>, Error> {
+        if self.network_id.is_empty() || self.network_id.len() > NETWORK_ID_MAX_LENGTH {
+            return Err(Error::InvalidRequest("invalid network id length"));
+        }
+        let tx_len = u32::try_from(self.tx.len())
+            .map_err(|_| Error::InvalidRequest("transaction too long"))?;
+        let mut flags = if self.inner_tx {
+            INNER_TX_TAG
+        } else {
+            NON_INNER_TX_TAG
+        };
+        if self.recipient_name.is_some() {
+            flags |= RECIPIENT_NAME_FLAG;
+        }
+
+        let mut header = [
+            &self.account_number.to_be_bytes()[..],
+            &tx_len.to_be_bytes(),
+            &[flags, self.network_id.len() as u8],
+            self.network_id.as_bytes(),
+        ]
+        .concat();
+        if let Some(name) = self.recipient_name {
+            let len = u8::try_from(name.len())
+                .map_err(|_| Error::InvalidRequest("recipient name too long"))?;
+            header.push(len);
+            header.extend_from_slice(name.as_bytes());
+        }
+        Ok(chunked(INS_SIGN_TX, p2, header, self.tx))
+    }
+}
+
+impl Request for SignTx<'_> {
+    type Response = Signature;
+
+    fn commands(&self) -> Result<Vec<Command>, Error> {
+        self.commands_with_p2(P2_SIGN_TX_SIGNATURE)
+    }
+
+    fn parse_response(&self, data: &[u8]) -> Result<Signature, Error> {
+        Signature::parse(data)
+    }
+}
+
+/// Sign a transaction, and get the signature followed by the hash of the
+/// SignedTx.
+///
+/// The app keeps the transaction until it is signed to compute the hash, so
+/// it refuses transactions longer than
+/// [`MAX_SIGNED_TX_LEN`](app_aeternity::tx::MAX_SIGNED_TX_LEN), which can
+/// only be signed with [`SignTx`].
+pub struct SignTxWithHash<'a>(pub SignTx<'a>);
+
+impl Request for SignTxWithHash<'_> {
+    type Response = TxSignature;
+
+    fn commands(&self) -> Result<Vec<Command>, Error> {
+        self.0.commands_with_p2(P2_SIGN_TX_SIGNATURE_AND_HASH)
+    }
+
+    fn parse_response(&self, data: &[u8]) -> Result<TxSignature, Error> {
+        if data.len() != 64 + 32 {
+            return Err(Error::InvalidResponse(
+                "signature and transaction hash are not 96 bytes long",
+            ));
+        }
+        let (signature, tx_hash) = data.split_at(64);
+        Ok(TxSignature {
+            signature: Signature::parse(signature)?,
+            tx_hash: TxHash(tx_hash.try_into().expect("hash is 32 bytes long")),
+        })
+    }
+}
+
+/// Sign a transaction, and get the RLP-encoded SignedTx built by the app,
+/// followed by its hash.
+///
+/// The first response starts with the length of the SignedTx and its hash on
+/// 2 bytes, and the rest is fetched with as many APDUs as needed. The app keeps the
+/// transaction until it is signed, so it refuses transactions longer than
+/// [`MAX_SIGNED_TX_LEN`](app_aeternity::tx::MAX_SIGNED_TX_LEN), which can
+/// only be signed with [`SignTx`].
+pub struct GetSignedTx<'a>(pub SignTx<'a>);
+
+impl Request for GetSignedTx<'_> {
+    type Response = SignedTx;
+
+    fn commands(&self) -> Result<Vec<Command>, Error> {
+        self.0.commands_with_p2(P2_SIGN_TX_SIGNED_TX)
+    }
+
+    fn next_command(&self, received: &[u8]) -> Option<Command> {
+        next_response_part(received)
+    }
+
+    fn parse_response(&self, data: &[u8]) -> Result<SignedTx, Error> {
+        match response_len(data) {
+            Some(len) if len == data.len() && len >= 2 + 32 => {
+                let (signed_tx, tx_hash) = data[2..].split_at(len - 2 - 32);
+                Ok(SignedTx {
+                    signed_tx: signed_tx.to_vec(),
+                    tx_hash: TxHash(tx_hash.try_into().expect("hash is 32 bytes long")),
+                })
+            }
+            _ => Err(Error::InvalidResponse("signed transaction length mismatch")),
+        }
+    }
+}
+
+/// Sign a batch of SpendTx from the same account, after a single review of
+/// all of them, and get their signatures in the same order.
+///
+/// Like with [`GetSignedTx`], the signatures may not fit in one response, and
+/// the rest is fetched with as many APDUs as needed.
+pub struct SignTxBatch<'a> {
+    pub account_number: u32,
+    pub network_id: &'a str,
+    pub txs: &'a [&'a [u8]],
+}
+
+impl Request for SignTxBatch<'_> {
+    type Response = Vec<Signature>;
+
+    fn commands(&self) -> Result<Vec<Command>, Error> {
+        if self.network_id.is_empty() || self.network_id.len() > NETWORK_ID_MAX_LENGTH {
+            return Err(Error::InvalidRequest("invalid network id length"));
+        }
+        if self.txs.is_empty() || self.txs.len() > MAX_BATCH_LEN {
+            return Err(Error::InvalidRequest("invalid number of transactions"));
+        }
+
+        let header = [
+            &self.account_number.to_be_bytes()[..],
+            &[self.txs.len() as u8, self.network_id.len() as u8],
+            self.network_id.as_bytes(),
+        ]
+        .concat();
+        let mut commands = vec![Command::new(INS_SIGN_TX_BATCH, P1_SIGN_TX_START, 0, header)];
+        // Each transaction starts in a new APDU, with its length
+        for tx in self.txs {
+            let tx_len = u32::try_from(tx.len())
+                .map_err(|_| Error::InvalidRequest("transaction too long"))?;
+            let data = [&tx_len.to_be_bytes()[..], tx].concat();
+            commands.extend(
+                data.chunks(MAX_DATA_LEN).map(|chunk| {
+                    Command::new(INS_SIGN_TX_BATCH, P1_SIGN_TX_MORE, 0, chunk.to_vec())
+                }),
+            );
+        }
+        Ok(commands)
+    }
+
+    fn next_command(&self, received: &[u8]) -> Option<Command> {
+        next_response_part(received)
+    }
+
+    fn parse_response(&self, data: &[u8]) -> Result<Vec<Signature>, Error> {
+        match response_len(data) {
+            Some(len) if len == data.len() && len == 2 + 64 * self.txs.len() => {
+                data[2..].chunks(64).map(Signature::parse).collect()
+            }
+            _ => Err(Error::InvalidResponse("batch signatures length mismatch")),
+        }
+    }
+}
+
+/// Sign a message, which must fit in a single APDU.
+pub struct SignMsg<'a> {
+    pub account_number: u32,
+    pub message: &'a [u8],
+}
+
+impl Request for SignMsg<'_> {
+    type Response = Signature;
+
+    fn commands(&self) -> Result<Vec<Command>, Error> {
+        let data = [
+            &self.account_number.to_be_bytes()[..],
+            &(self.message.len() as u32).to_be_bytes(),
+            self.message,
+        ]
+        .concat();
+        if data.len() > MAX_DATA_LEN {
+            return Err(Error::InvalidRequest("message too long"));
+        }
+        Ok(vec![Command::new(INS_SIGN_MSG, 0, 0, data)])
+    }
+
+    fn parse_response(&self, data: &[u8]) -> Result<Signature, Error> {
+        Signature::parse(data)
+    }
+}
+
+/// Add an `ak_` or `ct_` address to the address book of the app, with a
+/// label shown when it is the recipient of a transaction. The user must
+/// confirm it on the device.
+pub struct RegisterAddress<'a> {
+    pub label: &'a str,
+    pub address: &'a str,
+}
+
+impl Request for RegisterAddress<'_> {
+    type Response = ();
+
+    fn commands(&self) -> Result<Vec<Command>, Error> {
+        let mut data = Vec::new();
+        for field in [self.label, self.address] {
+            let len = u8::try_from(field.len())
+                .map_err(|_| Error::InvalidRequest("label or address too long"))?;
+            data.push(len);
+            data.extend_from_slice(field.as_bytes());
+        }
+        Ok(vec![Command::new(INS_REGISTER_ADDRESS, 0, 0, data)])
+    }
+
+    fn parse_response(&self, data: &[u8]) -> Result<(), Error> {
+        if data.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::InvalidResponse("unexpected data"))
+        }
+    }
+}
+
+/// Make the app forget a SignTx, SignData or SignTypedData whose chunks were
+/// not all sent, for example after an error of the host.
+pub struct Abort;
+
+impl Request for Abort {
+    type Response = ();
+
+    fn commands(&self) -> Result<Vec<Command>, Error> {
+        Ok(vec![Command::new(INS_ABORT, 0, 0, Vec::new())])
+    }
+
+    fn parse_response(&self, data: &[u8]) -> Result<(), Error> {
+        if data.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::InvalidResponse("unexpected data"))
+        }
+    }
+}
+
+/// Describe a network that the app does not know, like a Hyperchain, so that
+/// the amounts of its transactions are shown in its token. The descriptor is
+/// sent as is, and the app refuses it unless it is signed by its trusted key.
+pub struct ProvideNetwork<'a> {
+    pub descriptor: &'a [u8],
+}
+
+impl Request for ProvideNetwork<'_> {
+    type Response = ();
+
+    fn commands(&self) -> Result<Vec<Command>, Error> {
+        if self.descriptor.len() > MAX_DATA_LEN {
+            return Err(Error::InvalidRequest("descriptor too long"));
+        }
+        Ok(vec![Command::new(
+            INS_PROVIDE_NETWORK,
+            0,
+            0,
+            self.descriptor.to_vec(),
+        )])
+    }
+
+    fn parse_response(&self, data: &[u8]) -> Result<(), Error> {
+        if data.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::InvalidResponse("unexpected data"))
+        }
+    }
+}
+
+/// Sign arbitrary data, either as is or as its Blake2b-256 hash. The app
+/// refuses it unless blind signing is enabled in its settings.
+pub struct SignData<'a> {
+    pub account_number: u32,
+    pub data: &'a [u8],
+    pub hash_mode: bool,
+}
+
+impl Request for SignData<'_> {
+    type Response = Signature;
+
+    fn commands(&self) -> Result<Vec<Command>, Error> {
+        let data_len =
+            u32::try_from(self.data.len()).map_err(|_| Error::InvalidRequest("data too long"))?;
+        let p2 = if self.hash_mode {
+            P2_SIGN_DATA_HASH
+        } else {
+            P2_SIGN_DATA_RAW
+        };
+
+        let header = [
+            &self.account_number.to_be_bytes()[..],
+            &data_len.to_be_bytes(),
+        ]
+        .concat();
+        Ok(chunked(INS_SIGN_DATA, p2, header, self.data))
+    }
+
+    fn parse_response(&self, data: &[u8]) -> Result<Signature, Error> {
+        Signature::parse(data)
+    }
+}
+
+/// Full length of a response sent in parts, given the first part, which
+/// starts with the length of the rest on 2 bytes.
+fn response_len(received: &[u8]) -> Option<usize> {
+    let (len, _) = received.split_first_chunk::<2>()?;
+    Some(2 + usize::from(u16::from_be_bytes(*len)))
+}
+
+/// APDU to get the next part of a response sent in parts, if it is not
+/// complete.
+fn next_response_part(received: &[u8]) -> Option<Command> {
+    (response_len(received)? > received.len())
+        .then(|| Command::new(INS_SIGN_TX, P1_SIGN_TX_NEXT_RESPONSE, 0, Vec::new()))
+}
+
+/// Split a header followed by a payload over as many APDUs as needed. The
+/// first one starts with the header, and all but the first one are marked as
+/// continuation chunks.
+fn chunked(ins: u8, p2: u8, header: Vec<u8>, payload: &[u8]) -> Vec<Command> {
+    let (first, rest) = payload.split_at(payload.len().min(MAX_DATA_LEN - header.len()));
+
+    let mut commands = vec![Command::new(
+        ins,
+        P1_SIGN_TX_START,
+        p2,
+        [&header, first].concat(),
+    )];
+    commands.extend(
+        rest.chunks(MAX_DATA_LEN)
+            .map(|chunk| Command::new(ins, P1_SIGN_TX_MORE, p2, chunk.to_vec())),
+    );
+    commands
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_address() {
+        let request = GetAddress {
+            account_number: 3,
+            confirm: true,
+        };
+        assert_eq!(
+            request.commands().unwrap(),
+            [Command::new(INS_GET_ADDRESS, 1, 0, vec![0, 0, 0, 3])]
+        );
+
+        let address = "ak_11111111111111111111111111111111273Yts";
+        let response = [&[address.len() as u8], address.as_bytes()].concat();
+        assert_eq!(request.parse_response(&response).unwrap(), address);
+        assert!(request.parse_response(&response[1..]).is_err());
+        assert!(request.parse_response(&[]).is_err());
+    }
+
+    #[test]
+    fn sign_tx_chunks() {
+        let tx = [7; 600];
+        let request = SignTx {
+            account_number: 1,
+            network_id: "ae_uat",
+            inner_tx: false,
+            recipient_name: None,
+            tx: &tx,
+        };
+        let commands = request.commands().unwrap();
+
+        let header = [
+            0, 0, 0, 1, 0, 0, 2, 0x58, 0, 6, b'a', b'e', b'_', b'u', b'a', b't',
+        ];
+        let first_len = MAX_DATA_LEN - header.len();
+        assert_eq!(commands.len(), 3);
+        assert_eq!(
+            commands[0],
+            Command::new(
+                INS_SIGN_TX,
+                P1_SIGN_TX_START,
+                0,
+                [&header[..], &tx[..first_len]].concat()
+            )
+        );
+        assert_eq!(commands[1].p1, P1_SIGN_TX_MORE);
+        assert_eq!(commands[1].data.len(), MAX_DATA_LEN);
+        assert_eq!(commands[2].data.len(), tx.len() - first_len - MAX_DATA_LEN);
+
+        let too_long_network_id = "a".repeat(NETWORK_ID_MAX_LENGTH + 1);
+        let request = SignTx {
+            network_id: &too_long_network_id,
+            ..request
+        };
+        assert!(matches!(request.commands(), Err(Error::InvalidRequest(_))));
+    }
+
+    #[test]
+    fn sign_tx_response() {
+        let request = SignTx {
+            account_number: 0,
+            network_id: "ae_mainnet",
+            inner_tx: false,
+            recipient_name: None,
+            tx: &[0xc2, 0x0c, 0x01],
+        };
+        let response = [[5; 64], [0; 64]].concat();
+        assert_eq!(
+            request.parse_response(&response[..64]).unwrap(),
+            Signature([5; 64])
+        );
+        assert!(request.parse_response(&response[..96]).is_err());
+
+        // The hash is only sent when asked for, and then always
+        let request = SignTxWithHash(request);
+        assert_eq!(
+            request.commands().unwrap()[0].p2,
+            P2_SIGN_TX_SIGNATURE_AND_HASH
+        );
+        assert_eq!(
+            request.parse_response(&response[..96]).unwrap(),
+            TxSignature {
+                signature: Signature([5; 64]),
+                tx_hash: TxHash([0; 32]),
+            }
+        );
+        assert_eq!(
+            TxHash([0; 32]).to_string(),
+            "th_11111111111111111111111111111111273Yts"
+        );
+        assert!(request.parse_response(&response[..64]).is_err());
+        assert!(request.parse_response(&response[..80]).is_err());
+    }
+
+    #[test]
+    fn sign_tx_with_recipient_name() {
+        let request = SignTx {
+            account_number: 0,
+            network_id: "ae",
+            inner_tx: true,
+            recipient_name: Some("alice.chain"),
+            tx: &[0xc2, 0x0c, 0x01],
+        };
+        let commands = request.commands().unwrap();
+        assert_eq!(
+            commands[0].data,
+            b"\0\0\0\0\0\0\0\x03\x81\x02ae\x0balice.chain\xc2\x0c\x01"
+        );
+    }
+
+    #[test]
+    fn get_signed_tx() {
+        let request = GetSignedTx(SignTx {
+            account_number: 0,
+            network_id: "ae_mainnet",
+            inner_tx: false,
+            recipient_name: None,
+            tx: &[0xc2, 0x0c, 0x01],
+        });
+        let commands = request.commands().unwrap();
+        assert_eq!(commands.len(), 1);
+        assert_eq!(commands[0].p2, P2_SIGN_TX_SIGNED_TX);
+
+        assert_eq!(
+            request.next_command(&[0x01, 0x00, 1, 2, 3]),
+            Some(Command::new(
+                INS_SIGN_TX,
+                P1_SIGN_TX_NEXT_RESPONSE,
+                0,
+                Vec::new()
+            ))
+        );
+        assert_eq!(request.next_command(&[0x00, 0x03, 1, 2, 3]), None);
+        let response = [&[0x00, 0x23, 1, 2, 3][..], &[4; 32]].concat();
+        assert_eq!(
+            request.parse_response(&response).unwrap(),
+            SignedTx {
+                signed_tx: vec![1, 2, 3],
+                tx_hash: TxHash([4; 32]),
+            }
+        );
+        assert!(request.parse_response(&response[..34]).is_err());
+        assert!(request.parse_response(&[0x00, 0x03, 1, 2, 3]).is_err());
+        assert!(request.parse_response(&[0x00]).is_err());
+    }
+
+    #[test]
+    fn sign_tx_batch() {
+        let long_tx = [7; 300];
+        let request = SignTxBatch {
+            account_number: 1,
+            network_id: "ae_uat",
+            txs: &[&[0xc2, 0x0c, 0x01], &long_tx],
+        };
+        let commands = request.commands().unwrap();
+        assert_eq!(commands.len(), 4);
+        assert_eq!(
+            commands[0],
+            Command::new(
+                INS_SIGN_TX_BATCH,
+                P1_SIGN_TX_START,
+                0,
+                b"\0\0\0\x01\x02\x06ae_uat".to_vec()
+            )
+        );
+        assert_eq!(commands[1].p1, P1_SIGN_TX_MORE);
+        assert_eq!(commands[1].data, [0, 0, 0, 3, 0xc2, 0x0c, 0x01]);
+        assert_eq!(commands[2].data[..4], [0, 0, 0x01, 0x2c]);
+        assert_eq!(commands[3].data.len(), 4 + long_tx.len() - MAX_DATA_LEN);
+
+        let response = [&[0x00, 0x80][..], &[1; 64], &[2; 64]].concat();
+        assert_eq!(
+            request.next_command(&response[..100]).unwrap().p1,
+            P1_SIGN_TX_NEXT_RESPONSE
+        );
+        assert_eq!(request.next_command(&response), None);
+        assert_eq!(
+            request.parse_response(&response).unwrap(),
+            [Signature([1; 64]), Signature([2; 64])]
+        );
+        assert!(request.parse_response(&response[..66]).is_err());
+
+        let empty = SignTxBatch {
+            txs: &[],
+            ..request
+        };
+        assert!(matches!(empty.commands(), Err(Error::InvalidRequest(_))));
+    }
+
+    #[test]
+    fn sign_msg() {
+        let request = SignMsg {
+            account_number: 0,
+            message: b"hello",
+        };
+        assert_eq!(
+            request.commands().unwrap(),
+            [Command::new(
+                INS_SIGN_MSG,
+                0,
+                0,
+                vec![0, 0, 0, 0, 0, 0, 0, 5, b'h', b'e', b'l', b'l', b'o']
+            )]
+        );
+        assert_eq!(
+            request.parse_response(&[9; 64]).unwrap(),
+            Signature([9; 64])
+        );
+        assert!(request.parse_response(&[9; 63]).is_err());
+
+        let long_message = [b'a'; MAX_DATA_LEN];
+        let request = SignMsg {
+            account_number: 0,
+            message: &long_message,
+        };
+        assert!(request.commands().is_err());
+    }
+
+    #[test]
+    fn sign_data_chunks() {
+        let data = [1; 300];
+        let request = SignData {
+            account_number: 0,
+            data: &data,
+            hash_mode: true,
+        };
+        let commands = request.commands().unwrap();
+        assert_eq!(commands.len(), 2);
+        assert!(commands.iter().all(|c| c.p2 == P2_SIGN_DATA_HASH));
+        assert_eq!(&commands[0].data[..8], [0, 0, 0, 0, 0, 0, 1, 0x2c]);
+        assert_eq!(commands[0].data.len() + commands[1].data.len(), 8 + 300);
+
+        // Empty data still needs a first chunk
+        let request = SignData {
+            account_number: 0,
+            data: &[],
+            hash_mode: false,
+        };
+        assert_eq!(
+            request.commands().unwrap(),
+            [Command::new(INS_SIGN_DATA, P1_SIGN_TX_START, 0, vec![0; 8])]
+        );
+    }
+}
@@ -0,0 +1,90 @@
+//! Ways to exchange APDUs with the app.
+
+use std::io::{self, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+
+/// Default port of the APDU server of Speculos.
+pub const SPECULOS_APDU_PORT: u16 = 9999;
+
+/// Exchange of serialized APDUs with a device or an emulator.
+pub trait Transport {
+    /// Send a command APDU and return the response: its data followed by the
+    /// status word.
+    fn exchange(&mut self, command: &[u8]) -> io::Result<Vec<u8>>;
+}
+
+impl<T: Transport + ?Sized> Transport for &mut T {
+    fn exchange(&mut self, command: &[u8]) -> io::Result<Vec<u8>> {
+        (**self).exchange(command)
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for Box<T> {
+    fn exchange(&mut self, command: &[u8]) -> io::Result<Vec<u8>> {
+        (**self).exchange(command)
+    }
+}
+
+/// APDUs over TCP, as served by Speculos: each command is prefixed with its
+/// length on 4 bytes, and each response with the length of its data, without
+/// the status word, on 4 bytes.
+pub struct TcpTransport {
+    stream: TcpStream,
+}
+
+impl TcpTransport {
+    pub fn connect(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        stream.set_nodelay(true)?;
+        Ok(TcpTransport { stream })
+    }
+}
+
+impl Transport for TcpTransport {
+    fn exchange(&mut self, command: &[u8]) -> io::Result<Vec<u8>> {
+        let len = u32::try_from(command.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "command too long"))?;
+        self.stream.write_all(&len.to_be_bytes())?;
+        self.stream.write_all(command)?;
+
+        let mut len = [0; 4];
+        self.stream.read_exact(&mut len)?;
+        let len = usize::try_from(u32::from_be_bytes(len))
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "response too long"))?;
+
+        let mut response = vec![0; len + 2];
+        self.stream.read_exact(&mut response)?;
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+    use std::thread;
+
+    #[test]
+    fn tcp_framing() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut len = [0; 4];
+            stream.read_exact(&mut len).unwrap();
+            let mut command = vec![0; u32::from_be_bytes(len) as usize];
+            stream.read_exact(&mut command).unwrap();
+
+            stream
+                .write_all(&[0, 0, 0, 3, 1, 0, 0, 0x90, 0x00])
+                .unwrap();
+            command
+        });
+
+        let mut transport = TcpTransport::connect(addr).unwrap();
+        let response = transport.exchange(&[0xe0, 0x06, 0x00, 0x00, 0x00]).unwrap();
+        assert_eq!(response, [1, 0, 0, 0x90, 0x00]);
+        assert_eq!(server.join().unwrap(), [0xe0, 0x06, 0x00, 0x00, 0x00]);
+    }
+}
@@ -0,0 +1,43 @@
+//! Tests against the app running in Speculos, with its APDU server on the
+//! default port, or on the address in `SPECULOS_APDU_ADDR`:
+//!
+//! ```sh
+//! speculos --model nanosp target/nanosplus/release/app-aeternity &
+//! cargo +stable test-client -- --ignored
+//! ```
+
+use app_aeternity_client::{AppSW, Client, Error, SignTx, TcpTransport, SPECULOS_APDU_PORT};
+
+fn client() -> Client<TcpTransport> {
+    let addr = std::env::var("SPECULOS_APDU_ADDR")
+        .unwrap_or_else(|_| format!("127.0.0.1:{}", SPECULOS_APDU_PORT));
+    Client::new(TcpTransport::connect(addr).expect("Speculos must be running"))
+}
+
+#[test]
+#[ignore = "needs Speculos"]
+fn get_version() {
+    let version = client().get_version().unwrap();
+    assert!(version.major >= 1);
+}
+
+#[test]
+#[ignore = "needs Speculos"]
+fn get_address() {
+    let address = client().get_address(0, false).unwrap();
+    assert!(address.starts_with("ak_"));
+}
+
+#[test]
+#[ignore = "needs Speculos"]
+fn sign_unsupported_tx() {
+    // A list with the tag and the version of a NameClaimTx
+    let result = client().sign_tx(&SignTx {
+        account_number: 0,
+        network_id: "ae_mainnet",
+        inner_tx: false,
+        recipient_name: None,
+        tx: &[0xc2, 0x20, 0x02],
+    });
+    assert!(matches!(result, Err(Error::App(AppSW::TxUnsupportedTag))));
+}
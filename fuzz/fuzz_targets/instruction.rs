@@ -0,0 +1,9 @@
+#![no_main]
+
+use app_aeternity::instruction::Instruction;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|header: [u8; 3]| {
+    let [ins, p1, p2] = header;
+    let _ = Instruction::from_apdu_header(ins, p1, p2);
+});
@@ -0,0 +1,24 @@
+#![no_main]
+
+use app_aeternity::data::{format_data, DATA_PAGE_LEN, MAX_DATA_PAGES};
+use app_aeternity::requests::FirstChunk;
+use libfuzzer_sys::fuzz_target;
+
+// The first byte selects the hash mode, the rest is the data of the first
+// SignData APDU
+fuzz_target!(|data: &[u8]| {
+    let Some((hash_mode, data)) = data.split_first() else {
+        return;
+    };
+    let Ok(first_chunk) = FirstChunk::decode_data(data, hash_mode & 1 == 1) else {
+        return;
+    };
+
+    if let Some(pages) = format_data(first_chunk.chunk) {
+        assert!(pages.len() <= MAX_DATA_PAGES);
+        for page in pages {
+            assert!(page.len() <= DATA_PAGE_LEN);
+            assert!(!page.contains('\0'));
+        }
+    }
+});
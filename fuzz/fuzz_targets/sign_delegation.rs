@@ -0,0 +1,22 @@
+#![no_main]
+
+use app_aeternity::requests::{DelegationType, SignDelegationRequest};
+use libfuzzer_sys::fuzz_target;
+
+// The first byte selects the delegation type, the rest is the data of the
+// SignDelegation APDU
+fuzz_target!(|data: &[u8]| {
+    let Some((delegation_type, data)) = data.split_first() else {
+        return;
+    };
+    let delegation_type = match delegation_type % 3 {
+        0 => DelegationType::Preclaim,
+        1 => DelegationType::Oracle,
+        _ => DelegationType::Name,
+    };
+
+    if let Ok(request) = SignDelegationRequest::decode(data, delegation_type) {
+        assert!(!request.network_id.contains('\0'));
+        assert!(!request.name.unwrap_or_default().contains('\0'));
+    }
+});
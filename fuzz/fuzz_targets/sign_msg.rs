@@ -0,0 +1,18 @@
+#![no_main]
+
+use app_aeternity::message::{format_message, MessageDisplay, MAX_MESSAGE_DISPLAY_LEN};
+use app_aeternity::requests::SignMsgRequest;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(request) = SignMsgRequest::decode(data) else {
+        return;
+    };
+
+    let (MessageDisplay::Text { text, .. } | MessageDisplay::Hex(text) | MessageDisplay::Hash(text)) =
+        format_message(request.message, &[0; 32]);
+
+    // Everything reviewed on the device must be a short enough C string
+    assert!(!text.contains('\0'));
+    assert!(text.len() <= MAX_MESSAGE_DISPLAY_LEN);
+});
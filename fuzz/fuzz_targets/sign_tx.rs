@@ -0,0 +1,40 @@
+#![no_main]
+
+use app_aeternity::amount::display_amount;
+use app_aeternity::requests::SignTxHeader;
+use app_aeternity::tx::{parse_tx, ParsedTx};
+use app_aeternity::tx_stream::TxStream;
+use libfuzzer_sys::fuzz_target;
+
+// Data of the first SignTx APDU: the header followed by the start of the
+// transaction
+fuzz_target!(|data: &[u8]| {
+    let Ok(header) = SignTxHeader::decode(data) else {
+        return;
+    };
+    let parsed = parse_tx(header.tx_chunk);
+
+    // Decoding the transaction one byte at a time gives the same result
+    let mut stream = TxStream::new(header.tx_chunk.len(), false);
+    let streamed = header
+        .tx_chunk
+        .chunks(1)
+        .try_for_each(|byte| stream.feed(byte))
+        .and_then(|_| stream.finish())
+        .and_then(|tx| ParsedTx::from_decoded(&tx));
+    assert_eq!(streamed, parsed);
+
+    let Ok(tx) = parsed else {
+        return;
+    };
+
+    // Everything reviewed on the device must be a valid C string
+    for field in [
+        display_amount(tx.amount),
+        display_amount(tx.fee),
+        tx.recipient.unwrap_or_default(),
+        tx.payload,
+    ] {
+        assert!(!field.contains('\0'));
+    }
+});
@@ -0,0 +1,27 @@
+#![no_main]
+
+use app_aeternity::fate;
+use app_aeternity::requests::TypedData;
+use libfuzzer_sys::fuzz_target;
+
+// Typed data, once all its chunks are received
+fuzz_target!(|data: &[u8]| {
+    let Ok(typed_data) = TypedData::decode(data) else {
+        return;
+    };
+
+    let domain = typed_data.domain;
+    for field in [domain.name, domain.network_id].into_iter().flatten() {
+        assert!(!field.contains('\0'));
+    }
+
+    if let Ok(value) = fate::render(typed_data.data) {
+        assert!(!value.contains('\0'));
+    }
+    // The arity comes from the ACI, which is not checked against the data
+    let arity = typed_data.aci.len() % 16;
+    if let Ok(values) = fate::render_tuple(typed_data.data, arity) {
+        assert_eq!(values.len(), arity);
+        assert!(values.iter().all(|value| !value.contains('\0')));
+    }
+});
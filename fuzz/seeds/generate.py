@@ -0,0 +1,122 @@
+"""Generate the seeds of the fuzz targets.
+
+The transactions are synthetic, with made-up keys, but they follow the RLP
+serialization of the chain for each schema that the app decodes: SpendTx,
+ContractCreateTx, ContractCallTx and GAMetaTx.
+"""
+
+from pathlib import Path
+
+SEEDS = Path(__file__).parent
+
+
+def rlp(item) -> bytes:
+    if isinstance(item, list):
+        payload = b"".join(rlp(i) for i in item)
+        return rlp_length(len(payload), 0xC0) + payload
+    if isinstance(item, int):
+        item = item.to_bytes(32, "big").lstrip(b"\0")
+    if len(item) == 1 and item[0] < 0x80:
+        return item
+    return rlp_length(len(item), 0x80) + item
+
+
+def rlp_length(length: int, offset: int) -> bytes:
+    if length < 56:
+        return bytes([offset + length])
+    encoded = length.to_bytes(8, "big").lstrip(b"\0")
+    return bytes([offset + 55 + len(encoded)]) + encoded
+
+
+def account_id(pubkey: bytes) -> bytes:
+    return b"\x01" + pubkey
+
+
+def contract_id(pubkey: bytes) -> bytes:
+    return b"\x05" + pubkey
+
+
+SENDER = account_id(bytes(range(32)))
+RECIPIENT = account_id(bytes(range(32, 64)))
+CONTRACT = contract_id(bytes(range(64, 96)))
+# FATE 3 (Ceres), as `vm << 16 | abi`
+CT_VERSION = 8 << 16 | 3
+FATE_ABI = 3
+
+
+def spend_tx(amount: int, fee: int, nonce: int, payload: bytes) -> bytes:
+    return rlp([12, 1, SENDER, RECIPIENT, amount, fee, 0, nonce, payload])
+
+
+def contract_create_tx(code: bytes, call_data: bytes) -> bytes:
+    gas, gas_price = 100_000, 10**9
+    fee = 80_000_000_000_000
+    return rlp([42, 1, SENDER, 3, code, CT_VERSION, fee, 0, 0, 0, gas, gas_price, call_data])
+
+
+def contract_call_tx(amount: int, call_data: bytes) -> bytes:
+    gas, gas_price = 50_000, 10**9
+    fee = 180_000_000_000_000
+    return rlp([43, 1, SENDER, 4, CONTRACT, FATE_ABI, fee, 0, amount, gas, gas_price, call_data])
+
+
+def ga_meta_tx(auth_data: bytes, inner_tx: bytes) -> bytes:
+    gas, gas_price = 50_000, 10**9
+    fee = 100_000_000_000_000
+    return rlp([81, 2, SENDER, auth_data, FATE_ABI, fee, gas, gas_price, inner_tx])
+
+
+def u32(n: int) -> bytes:
+    return n.to_bytes(4, "big")
+
+
+def write(target: str, name: str, data: bytes) -> None:
+    directory = SEEDS / target
+    directory.mkdir(exist_ok=True)
+    (directory / name).write_bytes(data)
+
+
+def sign_tx_apdu(tx: bytes, network_id: bytes = b"ae_mainnet", inner: bool = False) -> bytes:
+    header = u32(0) + u32(len(tx)) + bytes([inner, len(network_id)]) + network_id
+    return header + tx
+
+
+def main() -> None:
+    ae = 10**18
+    txs = {
+        "spend": spend_tx(20 * ae, 16_840_000_000_000, 1, b""),
+        "spend_payload": spend_tx(ae // 2, 16_920_000_000_000, 42, b"Hello from aeternity"),
+        "spend_binary_payload": spend_tx(1, 17_000_000_000_000, 7, bytes([0, 10, 255])),
+        "contract_create": contract_create_tx(bytes(range(64)), b"\x2b\x11\x44\xd6\x44\x1f\x00"),
+        "contract_call": contract_call_tx(10**18, b"\x2b\x11\x9f\xae\x5a\x8b\x1b\x02"),
+        # Spend of a generalized account, wrapped with its (empty) signatures
+        "ga_meta": ga_meta_tx(
+            b"\x2b\x11\x4e\x40\x2c\x4f\x00", rlp([11, 1, [], spend_tx(1, 0, 0, b"")])
+        ),
+    }
+    for name, tx in txs.items():
+        write("sign_tx", name, sign_tx_apdu(tx))
+    write("sign_tx", "spend_inner", sign_tx_apdu(txs["spend"], b"ae_uat", inner=True))
+
+    for name, message in [("text", b"Hello"), ("escaped", b"one\ntwo"), ("binary", b"\xff\x00")]:
+        write("sign_msg", name, u32(0) + u32(len(message)) + message)
+
+    for name, (hash_mode, data) in {"raw": (0, b"data"), "hash": (1, bytes(range(64)))}.items():
+        write("sign_data", name, bytes([hash_mode]) + u32(0) + u32(len(data)) + data)
+
+    # Domain with every field, the ACI of a record and a tuple of an int and a string
+    domain = b"\x0f" + b"\x04test" + u32(2) + b"\x0aae_mainnet" + bytes(32)
+    aci = b'{"record":[{"name":"a","type":"int"},{"name":"b","type":"string"}]}'
+    data = b"\x2b\x02\x09ae"
+    typed_data = domain + len(aci).to_bytes(2, "big") + aci + len(data).to_bytes(2, "big") + data
+    write("sign_typed_data", "record", typed_data)
+
+    delegation = u32(0) + b"\x0aae_mainnet" + bytes(32)
+    write("sign_delegation", "preclaim", b"\x00" + delegation)
+    write("sign_delegation", "name", b"\x02" + delegation + b"\x08ae.chain")
+
+    write("instruction", "sign_tx", bytes([4, 0, 0]))
+
+
+if __name__ == "__main__":
+    main()
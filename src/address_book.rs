@@ -0,0 +1,248 @@
+//! Address book of trusted recipients, with the labels given by the user.
+//!
+//! Entries are stored in NVM by the app, each followed by a MAC computed with
+//! a key derived from the seed. Entries with an invalid MAC are ignored, so
+//! that entries can only be added through the app, after the user confirmed
+//! them.
+
+use alloc::string::String;
+
+use crate::encoding::{from_ae_string, parse_address, AeEncoding};
+use crate::AppSW;
+
+pub const MAX_ENTRIES: usize = 8;
+pub const MAX_LABEL_LEN: usize = 20;
+/// Length of a MAC, which is an HMAC-SHA256.
+pub const MAC_LEN: usize = 32;
+
+/// The address as an RLP id: its type (1 byte) and its 32 bytes.
+const ID_LEN: usize = 33;
+/// An entry is the id, the length of the label (1 byte) and the label padded
+/// with zeros.
+pub const ENTRY_DATA_LEN: usize = ID_LEN + 1 + MAX_LABEL_LEN;
+const ENTRY_LEN: usize = ENTRY_DATA_LEN + MAC_LEN;
+pub const STORAGE_LEN: usize = MAX_ENTRIES * ENTRY_LEN;
+
+// Types of the ids that can be registered
+const ACCOUNT_ID_TYPE: u8 = 1;
+const CONTRACT_ID_TYPE: u8 = 5;
+
+/// Storage of the address book. Empty slots are filled with zeros.
+pub type Storage = [u8; STORAGE_LEN];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    id: [u8; ID_LEN],
+    pub label: String,
+}
+
+impl Entry {
+    /// Entry for an `ak_` or `ct_` address, with a printable label.
+    pub fn new(address: &str, label: &str) -> Result<Self, AppSW> {
+        let (id_type, prefix) = if address.starts_with("ct_") {
+            (CONTRACT_ID_TYPE, AeEncoding::ContractAddress)
+        } else {
+            (ACCOUNT_ID_TYPE, AeEncoding::AccountAddress)
+        };
+        let bytes: [u8; 32] = from_ae_string(address, prefix)
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or(AppSW::AddressBookParsingFail)?;
+        if label.is_empty() || label.len() > MAX_LABEL_LEN || label.trim() != label {
+            return Err(AppSW::AddressBookParsingFail);
+        }
+
+        let mut id = [id_type; ID_LEN];
+        id[1..].copy_from_slice(&bytes);
+        Ok(Entry {
+            id,
+            label: label.into(),
+        })
+    }
+
+    pub fn address(&self) -> String {
+        parse_address(&self.id).expect("ids of entries are valid")
+    }
+
+    pub fn encode(&self) -> [u8; ENTRY_DATA_LEN] {
+        let mut data = [0; ENTRY_DATA_LEN];
+        data[..ID_LEN].copy_from_slice(&self.id);
+        data[ID_LEN] = self.label.len() as u8;
+        data[ID_LEN + 1..][..self.label.len()].copy_from_slice(self.label.as_bytes());
+        data
+    }
+
+    pub fn decode(data: &[u8; ENTRY_DATA_LEN]) -> Option<Self> {
+        let (id, rest) = data.split_first_chunk::<ID_LEN>()?;
+        let (len, label) = rest.split_first()?;
+        let label = core::str::from_utf8(label.get(..usize::from(*len))?).ok()?;
+        let entry = Entry::new(&parse_address(id).ok()?, label).ok()?;
+        // Make sure the encoding is unique, so that its MAC is too
+        (entry.encode() == *data).then_some(entry)
+    }
+}
+
+/// Entries of the storage that have a valid MAC, with their slots.
+fn entries<'a>(
+    storage: &'a Storage,
+    mac: &'a impl Fn(&[u8]) -> Option<[u8; MAC_LEN]>,
+) -> impl Iterator<Item = (usize, Entry)> + 'a {
+    storage
+        .chunks_exact(ENTRY_LEN)
+        .enumerate()
+        .filter_map(move |(slot, stored)| {
+            let (data, stored_mac) = stored.split_first_chunk::<ENTRY_DATA_LEN>()?;
+            if mac(data)?[..] != *stored_mac {
+                return None;
+            }
+            Some((slot, Entry::decode(data)?))
+        })
+}
+
+/// Label of an address, if it is in the address book.
+pub fn find(
+    storage: &Storage,
+    address: &str,
+    mac: impl Fn(&[u8]) -> Option<[u8; MAC_LEN]>,
+) -> Option<String> {
+    entries(storage, &mac)
+        .find(|(_, entry)| entry.address() == address)
+        .map(|(_, entry)| entry.label)
+}
+
+/// Add an entry, replacing the label of its address if it was already
+/// registered. Slots that do not hold a valid entry are reused.
+pub fn insert(
+    storage: &mut Storage,
+    entry: &Entry,
+    mac: impl Fn(&[u8]) -> Option<[u8; MAC_LEN]>,
+) -> Result<(), AppSW> {
+    let mut used = [false; MAX_ENTRIES];
+    let mut same_address = None;
+    for (slot, stored) in entries(storage, &mac) {
+        used[slot] = true;
+        if stored.id == entry.id {
+            same_address = Some(slot);
+        }
+    }
+    let slot = same_address
+        .or_else(|| used.iter().position(|used| !used))
+        .ok_or(AppSW::AddressBookFull)?;
+
+    let data = entry.encode();
+    let entry_mac = mac(&data).ok_or(AppSW::AddressBookFail)?;
+    let stored = &mut storage[slot * ENTRY_LEN..][..ENTRY_LEN];
+    stored[..ENTRY_DATA_LEN].copy_from_slice(&data);
+    stored[ENTRY_DATA_LEN..].copy_from_slice(&entry_mac);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::to_ae_string;
+    use sha2::{Digest, Sha256};
+
+    /// Stand-in for the HMAC of the app.
+    fn mac(data: &[u8]) -> Option<[u8; MAC_LEN]> {
+        Some(Sha256::digest([b"key", data].concat()).into())
+    }
+
+    fn account(byte: u8) -> String {
+        to_ae_string(&[byte; 32], AeEncoding::AccountAddress)
+    }
+
+    #[test]
+    fn entries() {
+        let entry = Entry::new(&account(1), "Treasury").unwrap();
+        assert_eq!(entry.address(), account(1));
+        assert_eq!(Entry::decode(&entry.encode()), Some(entry));
+
+        let contract = to_ae_string(&[2; 32], AeEncoding::ContractAddress);
+        assert_eq!(Entry::new(&contract, "DEX").unwrap().address(), contract);
+
+        let name = to_ae_string(&[2; 32], AeEncoding::Name);
+        for (address, label) in [
+            (account(1).as_str(), ""),
+            (account(1).as_str(), " padded"),
+            (account(1).as_str(), "a label that is far too long"),
+            (name.as_str(), "Name"),
+            ("ak_invalid", "Invalid"),
+        ] {
+            assert_eq!(
+                Entry::new(address, label),
+                Err(AppSW::AddressBookParsingFail)
+            );
+        }
+    }
+
+    #[test]
+    fn insert_and_find() {
+        let mut storage = [0; STORAGE_LEN];
+        assert_eq!(find(&storage, &account(1), mac), None);
+
+        let treasury = Entry::new(&account(1), "Treasury").unwrap();
+        insert(&mut storage, &treasury, mac).unwrap();
+        insert(
+            &mut storage,
+            &Entry::new(&account(2), "Payroll").unwrap(),
+            mac,
+        )
+        .unwrap();
+        assert_eq!(find(&storage, &account(1), mac).unwrap(), "Treasury");
+        assert_eq!(find(&storage, &account(2), mac).unwrap(), "Payroll");
+        assert_eq!(find(&storage, &account(3), mac), None);
+
+        // Registering an address again changes its label
+        insert(
+            &mut storage,
+            &Entry::new(&account(1), "Vault").unwrap(),
+            mac,
+        )
+        .unwrap();
+        assert_eq!(find(&storage, &account(1), mac).unwrap(), "Vault");
+        assert_eq!(storage[2 * ENTRY_LEN..], [0; STORAGE_LEN - 2 * ENTRY_LEN]);
+    }
+
+    #[test]
+    fn full() {
+        let mut storage = [0; STORAGE_LEN];
+        for i in 0..MAX_ENTRIES as u8 {
+            insert(
+                &mut storage,
+                &Entry::new(&account(i), "Label").unwrap(),
+                mac,
+            )
+            .unwrap();
+        }
+        let entry = Entry::new(&account(0xff), "Label").unwrap();
+        assert_eq!(
+            insert(&mut storage, &entry, mac),
+            Err(AppSW::AddressBookFull)
+        );
+    }
+
+    #[test]
+    fn forged_entries_are_ignored() {
+        let mut storage = [0; STORAGE_LEN];
+        let entry = Entry::new(&account(1), "Treasury").unwrap();
+        insert(&mut storage, &entry, mac).unwrap();
+
+        // A MAC computed with another key
+        let other_mac = |data: &[u8]| Some(Sha256::digest(data).into());
+        assert_eq!(find(&storage, &account(1), other_mac), None);
+
+        // A label changed without updating the MAC
+        storage[ID_LEN + 1] = b'X';
+        assert_eq!(find(&storage, &account(1), mac), None);
+
+        // The slot of the forged entry can be reused
+        for i in 0..MAX_ENTRIES as u8 {
+            insert(
+                &mut storage,
+                &Entry::new(&account(i + 2), "Label").unwrap(),
+                mac,
+            )
+            .unwrap();
+        }
+    }
+}
@@ -0,0 +1,117 @@
+//! Formatting of amounts of AE, and of the tokens of other networks.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use primitive_types::U256;
+
+/// Decimals of AE, whose smallest unit is the Aetto.
+pub const AE_DECIMALS: u8 = 18;
+
+/// Convert an amount in Aettos to an amount in AE.
+pub fn display_amount(amount: U256) -> String {
+    display_amount_with_decimals(amount, AE_DECIMALS)
+}
+
+/// Convert an amount in the smallest unit of a token to an amount of the
+/// token, which has the given number of decimals.
+///
+/// Since there's no need to deal with floating-point numbers, the conversion
+/// is done by converting the amount to String and moving the decimal point
+/// `decimals` places to left.
+pub fn display_amount_with_decimals(amount: U256, decimals: u8) -> String {
+    let decimal_places = usize::from(decimals);
+
+    // Pad the amount with as many leading zeros as decimals
+    let padded = ["0".repeat(decimal_places), amount.to_string()].concat();
+
+    // Move the decimal point to the left (divide by 10^decimals)
+    let (left, right) = padded.split_at(padded.len() - decimal_places);
+
+    // Remove leading zeros from the decimal part
+    let dec = left.chars().skip_while(|c| *c == '0').collect::<String>();
+    // Remove trailing zeros from the fractional part
+    let frac = right
+        .chars()
+        .rev()
+        .skip_while(|c| *c == '0')
+        .collect::<Vec<_>>()
+        .into_iter()
+        .rev()
+        .collect::<String>();
+
+    let mut output = String::new();
+
+    if dec.is_empty() {
+        output.push('0');
+    } else {
+        output.push_str(&dec);
+    }
+
+    if !frac.is_empty() {
+        output.push('.');
+        output.push_str(&frac);
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    const AE: u64 = 1_000_000_000_000_000_000;
+
+    #[test]
+    fn known_amounts() {
+        assert_eq!(display_amount(U256::zero()), "0");
+        assert_eq!(display_amount(U256::one()), "0.000000000000000001");
+        assert_eq!(display_amount(U256::from(AE)), "1");
+        assert_eq!(display_amount(U256::from(AE / 2)), "0.5");
+        assert_eq!(display_amount(U256::from(20) * U256::from(AE)), "20");
+        assert_eq!(
+            display_amount(U256::from(16_840_000_000_000u64)),
+            "0.00001684"
+        );
+        assert_eq!(
+            display_amount(U256::from(1_234_567_890_123_456_789u64)),
+            "1.234567890123456789"
+        );
+        assert_eq!(
+            display_amount(U256::MAX),
+            "115792089237316195423570985008687907853269984665640564039457.584007913129639935"
+        );
+    }
+
+    #[test]
+    fn other_decimals() {
+        let amount = U256::from(12_500);
+        assert_eq!(display_amount_with_decimals(amount, 3), "12.5");
+        assert_eq!(display_amount_with_decimals(amount, 0), "12500");
+        assert_eq!(display_amount_with_decimals(amount, 6), "0.0125");
+    }
+
+    proptest! {
+        #[test]
+        fn matches_division(high in any::<u128>(), low in any::<u128>()) {
+            let amount = (U256::from(high) << 128) | U256::from(low);
+            let formatted = display_amount(amount);
+
+            let (integer, fraction) = formatted.split_once('.').unwrap_or((&formatted, ""));
+            prop_assert!(!integer.is_empty());
+            prop_assert!(integer == "0" || !integer.starts_with('0'));
+            prop_assert!(fraction.len() <= 18 && !fraction.ends_with('0'));
+
+            let ae = U256::from(AE);
+            prop_assert_eq!(integer, (amount / ae).to_string());
+            let padded_fraction = format!("{:0<18}", fraction);
+            prop_assert_eq!(
+                U256::from_dec_str(&padded_fraction).unwrap(),
+                amount % ae
+            );
+        }
+    }
+}
@@ -18,7 +18,10 @@
 use include_gif::include_gif;
 use ledger_device_sdk::io::Comm;
 
-use ledger_device_sdk::nbgl::{NbglGlyph, NbglHomeAndSettings};
+use ledger_device_sdk::nbgl::{NbglChoice, NbglGlyph, NbglHomeAndSettings, NbglStatus};
+
+use crate::settings::{Setting, Settings, SETTINGS_STRINGS};
+use crate::storage;
 
 pub fn ui_menu_main(_: &mut Comm) -> NbglHomeAndSettings {
     // Load glyph from 64x64 4bpp gif file with include_gif macro. Creates an NBGL compatible glyph.
@@ -27,10 +30,31 @@ pub fn ui_menu_main(_: &mut Comm) -> NbglHomeAndSettings {
     #[cfg(any(target_os = "nanosplus", target_os = "nanox"))]
     const FERRIS: NbglGlyph = NbglGlyph::from_include(include_gif!("icons/ae_16x16.gif", NBGL));
 
+    let mut settings = Settings;
+
     // Display the home screen.
-    NbglHomeAndSettings::new().glyph(&FERRIS).infos(
-        "Aeternity",
-        env!("CARGO_PKG_VERSION"),
-        env!("CARGO_PKG_AUTHORS"),
-    )
+    NbglHomeAndSettings::new()
+        .glyph(&FERRIS)
+        .settings(settings.get_mut(), &SETTINGS_STRINGS)
+        .infos(
+            "Aeternity",
+            env!("CARGO_PKG_VERSION"),
+            env!("CARGO_PKG_AUTHORS"),
+        )
+}
+
+/// Ask to confirm the reset of the nonce tracker, once its switch was turned
+/// on in the settings, and forget the nonces right away if the user confirms.
+pub fn ui_reset_nonces() {
+    let confirmed = NbglChoice::new().show(
+        "Reset the nonce tracker?",
+        "Reused nonces of the transactions signed so far will not be detected anymore",
+        "Reset nonces",
+        "Cancel",
+    );
+    if confirmed {
+        storage::reset_nonces();
+        NbglStatus::new().text("Nonces reset").show(true);
+    }
+    Settings.disable(Setting::ResetNonces);
 }
@@ -0,0 +1,36 @@
+use include_gif::include_gif;
+use ledger_device_sdk::nbgl::{Field, NbglGlyph, NbglReview};
+
+use app_aeternity::address_book::Entry;
+
+use crate::AppSW;
+
+/// Displays an address to add to the address book and returns true if the
+/// user approved it.
+pub fn ui_display_register_address(entry: &Entry) -> Result<bool, AppSW> {
+    let address = entry.address();
+    let fields = [
+        Field {
+            name: "Label",
+            value: entry.label.as_str(),
+        },
+        Field {
+            name: "Address",
+            value: address.as_str(),
+        },
+    ];
+
+    #[cfg(any(target_os = "stax", target_os = "flex"))]
+    const FERRIS: NbglGlyph = NbglGlyph::from_include(include_gif!("icons/ae_64x64.gif", NBGL));
+    #[cfg(any(target_os = "nanosplus", target_os = "nanox"))]
+    const FERRIS: NbglGlyph = NbglGlyph::from_include(include_gif!("icons/ae_16x16.gif", NBGL));
+    let review: NbglReview = NbglReview::new()
+        .titles(
+            "Review address\nto trust it",
+            "",
+            "Add address\nto the address book",
+        )
+        .glyph(&FERRIS);
+
+    Ok(review.show(&fields))
+}
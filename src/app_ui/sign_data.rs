@@ -1,23 +1,79 @@
-use alloc::{borrow::ToOwned, string::String, vec::Vec};
+use alloc::{format, string::String, vec::Vec};
 
 use include_gif::include_gif;
 use ledger_device_sdk::nbgl::{Field, NbglGlyph, NbglReview};
 
-use base64::prelude::{Engine, BASE64_STANDARD};
+use app_aeternity::data::format_data;
 
 use crate::AppSW;
 
-pub fn ui_display_data(data_bytes: &[u8]) -> Result<bool, AppSW> {
-    let data = format_data(data_bytes);
+/// What is going to be signed.
+pub enum DataReview<'a> {
+    /// The data itself is signed, its hash is shown when it's too long.
+    Raw { data: &'a [u8], hash: &'a [u8; 32] },
+    /// The Blake2b-256 hash of the data is signed.
+    Hash { hash: &'a [u8; 32], data_len: usize },
+}
 
-    let my_fields = match &data {
-        Some(string) => Vec::from([Field {
-            name: "Data",
-            value: string,
-        }]),
-        None => Vec::new(),
+/// Displays data to be signed and returns true if user approved it.
+///
+/// Short data is shown as text if it's printable, or base64 otherwise, and is
+/// split in several pages when needed. The hash is shown instead when the data
+/// does not fit in [`MAX_DATA_PAGES`](app_aeternity::data::MAX_DATA_PAGES) pages, or when the hash is what gets
+/// signed.
+pub fn ui_display_data(review: DataReview) -> Result<bool, AppSW> {
+    let (pages, hash, data_len, warning) = match review {
+        DataReview::Raw { data, hash } => match format_data(data) {
+            Some(pages) => (pages, None, data.len(), None),
+            None => (
+                Vec::new(),
+                Some(hex::encode(hash)),
+                data.len(),
+                Some("Data too long, showing its Blake2b-256 hash"),
+            ),
+        },
+        DataReview::Hash { hash, data_len } => {
+            (Vec::new(), Some(hex::encode(hash)), data_len, None)
+        }
     };
 
+    let data_len_str = format!("{} bytes", data_len);
+    let page_names: Vec<String> = (1..=pages.len())
+        .map(|i| format!("Data ({}/{})", i, pages.len()))
+        .collect();
+
+    let mut my_fields = Vec::new();
+
+    if let Some(warning) = warning {
+        my_fields.push(Field {
+            name: "Warning",
+            value: warning,
+        });
+    }
+
+    my_fields.push(Field {
+        name: "Size",
+        value: data_len_str.as_str(),
+    });
+
+    match pages.as_slice() {
+        [page] => my_fields.push(Field {
+            name: "Data",
+            value: page.as_str(),
+        }),
+        _ => my_fields.extend(pages.iter().zip(&page_names).map(|(page, name)| Field {
+            name: name.as_str(),
+            value: page.as_str(),
+        })),
+    }
+
+    if let Some(hash) = &hash {
+        my_fields.push(Field {
+            name: "Data hash",
+            value: hash.as_str(),
+        });
+    }
+
     #[cfg(any(target_os = "stax", target_os = "flex"))]
     const FERRIS: NbglGlyph = NbglGlyph::from_include(include_gif!("icons/ae_64x64.gif", NBGL));
     #[cfg(any(target_os = "nanosplus", target_os = "nanox"))]
@@ -26,18 +82,8 @@ pub fn ui_display_data(data_bytes: &[u8]) -> Result<bool, AppSW> {
     // with constant generic parameters of NbglReview. Default values are 32 and 1024 respectively.
     let review: NbglReview = NbglReview::new()
         .titles("Review data", "", "Sign data")
-        .glyph(&FERRIS);
+        .glyph(&FERRIS)
+        .blind();
 
     Ok(review.show(&my_fields))
 }
-
-fn format_data(data: &[u8]) -> Option<String> {
-    if data.len() > 50 {
-        None
-    } else {
-        match core::str::from_utf8(data) {
-            Ok(utf8_str) if utf8_str.is_ascii() => Some(utf8_str.to_owned()),
-            _ => Some(BASE64_STANDARD.encode(data)),
-        }
-    }
-}
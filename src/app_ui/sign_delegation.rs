@@ -0,0 +1,85 @@
+use alloc::{format, vec::Vec};
+
+use include_gif::include_gif;
+use ledger_device_sdk::nbgl::{Field, NbglGlyph, NbglReview};
+
+use app_aeternity::encoding::{self, AeEncoding};
+use app_aeternity::requests::DelegationType;
+
+use crate::AppSW;
+
+/// Displays a delegation to a contract and returns true if user approved it.
+///
+/// # Arguments
+///
+/// * `delegation_type` - What the contract is allowed to do
+/// * `network_id` - Network the delegation is valid on
+/// * `account` - Public key of the delegating account
+/// * `contract` - Public key of the contract the account delegates to
+/// * `name` - Name managed by the contract, for name delegations only
+pub fn ui_display_delegation(
+    delegation_type: DelegationType,
+    network_id: &str,
+    account: &[u8; 32],
+    contract: &[u8; 32],
+    name: Option<&str>,
+) -> Result<bool, AppSW> {
+    let account_str = encoding::to_ae_string(account, AeEncoding::AccountAddress);
+    let contract_str = encoding::to_ae_string(contract, AeEncoding::ContractAddress);
+    let name_str = name.map(|name| name.to_ascii_lowercase());
+
+    let summary = match (delegation_type, &name_str) {
+        (DelegationType::Preclaim, None) => format!(
+            "Allow contract {} to preclaim names on your behalf",
+            contract_str
+        ),
+        (DelegationType::Oracle, None) => format!(
+            "Allow contract {} to manage your oracle on your behalf",
+            contract_str
+        ),
+        (DelegationType::Name, Some(name)) => format!(
+            "Allow contract {} to manage name {} on your behalf",
+            contract_str, name
+        ),
+        _ => return Err(AppSW::DelegationParsingFail),
+    };
+
+    let mut my_fields = Vec::from([Field {
+        name: "Delegation",
+        value: summary.as_str(),
+    }]);
+
+    if let Some(name) = &name_str {
+        my_fields.push(Field {
+            name: "Name",
+            value: name.as_str(),
+        });
+    }
+
+    my_fields.extend([
+        Field {
+            name: "Contract",
+            value: contract_str.as_str(),
+        },
+        Field {
+            name: "Account",
+            value: account_str.as_str(),
+        },
+        Field {
+            name: "Network ID",
+            value: network_id,
+        },
+    ]);
+
+    #[cfg(any(target_os = "stax", target_os = "flex"))]
+    const FERRIS: NbglGlyph = NbglGlyph::from_include(include_gif!("icons/ae_64x64.gif", NBGL));
+    #[cfg(any(target_os = "nanosplus", target_os = "nanox"))]
+    const FERRIS: NbglGlyph = NbglGlyph::from_include(include_gif!("icons/ae_16x16.gif", NBGL));
+    // Create NBGL review. Maximum number of fields and string buffer length can be customised
+    // with constant generic parameters of NbglReview. Default values are 32 and 1024 respectively.
+    let review: NbglReview = NbglReview::new()
+        .titles("Review delegation", "", "Sign delegation")
+        .glyph(&FERRIS);
+
+    Ok(review.show(&my_fields))
+}
@@ -1,15 +1,63 @@
+use alloc::vec::Vec;
+
 use include_gif::include_gif;
 use ledger_device_sdk::nbgl::{Field, NbglGlyph, NbglReview};
 
+use app_aeternity::message::{format_message, MessageDisplay};
+
 use crate::AppSW;
 
-pub fn ui_display_msg(message_bytes: &[u8]) -> Result<bool, AppSW> {
-    let message = core::str::from_utf8(message_bytes).unwrap();
+/// Displays a message and returns true if user approved it.
+///
+/// # Arguments
+///
+/// * `message_bytes` - Raw message to be displayed for validation
+/// * `hash` - Blake2b hash of the message, as it is going to be signed
+pub fn ui_display_msg(message_bytes: &[u8], hash: &[u8; 32]) -> Result<bool, AppSW> {
+    let display = format_message(message_bytes, hash);
 
-    let my_fields = [Field {
-        name: "Message",
-        value: message,
-    }];
+    let my_fields = match &display {
+        MessageDisplay::Text {
+            text,
+            escaped: false,
+        } => Vec::from([Field {
+            name: "Message",
+            value: text,
+        }]),
+        MessageDisplay::Text {
+            text,
+            escaped: true,
+        } => Vec::from([
+            Field {
+                name: "Warning",
+                value: "Message contains newlines or control characters, shown escaped",
+            },
+            Field {
+                name: "Message",
+                value: text,
+            },
+        ]),
+        MessageDisplay::Hex(hex) => Vec::from([
+            Field {
+                name: "Warning",
+                value: "Message is not printable text, showing hex",
+            },
+            Field {
+                name: "Message (hex)",
+                value: hex,
+            },
+        ]),
+        MessageDisplay::Hash(hash) => Vec::from([
+            Field {
+                name: "Warning",
+                value: "Message too long, showing hash",
+            },
+            Field {
+                name: "Message hash",
+                value: hash,
+            },
+        ]),
+    };
 
     #[cfg(any(target_os = "stax", target_os = "flex"))]
     const FERRIS: NbglGlyph = NbglGlyph::from_include(include_gif!("icons/ae_64x64.gif", NBGL));
@@ -14,46 +14,121 @@
  *  See the License for the specific language governing permissions and
  *  limitations under the License.
  *****************************************************************************/
-use alloc::{
-    string::{String, ToString},
-    vec::Vec,
-};
+use alloc::borrow::ToOwned;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
 
-use primitive_types::U256;
+use app_aeternity::batch::Batch;
+use app_aeternity::encoding::{to_ae_string, AeEncoding};
+use app_aeternity::fee::{FeeCheck, OVERPAID_FACTOR};
+use app_aeternity::network::Network;
+use app_aeternity::tx::{ParsedTx, TxKind};
 
-use crate::handlers::sign_tx::TxFirstChunk;
+use crate::settings::{Setting, Settings};
 use crate::AppSW;
 
 use include_gif::include_gif;
-use ledger_device_sdk::nbgl::{Field, NbglGlyph, NbglReview};
+use ledger_device_sdk::nbgl::{Field, NbglGlyph, NbglReview, NbglStatus};
 
 /// Displays a transaction and returns true if user approved it.
 ///
 /// This method can return [`AppSW::TxDisplayFail`] error if the coin name length is too long.
+/// Transactions that use gas also show their maximum total cost, and its
+/// breakdown in expert mode.
 ///
 /// # Arguments
 ///
 /// * `tx` - Transaction to be displayed for validation
-pub fn ui_display_tx(tx: &TxFirstChunk) -> Result<bool, AppSW> {
-    let amount_str = display_amount(tx.amount);
-    let fee_str = display_amount(tx.fee);
-    let to_str = tx.recipient.clone();
+/// * `network` - Network of the transaction, whose token the amounts are in
+/// * `recipient_name` - AENS name of an `nm_` recipient, checked against it
+/// * `recipient_label` - Label of the recipient, if it is in the address book
+/// * `nonce_reused` - Whether the nonce, or a higher one, was already signed
+pub fn ui_display_tx(
+    tx: &ParsedTx,
+    network: &Network,
+    recipient_name: Option<&str>,
+    recipient_label: Option<&str>,
+    nonce_reused: bool,
+) -> Result<bool, AppSW> {
+    let amount_str = if network.is_mainnet() {
+        network.format_amount(tx.amount)
+    } else {
+        format!("{} on {}", network.format_amount(tx.amount), network.name)
+    };
+    let fee_str = network.format_amount(tx.fee);
+    let to_str = tx
+        .recipient
+        .as_deref()
+        .map(|recipient| match (recipient_name, recipient_label) {
+            (Some(name), _) => name.to_owned(),
+            (None, Some(label)) => format!("{} ({})", label, recipient),
+            (None, None) => recipient.to_owned(),
+        });
+    let fee_warning = match tx.fee_check() {
+        FeeCheck::Ok => None,
+        FeeCheck::BelowMinimum => Some(format!(
+            "Fee below the minimum of {}, the transaction will be rejected",
+            network.format_amount(tx.min_fee)
+        )),
+        FeeCheck::Overpaid => Some(format!(
+            "Fee {}x or more above the minimum of {}",
+            OVERPAID_FACTOR,
+            network.format_amount(tx.min_fee)
+        )),
+    };
+    let nonce_warning = tx.nonce.filter(|_| nonce_reused).map(|nonce| {
+        format!(
+            "Nonce {} was already signed, this may replace or double-spend another transaction",
+            nonce
+        )
+    });
+    // The fee is not all that the user can lose when the transaction uses gas
+    let total_cost_str = network.format_amount(tx.max_total_cost()?);
+    let deposit_str = network.format_amount(tx.deposit);
+    let gas_str = format!("{}", tx.gas);
+    let gas_price_str = network.format_amount(tx.gas_price);
+    let gas_cost_str = network.format_amount(tx.max_gas_cost()?);
 
     // Define transaction review fields
-    let mut my_fields = Vec::from([
-        Field {
+    let mut my_fields = Vec::new();
+    if tx.hides_inner_tx() {
+        my_fields.push(Field {
+            name: "Warning",
+            value: "The inner transaction cannot be shown, check it on your computer",
+        });
+    }
+    for warning in [&nonce_warning, &fee_warning].into_iter().flatten() {
+        my_fields.push(Field {
+            name: "Warning",
+            value: warning.as_str(),
+        });
+    }
+    if tx.recipient.is_some() && recipient_label.is_none() {
+        my_fields.push(Field {
+            name: "Warning",
+            value: "New recipient, not in the address book",
+        });
+    }
+    if tx.kind != TxKind::GaMeta {
+        my_fields.push(Field {
             name: "Amount",
             value: amount_str.as_str(),
-        },
-        Field {
-            name: "Fee",
-            value: fee_str.as_str(),
-        },
-        Field {
-            name: "Destination",
+        });
+    }
+    my_fields.push(Field {
+        name: "Fee",
+        value: fee_str.as_str(),
+    });
+    if let Some(to_str) = &to_str {
+        my_fields.push(Field {
+            name: match tx.kind {
+                TxKind::ContractCall => "Contract",
+                _ => "Destination",
+            },
             value: to_str.as_str(),
-        },
-    ]);
+        });
+    }
 
     if !tx.payload.is_empty() {
         my_fields.push(Field {
@@ -62,6 +137,45 @@ pub fn ui_display_tx(tx: &TxFirstChunk) -> Result<bool, AppSW> {
         });
     }
 
+    if tx.uses_gas() {
+        if Settings.is_enabled(Setting::ExpertMode) {
+            if let Some(vm) = tx.vm {
+                my_fields.push(Field {
+                    name: "VM",
+                    value: vm,
+                });
+            }
+            if tx.kind == TxKind::ContractCreate {
+                my_fields.push(Field {
+                    name: "Deposit",
+                    value: deposit_str.as_str(),
+                });
+            }
+            my_fields.extend([
+                Field {
+                    name: "Gas limit",
+                    value: gas_str.as_str(),
+                },
+                Field {
+                    name: "Gas price",
+                    value: gas_price_str.as_str(),
+                },
+                Field {
+                    name: "Max gas cost",
+                    value: gas_cost_str.as_str(),
+                },
+            ]);
+        }
+        my_fields.push(Field {
+            name: if tx.hides_inner_tx() {
+                "Max cost without inner transaction"
+            } else {
+                "Max total cost"
+            },
+            value: total_cost_str.as_str(),
+        });
+    }
+
     // Create transaction review
 
     // Load glyph from 64x64 4bpp gif file with include_gif macro. Creates an NBGL compatible glyph.
@@ -71,55 +185,233 @@ pub fn ui_display_tx(tx: &TxFirstChunk) -> Result<bool, AppSW> {
     const FERRIS: NbglGlyph = NbglGlyph::from_include(include_gif!("icons/ae_16x16.gif", NBGL));
     // Create NBGL review. Maximum number of fields and string buffer length can be customised
     // with constant generic parameters of NbglReview. Default values are 32 and 1024 respectively.
-    let review: NbglReview = NbglReview::new()
-        .titles(
-            "Review transaction\nto send AE",
-            "",
-            "Sign transaction\nto send AE",
-        )
+    let (review_title, sign_title) = match tx.kind {
+        // Spends are in the token of the network
+        TxKind::Spend => (
+            format!("Review transaction\nto send {}", network.ticker),
+            format!("Sign transaction\nto send {}", network.ticker),
+        ),
+        TxKind::ContractCreate => (
+            "Review transaction\nto create a contract".into(),
+            "Sign transaction\nto create a contract".into(),
+        ),
+        TxKind::ContractCall => (
+            "Review transaction\nto call a contract".into(),
+            "Sign transaction\nto call a contract".into(),
+        ),
+        TxKind::GaMeta => (
+            "Review generalized\naccount transaction".into(),
+            "Blind sign generalized\naccount transaction".into(),
+        ),
+    };
+    let mut review: NbglReview = NbglReview::new()
+        .titles(&review_title, "", &sign_title)
         .glyph(&FERRIS);
+    if tx.hides_inner_tx() {
+        review = review.blind();
+    }
 
     Ok(review.show(&my_fields))
 }
 
-/// Convert an amount in Aettos to an amount in AE.
+/// Displays the summary of a batch of SpendTx and returns true if user
+/// approved all of them.
+///
+/// # Arguments
 ///
-/// Since there's no need to deal with floating-point numbers, the conversion
-/// is done by converting the amount to String and moving the decimal point 18
-/// places to left.
-fn display_amount(amount: U256) -> String {
-    const DECIMAL_PLACES: usize = 18;
-
-    // Pad the amount in Aettos with 18 leading zeros
-    let padded = ["0".repeat(DECIMAL_PLACES), amount.to_string()].concat();
-
-    // Move the decimal point 18 places to the left (divide by 10^18)
-    let (left, right) = padded.split_at(padded.len() - DECIMAL_PLACES);
-
-    // Remove leading zeros from the decimal part
-    let dec = left.chars().skip_while(|c| *c == '0').collect::<String>();
-    // Remove trailing zeros from the fractional part
-    let frac = right
-        .chars()
-        .rev()
-        .skip_while(|c| *c == '0')
-        .collect::<Vec<_>>()
-        .into_iter()
-        .rev()
-        .collect::<String>();
-
-    let mut output = String::new();
-
-    if dec.is_empty() {
-        output.push('0');
+/// * `batch` - Transactions of the batch, all of them received and decoded
+/// * `network` - Network of the transactions, whose token the amounts are in
+/// * `labels` - Label of the recipient of each payment, if it is in the
+///   address book
+/// * `nonce_reused` - Whether the nonce of the first transaction, or a higher
+///   one, was already signed
+pub fn ui_display_tx_batch(
+    batch: &Batch,
+    network: &Network,
+    labels: &[Option<String>],
+    nonce_reused: bool,
+) -> Result<bool, AppSW> {
+    let count_str = format!("{}", batch.payments.len());
+    let total_amount_str = if network.is_mainnet() {
+        network.format_amount(batch.total_amount)
     } else {
-        output.push_str(&dec);
+        format!(
+            "{} on {}",
+            network.format_amount(batch.total_amount),
+            network.name
+        )
+    };
+    let total_fee_str = network.format_amount(batch.total_fee);
+    let total_cost_str = network.format_amount(batch.total_cost()?);
+
+    let mut warnings = Vec::new();
+    if let Some(nonce) = batch.first_nonce.filter(|_| nonce_reused) {
+        warnings.push(format!(
+            "Nonce {} was already signed, this may replace or double-spend other transactions",
+            nonce
+        ));
+    }
+    for (index, payment) in batch.payments.iter().enumerate() {
+        let min_fee = network.format_amount(payment.min_fee);
+        match payment.fee_check {
+            FeeCheck::Ok => {}
+            FeeCheck::BelowMinimum => warnings.push(format!(
+                "Fee of payment {} below the minimum of {}, the transaction will be rejected",
+                index + 1,
+                min_fee
+            )),
+            FeeCheck::Overpaid => warnings.push(format!(
+                "Fee of payment {} {}x or more above the minimum of {}",
+                index + 1,
+                OVERPAID_FACTOR,
+                min_fee
+            )),
+        }
     }
+    let new_recipients = labels.iter().filter(|label| label.is_none()).count();
+    if new_recipients > 0 {
+        warnings.push(format!(
+            "{} of the {} recipients are not in the address book",
+            new_recipients,
+            labels.len()
+        ));
+    }
+
+    // One field for each payment, paginated by the review
+    let payment_names = (1..=batch.payments.len())
+        .map(|index| format!("Payment {} of {}", index, batch.payments.len()))
+        .collect::<Vec<_>>();
+    let payment_strs = batch
+        .payments
+        .iter()
+        .zip(labels)
+        .map(|(payment, label)| {
+            let amount = network.format_amount(payment.amount);
+            match label {
+                Some(label) => format!("{} to {} ({})", amount, label, payment.recipient),
+                None => format!("{} to {}", amount, payment.recipient),
+            }
+        })
+        .collect::<Vec<_>>();
 
-    if !frac.is_empty() {
-        output.push('.');
-        output.push_str(&frac);
+    let mut my_fields = Vec::new();
+    for warning in &warnings {
+        my_fields.push(Field {
+            name: "Warning",
+            value: warning.as_str(),
+        });
     }
+    my_fields.extend([
+        Field {
+            name: "Transactions",
+            value: count_str.as_str(),
+        },
+        Field {
+            name: "Total amount",
+            value: total_amount_str.as_str(),
+        },
+        Field {
+            name: "Total fees",
+            value: total_fee_str.as_str(),
+        },
+        Field {
+            name: "Total cost",
+            value: total_cost_str.as_str(),
+        },
+        Field {
+            name: "Network",
+            value: network.name.as_str(),
+        },
+    ]);
+    for (name, value) in payment_names.iter().zip(&payment_strs) {
+        my_fields.push(Field {
+            name: name.as_str(),
+            value: value.as_str(),
+        });
+    }
+
+    #[cfg(any(target_os = "stax", target_os = "flex"))]
+    const FERRIS: NbglGlyph = NbglGlyph::from_include(include_gif!("icons/ae_64x64.gif", NBGL));
+    #[cfg(any(target_os = "nanosplus", target_os = "nanox"))]
+    const FERRIS: NbglGlyph = NbglGlyph::from_include(include_gif!("icons/ae_16x16.gif", NBGL));
+    let review_title = format!(
+        "Review {} transactions\nto send {}",
+        batch.payments.len(),
+        network.ticker
+    );
+    let sign_title = format!(
+        "Sign {} transactions\nto send {}",
+        batch.payments.len(),
+        network.ticker
+    );
+    let review: NbglReview = NbglReview::new()
+        .titles(&review_title, "", &sign_title)
+        .glyph(&FERRIS);
+
+    Ok(review.show(&my_fields))
+}
+
+/// Displays the hash of a transaction that cannot be decoded, most likely
+/// because it comes from a later hard fork, and returns true if user approved
+/// it. Only used when blind signing is enabled.
+///
+/// # Arguments
+///
+/// * `tag` - Type of the transaction
+/// * `version` - Version of the type, whose layout is unknown
+/// * `network` - Network of the transaction
+/// * `hash` - Blake2b-256 hash of the transaction, which is signed
+pub fn ui_display_blind_tx(
+    tag: u8,
+    version: u8,
+    network: &Network,
+    hash: &[u8; 32],
+) -> Result<bool, AppSW> {
+    let type_str = format!("{} (version {})", tag, version);
+    let hash_str = hex::encode(hash);
+
+    let my_fields = [
+        Field {
+            name: "Warning",
+            value: "Unknown transaction type or version, its fields cannot be shown",
+        },
+        Field {
+            name: "Type",
+            value: type_str.as_str(),
+        },
+        Field {
+            name: "Network",
+            value: network.name.as_str(),
+        },
+        Field {
+            name: "Transaction hash",
+            value: hash_str.as_str(),
+        },
+    ];
+
+    #[cfg(any(target_os = "stax", target_os = "flex"))]
+    const FERRIS: NbglGlyph = NbglGlyph::from_include(include_gif!("icons/ae_64x64.gif", NBGL));
+    #[cfg(any(target_os = "nanosplus", target_os = "nanox"))]
+    const FERRIS: NbglGlyph = NbglGlyph::from_include(include_gif!("icons/ae_16x16.gif", NBGL));
+    let review: NbglReview = NbglReview::new()
+        .blind()
+        .titles(
+            "Review unknown\ntransaction",
+            "",
+            "Blind sign unknown\ntransaction",
+        )
+        .glyph(&FERRIS);
+
+    Ok(review.show(&my_fields))
+}
 
-    output
+/// Displays the `th_` hash of the SignedTx once a transaction was signed, in
+/// place of the status of the review, so that the user can find it on the
+/// chain.
+pub fn ui_display_tx_hash(tx_hash: &[u8; 32]) {
+    let text = format!(
+        "Transaction signed\n{}",
+        to_ae_string(tx_hash, AeEncoding::TxHash)
+    );
+    NbglStatus::new().text(&text).show(true);
 }
@@ -0,0 +1,70 @@
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+
+use include_gif::include_gif;
+use ledger_device_sdk::nbgl::{Field, NbglGlyph, NbglReview};
+
+use app_aeternity::encoding::{self, AeEncoding};
+use app_aeternity::requests::Domain;
+
+use crate::AppSW;
+
+/// Displays the domain and the decoded fields of typed data, and returns true
+/// if user approved it.
+///
+/// # Arguments
+///
+/// * `domain` - Domain the typed data belongs to
+/// * `fields` - Names and rendered values of the typed data fields
+pub fn ui_display_typed_data(domain: &Domain, fields: &[(&str, String)]) -> Result<bool, AppSW> {
+    let version = domain.version.map(|version| version.to_string());
+    let contract = domain
+        .contract_address
+        .map(|address| encoding::to_ae_string(address, AeEncoding::ContractAddress));
+
+    let mut my_fields = Vec::new();
+
+    if let Some(name) = domain.name {
+        my_fields.push(Field {
+            name: "Domain",
+            value: name,
+        });
+    }
+    if let Some(version) = &version {
+        my_fields.push(Field {
+            name: "Version",
+            value: version,
+        });
+    }
+    if let Some(network_id) = domain.network_id {
+        my_fields.push(Field {
+            name: "Network ID",
+            value: network_id,
+        });
+    }
+    if let Some(contract) = &contract {
+        my_fields.push(Field {
+            name: "Contract",
+            value: contract,
+        });
+    }
+
+    my_fields.extend(fields.iter().map(|(name, value)| Field {
+        name,
+        value: value.as_str(),
+    }));
+
+    #[cfg(any(target_os = "stax", target_os = "flex"))]
+    const FERRIS: NbglGlyph = NbglGlyph::from_include(include_gif!("icons/ae_64x64.gif", NBGL));
+    #[cfg(any(target_os = "nanosplus", target_os = "nanox"))]
+    const FERRIS: NbglGlyph = NbglGlyph::from_include(include_gif!("icons/ae_16x16.gif", NBGL));
+    // Create NBGL review. Maximum number of fields and string buffer length can be customised
+    // with constant generic parameters of NbglReview. Default values are 32 and 1024 respectively.
+    let review: NbglReview = NbglReview::new()
+        .titles("Review typed data", "", "Sign typed data")
+        .glyph(&FERRIS);
+
+    Ok(review.show(&my_fields))
+}
@@ -0,0 +1,175 @@
+//! Batches of SpendTx, signed after a single review of all of them.
+//!
+//! Payrolls and other batch payments sign many SpendTx from the same account
+//! in a row. Each transaction of a batch is decoded and checked like a single
+//! SpendTx, but the user only reviews a summary: the totals and the list of
+//! payments.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use primitive_types::U256;
+
+use crate::fee::FeeCheck;
+use crate::tx::{ParsedTx, TxKind};
+use crate::AppSW;
+
+/// Payments are kept until the batch is reviewed, and each one is a field of
+/// the review, so a batch cannot be much longer.
+pub const MAX_BATCH_LEN: usize = 16;
+
+/// Payment of a transaction of a batch.
+#[derive(Debug, PartialEq)]
+pub struct Payment {
+    pub recipient: String,
+    pub amount: U256,
+    pub fee_check: FeeCheck,
+    pub min_fee: U256,
+    /// Blake2b-256 hash of the transaction, which is signed
+    pub hash: [u8; 32],
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Batch {
+    /// Number of transactions announced by the host
+    pub tx_count: usize,
+    pub payments: Vec<Payment>,
+    pub total_amount: U256,
+    pub total_fee: U256,
+    pub first_nonce: Option<U256>,
+    pub last_nonce: Option<U256>,
+}
+
+impl Batch {
+    pub fn new(tx_count: usize) -> Self {
+        Batch {
+            tx_count,
+            ..Default::default()
+        }
+    }
+
+    /// Add a decoded transaction, which must be a SpendTx whose nonce follows
+    /// the ones of the previous transactions.
+    pub fn add(&mut self, tx: ParsedTx, hash: [u8; 32]) -> Result<(), AppSW> {
+        if self.is_complete() {
+            return Err(AppSW::BatchParsingFail);
+        }
+        if tx.kind != TxKind::Spend {
+            return Err(AppSW::TxUnsupportedTag);
+        }
+        let nonce = tx.nonce.ok_or(AppSW::TxParsingFail)?;
+        // Two transactions with the same nonce would replace each other
+        if self.last_nonce.is_some_and(|last| nonce <= last) {
+            return Err(AppSW::BatchNonceFail);
+        }
+
+        let total_amount = self.total_amount.checked_add(tx.amount);
+        let total_fee = self.total_fee.checked_add(tx.fee);
+        // Reject batches whose total cost cannot even be shown
+        let (Some(total_amount), Some(total_fee)) = (total_amount, total_fee) else {
+            return Err(AppSW::TxCostOverflow);
+        };
+        total_amount
+            .checked_add(total_fee)
+            .ok_or(AppSW::TxCostOverflow)?;
+
+        self.payments.push(Payment {
+            fee_check: tx.fee_check(),
+            recipient: tx.recipient.ok_or(AppSW::TxParsingFail)?,
+            amount: tx.amount,
+            min_fee: tx.min_fee,
+            hash,
+        });
+        self.total_amount = total_amount;
+        self.total_fee = total_fee;
+        self.first_nonce.get_or_insert(nonce);
+        self.last_nonce = Some(nonce);
+        Ok(())
+    }
+
+    /// Whether all the announced transactions were received.
+    pub fn is_complete(&self) -> bool {
+        self.payments.len() == self.tx_count
+    }
+
+    /// Most that the sender can lose with the batch: the amounts and the fees
+    /// of all the transactions.
+    pub fn total_cost(&self) -> Result<U256, AppSW> {
+        self.total_amount
+            .checked_add(self.total_fee)
+            .ok_or(AppSW::TxCostOverflow)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::string::ToString;
+
+    fn spend(recipient: &str, amount: u64, nonce: u64) -> ParsedTx {
+        ParsedTx {
+            kind: TxKind::Spend,
+            recipient: Some(recipient.to_string()),
+            nonce: Some(nonce.into()),
+            amount: amount.into(),
+            fee: 20.into(),
+            min_fee: 20.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn totals() {
+        let mut batch = Batch::new(2);
+        batch.add(spend("ak_alice", 100, 4), [1; 32]).unwrap();
+        assert!(!batch.is_complete());
+        batch.add(spend("ak_bob", 50, 5), [2; 32]).unwrap();
+        assert!(batch.is_complete());
+
+        assert_eq!(batch.total_amount, 150.into());
+        assert_eq!(batch.total_fee, 40.into());
+        assert_eq!(batch.total_cost(), Ok(190.into()));
+        assert_eq!(
+            (batch.first_nonce, batch.last_nonce),
+            (Some(4.into()), Some(5.into()))
+        );
+        assert_eq!(batch.payments[1].recipient, "ak_bob");
+        assert_eq!(batch.payments[1].hash, [2; 32]);
+
+        // More transactions than announced
+        assert_eq!(
+            batch.add(spend("ak_carol", 1, 6), [3; 32]),
+            Err(AppSW::BatchParsingFail)
+        );
+    }
+
+    #[test]
+    fn nonces_must_increase() {
+        let mut batch = Batch::new(3);
+        batch.add(spend("ak_alice", 100, 4), [1; 32]).unwrap();
+        for nonce in [4, 3] {
+            assert_eq!(
+                batch.add(spend("ak_bob", 50, nonce), [2; 32]),
+                Err(AppSW::BatchNonceFail)
+            );
+        }
+        // Nonces may be skipped
+        assert_eq!(batch.add(spend("ak_bob", 50, 7), [2; 32]), Ok(()));
+    }
+
+    #[test]
+    fn only_spends() {
+        let mut batch = Batch::new(1);
+        let call = ParsedTx {
+            kind: TxKind::ContractCall,
+            ..spend("ct_contract", 1, 1)
+        };
+        assert_eq!(batch.add(call, [0; 32]), Err(AppSW::TxUnsupportedTag));
+
+        let overflow = ParsedTx {
+            amount: U256::MAX,
+            ..spend("ak_alice", 0, 1)
+        };
+        assert_eq!(batch.add(overflow, [0; 32]), Err(AppSW::TxCostOverflow));
+    }
+}
@@ -0,0 +1,160 @@
+//! Configuration of the app, sent to hosts so that they can adapt to it.
+
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::requests::{printable_str, MAX_MESSAGE_LEN, MAX_RAW_DATA_LEN, MAX_TYPED_DATA_LEN};
+use crate::tx::supported_tags;
+use crate::version::parse_version_string;
+
+pub const APP_NAME: &str = "Aeternity";
+
+// Bits of the settings byte
+pub const SETTING_BLIND_SIGNING: u8 = 0x01;
+pub const SETTING_EXPERT_MODE: u8 = 0x02;
+
+// Bits of the features, telling which parts of the protocol are supported
+/// SignTx can respond with the whole SignedTx
+pub const FEATURE_SIGNED_TX_RESPONSE: u16 = 0x0001;
+/// SignData can sign the hash of data of any length
+pub const FEATURE_SIGN_DATA_HASH: u16 = 0x0002;
+pub const FEATURE_SIGN_TYPED_DATA: u16 = 0x0004;
+pub const FEATURE_SIGN_DELEGATION: u16 = 0x0008;
+/// SignTxBatch signs several SpendTx after a single review
+pub const FEATURE_SIGN_TX_BATCH: u16 = 0x0010;
+/// SignTx can respond with the signature and the hash of the SignedTx, with
+/// [`P2_SIGN_TX_SIGNATURE_AND_HASH`](crate::instruction::P2_SIGN_TX_SIGNATURE_AND_HASH)
+pub const FEATURE_TX_HASH: u16 = 0x0020;
+
+const FEATURES: u16 = FEATURE_SIGNED_TX_RESPONSE
+    | FEATURE_SIGN_DATA_HASH
+    | FEATURE_SIGN_TYPED_DATA
+    | FEATURE_SIGN_DELEGATION
+    | FEATURE_SIGN_TX_BATCH
+    | FEATURE_TX_HASH;
+
+/// Response of GetAppConfiguration: the version (3 bytes), the name and the
+/// device model (1 byte length + string each), the enabled settings (1 byte),
+/// the features (2 bytes), the maximum lengths of messages, raw data and
+/// typed data (2 bytes each), and the supported transaction tags (1 byte
+/// count + 1 byte each).
+#[derive(Clone, Debug, PartialEq)]
+pub struct AppConfiguration {
+    pub version: (u8, u8, u8),
+    pub name: String,
+    pub model: String,
+    pub settings: u8,
+    pub features: u16,
+    pub max_message_len: u16,
+    pub max_raw_data_len: u16,
+    pub max_typed_data_len: u16,
+    pub tx_tags: Vec<u8>,
+}
+
+impl AppConfiguration {
+    /// Configuration of this build of the app, running on `model` with the
+    /// given settings.
+    pub fn new(model: &str, settings: u8) -> Self {
+        AppConfiguration {
+            version: parse_version_string(env!("CARGO_PKG_VERSION")).unwrap_or_default(),
+            name: APP_NAME.to_owned(),
+            model: model.to_owned(),
+            settings,
+            features: FEATURES,
+            max_message_len: MAX_MESSAGE_LEN as u16,
+            max_raw_data_len: MAX_RAW_DATA_LEN as u16,
+            max_typed_data_len: MAX_TYPED_DATA_LEN as u16,
+            tx_tags: supported_tags(),
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let (major, minor, patch) = self.version;
+        let mut output = Vec::from([major, minor, patch]);
+        for s in [&self.name, &self.model] {
+            output.push(s.len() as u8);
+            output.extend_from_slice(s.as_bytes());
+        }
+        output.push(self.settings);
+        output.extend(self.features.to_be_bytes());
+        for len in [
+            self.max_message_len,
+            self.max_raw_data_len,
+            self.max_typed_data_len,
+        ] {
+            output.extend(len.to_be_bytes());
+        }
+        output.push(self.tx_tags.len() as u8);
+        output.extend_from_slice(&self.tx_tags);
+        output
+    }
+
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let ([major, minor, patch], data) = data.split_first_chunk::<3>()?;
+        let (name, data) = short_str(data)?;
+        let (model, data) = short_str(data)?;
+        let ([settings, f0, f1, l0, l1, l2, l3, l4, l5], data) = data.split_first_chunk::<9>()?;
+        let (count, tx_tags) = data.split_first()?;
+        if tx_tags.len() != usize::from(*count) {
+            return None;
+        }
+
+        Some(AppConfiguration {
+            version: (*major, *minor, *patch),
+            name,
+            model,
+            settings: *settings,
+            features: u16::from_be_bytes([*f0, *f1]),
+            max_message_len: u16::from_be_bytes([*l0, *l1]),
+            max_raw_data_len: u16::from_be_bytes([*l2, *l3]),
+            max_typed_data_len: u16::from_be_bytes([*l4, *l5]),
+            tx_tags: tx_tags.to_vec(),
+        })
+    }
+
+    pub fn is_enabled(&self, setting: u8) -> bool {
+        self.settings & setting != 0
+    }
+
+    pub fn supports(&self, feature: u16) -> bool {
+        self.features & feature != 0
+    }
+}
+
+fn short_str(data: &[u8]) -> Option<(String, &[u8])> {
+    let (len, data) = data.split_first()?;
+    let (bytes, rest) = data.split_at_checked(usize::from(*len))?;
+    Some((printable_str(bytes)?.to_owned(), rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let config = AppConfiguration::new("nanosplus", SETTING_BLIND_SIGNING);
+        assert_eq!(config.name, "Aeternity");
+        assert_eq!(config.tx_tags, [0x0c, 0x2a, 0x2b, 0x51]);
+        assert!(config.is_enabled(SETTING_BLIND_SIGNING));
+        assert!(!config.is_enabled(SETTING_EXPERT_MODE));
+        assert!(config.supports(FEATURE_SIGNED_TX_RESPONSE));
+        assert!(config.supports(FEATURE_TX_HASH));
+
+        let encoded = config.encode();
+        assert_eq!(&encoded[3..14], b"\x09Aeternity\x09");
+        assert_eq!(AppConfiguration::decode(&encoded), Some(config));
+    }
+
+    #[test]
+    fn invalid() {
+        let encoded = AppConfiguration::new("flex", 0).encode();
+        for len in 0..encoded.len() {
+            assert_eq!(AppConfiguration::decode(&encoded[..len]), None);
+        }
+        let mut longer = encoded.clone();
+        longer.push(0);
+        assert_eq!(AppConfiguration::decode(&longer), None);
+    }
+}
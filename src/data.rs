@@ -0,0 +1,70 @@
+//! Rendering of the data signed with SignData.
+
+use alloc::{borrow::ToOwned, string::String, vec::Vec};
+
+use base64::prelude::{Engine, BASE64_STANDARD};
+
+/// Number of characters of the data shown on each page of the review.
+pub const DATA_PAGE_LEN: usize = 128;
+/// Data needing more pages than this is reviewed by hash.
+pub const MAX_DATA_PAGES: usize = 8;
+
+/// Split the data in pages of text if it's printable, or base64 otherwise.
+///
+/// Returns `None` if the data does not fit in [`MAX_DATA_PAGES`] pages.
+pub fn format_data(data: &[u8]) -> Option<Vec<String>> {
+    let formatted = match core::str::from_utf8(data) {
+        Ok(utf8_str)
+            if utf8_str
+                .chars()
+                .all(|c| c.is_ascii() && !c.is_ascii_control()) =>
+        {
+            utf8_str.to_owned()
+        }
+        _ => BASE64_STANDARD.encode(data),
+    };
+
+    if formatted.len() > DATA_PAGE_LEN * MAX_DATA_PAGES {
+        return None;
+    }
+
+    // The formatted data is ASCII, so it can be split at any byte
+    Some(
+        formatted
+            .as_bytes()
+            .chunks(DATA_PAGE_LEN)
+            .map(|page| String::from_utf8_lossy(page).into_owned())
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_pages() {
+        assert_eq!(format_data(b"hello"), Some(Vec::from(["hello".into()])));
+
+        let text = [b'x'; DATA_PAGE_LEN + 1];
+        let pages = format_data(&text).unwrap();
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].len(), DATA_PAGE_LEN);
+        assert_eq!(pages[1], "x");
+    }
+
+    #[test]
+    fn binary_data() {
+        assert_eq!(format_data(&[0, 1, 2]), Some(Vec::from(["AAEC".into()])));
+        assert_eq!(format_data(b"tab\t"), Some(Vec::from(["dGFiCQ==".into()])));
+    }
+
+    #[test]
+    fn too_long() {
+        assert!(format_data(&[b'x'; DATA_PAGE_LEN * MAX_DATA_PAGES]).is_some());
+        assert_eq!(
+            format_data(&[b'x'; DATA_PAGE_LEN * MAX_DATA_PAGES + 1]),
+            None
+        );
+    }
+}
@@ -0,0 +1,247 @@
+//! Encodings of the identifiers and lengths used by Aeternity.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+use base64::prelude::{Engine, BASE64_STANDARD};
+use sha2::{Digest, Sha256};
+
+use crate::AppSW;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AeEncoding {
+    AccountAddress,
+    Name,
+    Commitment,
+    OracleAddress,
+    ContractAddress,
+    Channel,
+    Signature,
+    /// Serialized transaction, encoded in base64
+    Transaction,
+    /// Hash of a SignedTx, which identifies it on the chain
+    TxHash,
+}
+
+impl fmt::Display for AeEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use AeEncoding::*;
+
+        match self {
+            AccountAddress => write!(f, "ak"),
+            Name => write!(f, "nm"),
+            Commitment => write!(f, "cm"),
+            OracleAddress => write!(f, "ok"),
+            ContractAddress => write!(f, "ct"),
+            Channel => write!(f, "ch"),
+            Signature => write!(f, "sg"),
+            Transaction => write!(f, "tx"),
+            TxHash => write!(f, "th"),
+        }
+    }
+}
+
+/// Encode a length the same way as the Bitcoin "varint", which is used in the
+/// prefix of signed messages.
+pub fn varuint_encode(n: usize) -> Vec<u8> {
+    let mut output = Vec::new();
+
+    if n <= 0xFC {
+        output.push(n as u8);
+    } else if n <= 0xFFFF {
+        output.push(0xFD);
+        output.extend((n as u16).to_le_bytes());
+    } else {
+        output.push(0xFE);
+        output.extend((n as u32).to_le_bytes());
+    }
+
+    output
+}
+
+/// Encode a public key or a hash with its prefix, e.g. `ak_...`.
+///
+/// The bytes are followed by a 4 bytes checksum and encoded in base58.
+pub fn to_ae_string(pubkey: &[u8], prefix: AeEncoding) -> String {
+    let pk = [pubkey, &make_check(pubkey)].concat();
+
+    let mut output = prefix.to_string();
+    output.push('_');
+    // The output buffer is resizeable, so it's fine to use unwrap here
+    let _ = bs58::encode(pk).onto(&mut output).unwrap();
+
+    output
+}
+
+/// Encode serialized data with its prefix, e.g. `tx_...`.
+///
+/// The bytes are followed by a 4 bytes checksum and encoded in base64.
+pub fn to_ae_base64_string(bytes: &[u8], prefix: AeEncoding) -> String {
+    let encoded = BASE64_STANDARD.encode([bytes, &make_check(bytes)].concat());
+    [prefix.to_string().as_str(), "_", &encoded].concat()
+}
+
+/// Decode a string made by [`to_ae_string`] or [`to_ae_base64_string`],
+/// checking its prefix and its checksum.
+pub fn from_ae_string(input: &str, prefix: AeEncoding) -> Option<Vec<u8>> {
+    let (input_prefix, encoded) = input.split_once('_')?;
+    if input_prefix != prefix.to_string() {
+        return None;
+    }
+
+    let decoded = match prefix {
+        AeEncoding::Transaction => BASE64_STANDARD.decode(encoded).ok()?,
+        _ => bs58::decode(encoded).into_vec().ok()?,
+    };
+    let (bytes, check) = decoded.split_last_chunk::<4>()?;
+    (*check == make_check(bytes)).then(|| bytes.to_vec())
+}
+
+/// Convert an RLP-encoded id, which is the type of the id (1 byte) followed
+/// by 32 bytes, to its string form.
+pub fn parse_address(address: &[u8]) -> Result<String, AppSW> {
+    let (prefix_byte, rest) = address.split_first().ok_or(AppSW::TxBadAddressLength)?;
+
+    let prefix = match *prefix_byte {
+        1 => AeEncoding::AccountAddress,
+        2 => AeEncoding::Name,
+        3 => AeEncoding::Commitment,
+        4 => AeEncoding::OracleAddress,
+        5 => AeEncoding::ContractAddress,
+        6 => AeEncoding::Channel,
+        _ => Err(AppSW::TxBadAddressPrefix)?,
+    };
+
+    let address_bytes: [u8; 32] = rest.try_into().map_err(|_| AppSW::TxBadAddressLength)?;
+
+    Ok(to_ae_string(&address_bytes, prefix))
+}
+
+fn make_check(input: &[u8]) -> [u8; 4] {
+    let digest = Sha256::digest(Sha256::digest(input));
+    *digest
+        .first_chunk::<4>()
+        .expect("SHA-256 digest must be 32 bytes")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn varuint() {
+        assert_eq!(varuint_encode(0), [0x00]);
+        assert_eq!(varuint_encode(0xFC), [0xFC]);
+        assert_eq!(varuint_encode(0xFD), [0xFD, 0xFD, 0x00]);
+        assert_eq!(varuint_encode(0x1234), [0xFD, 0x34, 0x12]);
+        assert_eq!(varuint_encode(0xFFFF), [0xFD, 0xFF, 0xFF]);
+        assert_eq!(varuint_encode(0x10000), [0xFE, 0x00, 0x00, 0x01, 0x00]);
+    }
+
+    #[test]
+    fn known_addresses() {
+        // Address of the zero public key, used as a placeholder by aepp-sdk
+        assert_eq!(
+            to_ae_string(&[0; 32], AeEncoding::AccountAddress),
+            "ak_11111111111111111111111111111111273Yts"
+        );
+        assert_eq!(
+            to_ae_string(&[0; 32], AeEncoding::ContractAddress),
+            "ct_11111111111111111111111111111111273Yts"
+        );
+
+        let pubkey: Vec<u8> = (0..32).collect();
+        assert_eq!(
+            to_ae_string(&pubkey, AeEncoding::AccountAddress),
+            "ak_16qJFWMMHFy3xDdLmvUeyc2S6FrWRhJP51HsvDYdz9d1FsYG"
+        );
+        assert_eq!(
+            to_ae_string(&[0xFF; 32], AeEncoding::OracleAddress),
+            "ok_2wkBET2rRgE8pahuaczxKbmv7ciehqsne57F9gtzf1PVZS9BEY"
+        );
+        assert_eq!(
+            to_ae_string(&[0; 32], AeEncoding::TxHash),
+            "th_11111111111111111111111111111111273Yts"
+        );
+    }
+
+    #[test]
+    fn parse_ids() {
+        let id = [&[5][..], &[0; 32]].concat();
+        assert_eq!(
+            parse_address(&id).as_deref(),
+            Ok("ct_11111111111111111111111111111111273Yts")
+        );
+
+        assert_eq!(parse_address(&[]), Err(AppSW::TxBadAddressLength));
+        assert_eq!(parse_address(&[1; 32]), Err(AppSW::TxBadAddressLength));
+        assert_eq!(parse_address(&[1; 34]), Err(AppSW::TxBadAddressLength));
+        let unknown = [&[7][..], &[0; 32]].concat();
+        assert_eq!(parse_address(&unknown), Err(AppSW::TxBadAddressPrefix));
+    }
+
+    #[test]
+    fn decode_strings() {
+        let address = "ak_16qJFWMMHFy3xDdLmvUeyc2S6FrWRhJP51HsvDYdz9d1FsYG";
+        let pubkey = (0..32).collect::<Vec<u8>>();
+        assert_eq!(
+            from_ae_string(address, AeEncoding::AccountAddress),
+            Some(pubkey)
+        );
+        assert_eq!(from_ae_string(address, AeEncoding::OracleAddress), None);
+        // Wrong checksum
+        let corrupted = address.replace("FsYG", "FsYH");
+        assert_eq!(from_ae_string(&corrupted, AeEncoding::AccountAddress), None);
+        assert_eq!(from_ae_string("ak", AeEncoding::AccountAddress), None);
+
+        let tx = to_ae_base64_string(&[0xc2, 0x0c, 0x01], AeEncoding::Transaction);
+        assert!(tx.starts_with("tx_"));
+        assert_eq!(
+            from_ae_string(&tx, AeEncoding::Transaction),
+            Some(vec![0xc2, 0x0c, 0x01])
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn base64_round_trip(bytes in proptest::collection::vec(any::<u8>(), 0..300)) {
+            let encoded = to_ae_base64_string(&bytes, AeEncoding::Transaction);
+            prop_assert_eq!(from_ae_string(&encoded, AeEncoding::Transaction), Some(bytes));
+        }
+
+        #[test]
+        fn varuint_length(n in 0..=u32::MAX as usize) {
+            let encoded = varuint_encode(n);
+            let expected_len = match n {
+                0..=0xFC => 1,
+                0xFD..=0xFFFF => 3,
+                _ => 5,
+            };
+            prop_assert_eq!(encoded.len(), expected_len);
+
+            let mut value = [0; 4];
+            match encoded.split_first() {
+                Some((len, [])) => value[0] = *len,
+                Some((_, rest)) => value[..rest.len()].copy_from_slice(rest),
+                None => unreachable!(),
+            }
+            prop_assert_eq!(u32::from_le_bytes(value) as usize, n);
+        }
+
+        #[test]
+        fn ae_string_round_trip(pubkey in any::<[u8; 32]>(), tag in 1u8..=6) {
+            let id = [&[tag][..], &pubkey].concat();
+            let encoded = parse_address(&id).unwrap();
+
+            let (prefix, base58) = encoded.split_once('_').unwrap();
+            let expected_prefix = ["ak", "nm", "cm", "ok", "ct", "ch"][usize::from(tag - 1)];
+            prop_assert_eq!(prefix, expected_prefix);
+
+            let decoded = bs58::decode(base58).into_vec().unwrap();
+            prop_assert_eq!(&decoded[..32], &pubkey[..]);
+            prop_assert_eq!(&decoded[32..], &make_check(&pubkey)[..]);
+        }
+    }
+}
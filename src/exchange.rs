@@ -0,0 +1,101 @@
+//! Entry points used when the app is called as a library by the Exchange app.
+
+use ledger_device_sdk::io::Comm;
+use ledger_device_sdk::libcall::swap::{self, CheckAddressParams, CreateTxParams, SwapResult};
+use ledger_device_sdk::libcall::{self, LibCallCommand};
+
+use app_aeternity::encoding::{to_ae_string, AeEncoding};
+use app_aeternity::instruction::{Instruction, CLA};
+use app_aeternity::swap::{account_from_path, printable_amount, SwapParams};
+
+use crate::handlers::get_app_configuration::handler_get_app_configuration;
+use crate::handlers::get_version::handler_get_version;
+use crate::handlers::sign_tx::{handler_get_signed_tx_chunk, handler_sign_tx, TxContext};
+use crate::utils;
+use crate::AppSW;
+
+pub fn swap_main(arg0: u32) {
+    match libcall::get_command(arg0) {
+        LibCallCommand::SwapCheckAddress => {
+            let mut params = swap::get_check_address_params(arg0);
+            let matches = check_address(&params);
+            swap::swap_return(SwapResult::CheckAddressResult(&mut params, matches as i32));
+        }
+        LibCallCommand::SwapGetPrintableAmount => {
+            let mut params = swap::get_printable_amount_params(arg0);
+            // The amount is right-aligned in its buffer
+            let amount = printable_amount(&params.amount).unwrap_or_default();
+            swap::swap_return(SwapResult::PrintableAmountResult(&mut params, &amount));
+        }
+        LibCallCommand::SwapSignTransaction => {
+            let mut params = swap::sign_tx_params(arg0);
+            let signed = sign_swap_tx(&params);
+            swap::swap_return(SwapResult::CreateTxResult(&mut params, signed as u8));
+        }
+    }
+}
+
+/// Whether the address to check is the address of the account of the path.
+fn check_address(params: &CheckAddressParams) -> bool {
+    // The lengths are given by the caller, and may not fit in the buffers
+    let (Some(dpath), Some(ref_address)) = (
+        params.dpath.get(..params.dpath_len.saturating_mul(4)),
+        params.ref_address.get(..params.ref_address_len),
+    ) else {
+        return false;
+    };
+    let address = account_from_path(dpath)
+        .and_then(utils::get_public_key)
+        .map(|pk| to_ae_string(&pk, AeEncoding::AccountAddress));
+
+    address.is_some_and(|address| address.as_bytes() == ref_address)
+}
+
+/// Receive the transaction of the swap from the host, and sign it if it
+/// matches the parameters of the swap. Only one transaction can be signed.
+fn sign_swap_tx(params: &CreateTxParams) -> bool {
+    let Some(swap_params) = params
+        .dest_address
+        .get(..params.dest_address_len)
+        .and_then(|destination| SwapParams::new(&params.amount, &params.fee_amount, destination))
+    else {
+        return false;
+    };
+
+    let mut comm = Comm::new().set_expected_cla(CLA);
+    let mut ctx = TxContext::for_swap(swap_params);
+
+    loop {
+        let ins: Instruction = comm.next_command();
+        ctx.end_session_unless_tx(&ins);
+
+        let result = match ins {
+            Instruction::SignTx {
+                first_chunk,
+                response,
+            } => handler_sign_tx(&mut comm, first_chunk, response, &mut ctx),
+            Instruction::GetSignedTxChunk => handler_get_signed_tx_chunk(&mut comm, &mut ctx),
+            Instruction::GetVersion => handler_get_version(&mut comm),
+            Instruction::GetAppConfiguration => handler_get_app_configuration(&mut comm),
+            // The state of SignTx was already reset when receiving Abort
+            Instruction::Abort => Ok(()),
+            _ => Err(AppSW::InsNotSupported),
+        };
+
+        match result {
+            Ok(()) => comm.swap_reply_ok(),
+            Err(sw) => comm.swap_reply(sw),
+        }
+
+        match (ins, result) {
+            // A transaction that does not match the swap ends it
+            (Instruction::SignTx { .. }, Err(_)) => return false,
+            (Instruction::SignTx { .. } | Instruction::GetSignedTxChunk, Ok(()))
+                if ctx.is_finished() && ctx.is_response_sent() =>
+            {
+                return true
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,466 @@
+//! Minimal FATE (Fast Aeternity Transaction Engine) value serialization.
+//!
+//! Only the subset needed for typed data signing is implemented: encoding the
+//! values of the typed data domain, and rendering arbitrary FATE values as
+//! strings to be reviewed on the device.
+//!
+//! The encoding follows `aeb_fate_encoding` from the aebytecode library.
+
+use alloc::{
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::fmt::Write;
+
+use aerlp::RlpItem;
+use primitive_types::U256;
+
+use crate::encoding::{self, AeEncoding};
+use crate::AppSW;
+
+const SMALL_INT_SIZE: u32 = 64;
+const SHORT_STRING_SIZE: usize = 64;
+const SHORT_COLLECTION_SIZE: usize = 16;
+
+// Tags that are identified by their low bits only
+const SHORT_STRING: u8 = 0b01;
+const SHORT_LIST: u8 = 0b0011;
+const SHORT_TUPLE: u8 = 0b1011;
+
+// Tags that are identified by the whole byte
+const LONG_STRING: u8 = 0b0000_0001;
+const LONG_TUPLE: u8 = 0b0000_1011;
+const LONG_LIST: u8 = 0b0001_1111;
+const MAP: u8 = 0b0010_1111;
+const EMPTY_TUPLE: u8 = 0b0011_1111;
+const EMPTY_STRING: u8 = 0b0101_1111;
+const POS_BIG_INT: u8 = 0b0110_1111;
+const FALSE: u8 = 0b0111_1111;
+const OBJECT: u8 = 0b1001_1111;
+const VARIANT: u8 = 0b1010_1111;
+const NEG_BIG_INT: u8 = 0b1110_1111;
+const TRUE: u8 = 0b1111_1111;
+
+// Object types
+const OTYPE_ADDRESS: u8 = 0;
+const OTYPE_BYTES: u8 = 1;
+const OTYPE_CONTRACT: u8 = 2;
+const OTYPE_ORACLE: u8 = 3;
+const OTYPE_CHANNEL: u8 = 5;
+
+/// Maximum nesting of compound values accepted when rendering, to keep the
+/// stack usage bounded.
+const MAX_DEPTH: usize = 8;
+
+/// Append the FATE encoding of a non-negative integer.
+pub fn encode_int(output: &mut Vec<u8>, n: u32) {
+    if n < SMALL_INT_SIZE {
+        // Sign bit is 0, and the lowest bit is the small int tag (0)
+        output.push((n as u8) << 1);
+    } else {
+        output.push(POS_BIG_INT);
+        rlp_encode_int(output, n - SMALL_INT_SIZE);
+    }
+}
+
+/// Append the FATE encoding of a string.
+pub fn encode_string(output: &mut Vec<u8>, s: &[u8]) {
+    if s.is_empty() {
+        output.push(EMPTY_STRING);
+    } else if s.len() < SHORT_STRING_SIZE {
+        output.push(((s.len() as u8) << 2) | SHORT_STRING);
+        output.extend_from_slice(s);
+    } else {
+        output.push(LONG_STRING);
+        rlp_encode_int(output, (s.len() - SHORT_STRING_SIZE) as u32);
+        output.extend_from_slice(s);
+    }
+}
+
+/// Append the FATE encoding of a contract address.
+pub fn encode_contract(output: &mut Vec<u8>, address: &[u8; 32]) {
+    output.push(OBJECT);
+    output.push(OTYPE_CONTRACT);
+    rlp_encode_bytes(output, address);
+}
+
+/// Append the header of a tuple of `size` elements, the elements themselves
+/// must be appended right after.
+pub fn encode_tuple_header(output: &mut Vec<u8>, size: usize) {
+    if size == 0 {
+        output.push(EMPTY_TUPLE);
+    } else if size < SHORT_COLLECTION_SIZE {
+        output.push(((size as u8) << 4) | SHORT_TUPLE);
+    } else {
+        output.push(LONG_TUPLE);
+        rlp_encode_int(output, (size - SHORT_COLLECTION_SIZE) as u32);
+    }
+}
+
+/// Append the FATE encoding of a Sophia `option`, which is a variant with the
+/// arities `[0, 1]`. The value (if any) is encoded by `encode_value`.
+pub fn encode_option<T>(
+    output: &mut Vec<u8>,
+    value: Option<T>,
+    encode_value: impl FnOnce(&mut Vec<u8>, T),
+) {
+    output.push(VARIANT);
+    rlp_encode_bytes(output, &[0, 1]);
+    match value {
+        None => {
+            output.push(0);
+            encode_tuple_header(output, 0);
+        }
+        Some(value) => {
+            output.push(1);
+            encode_tuple_header(output, 1);
+            encode_value(output, value);
+        }
+    }
+}
+
+fn rlp_encode_int(output: &mut Vec<u8>, n: u32) {
+    let bytes = n.to_be_bytes();
+    let first_non_zero = bytes
+        .iter()
+        .position(|b| *b != 0)
+        .unwrap_or(bytes.len() - 1);
+    rlp_encode_bytes(output, &bytes[first_non_zero..]);
+}
+
+fn rlp_encode_bytes(output: &mut Vec<u8>, bytes: &[u8]) {
+    match bytes {
+        [b] if *b < 0x80 => output.push(*b),
+        _ if bytes.len() < 56 => {
+            output.push(0x80 + bytes.len() as u8);
+            output.extend_from_slice(bytes);
+        }
+        _ => {
+            let len = (bytes.len() as u32).to_be_bytes();
+            let first_non_zero = len.iter().position(|b| *b != 0).unwrap_or(len.len() - 1);
+            output.push(0xB7 + (len.len() - first_non_zero) as u8);
+            output.extend_from_slice(&len[first_non_zero..]);
+            output.extend_from_slice(bytes);
+        }
+    }
+}
+
+/// Render the elements of a FATE tuple. The whole input must be consumed.
+///
+/// Returns [`AppSW::TypedDataParsingFail`] if the input is not a single tuple
+/// of `arity` elements.
+pub fn render_tuple(data: &[u8], arity: usize) -> Result<Vec<String>, AppSW> {
+    let (size, mut rest) = decode_tuple_header(data)?;
+    if size != arity {
+        return Err(AppSW::TypedDataParsingFail);
+    }
+
+    let mut elements = Vec::with_capacity(size);
+    for _ in 0..size {
+        let mut element = String::new();
+        rest = render_value(rest, &mut element, 1)?;
+        elements.push(element);
+    }
+
+    if !rest.is_empty() {
+        return Err(AppSW::TypedDataParsingFail);
+    }
+
+    Ok(elements)
+}
+
+/// Render a single FATE value. The whole input must be consumed.
+pub fn render(data: &[u8]) -> Result<String, AppSW> {
+    let mut output = String::new();
+    let rest = render_value(data, &mut output, 0)?;
+    if rest.is_empty() {
+        Ok(output)
+    } else {
+        Err(AppSW::TypedDataParsingFail)
+    }
+}
+
+fn decode_tuple_header(data: &[u8]) -> Result<(usize, &[u8]), AppSW> {
+    let (tag, rest) = data.split_first().ok_or(AppSW::TypedDataParsingFail)?;
+    match *tag {
+        EMPTY_TUPLE => Ok((0, rest)),
+        LONG_TUPLE => {
+            let (size, rest) = rlp_decode_int(rest)?;
+            Ok((size + SHORT_COLLECTION_SIZE, rest))
+        }
+        tag if tag & 0x0F == SHORT_TUPLE => Ok(((tag >> 4).into(), rest)),
+        _ => Err(AppSW::TypedDataParsingFail),
+    }
+}
+
+fn render_value<'a>(data: &'a [u8], output: &mut String, depth: usize) -> Result<&'a [u8], AppSW> {
+    if depth > MAX_DEPTH {
+        return Err(AppSW::TypedDataParsingFail);
+    }
+
+    let (tag, rest) = data.split_first().ok_or(AppSW::TypedDataParsingFail)?;
+
+    match *tag {
+        TRUE => {
+            output.push_str("true");
+            Ok(rest)
+        }
+        FALSE => {
+            output.push_str("false");
+            Ok(rest)
+        }
+        EMPTY_STRING => {
+            output.push_str("\"\"");
+            Ok(rest)
+        }
+        LONG_STRING => {
+            let (size, rest) = rlp_decode_int(rest)?;
+            render_string(rest, size + SHORT_STRING_SIZE, output)
+        }
+        POS_BIG_INT | NEG_BIG_INT => {
+            let (bytes, rest) = rlp_decode_bytes(rest)?;
+            if bytes.len() > 32 {
+                return Err(AppSW::TypedDataParsingFail);
+            }
+            let abs = U256::from_big_endian(&bytes)
+                .checked_add(U256::from(SMALL_INT_SIZE))
+                .ok_or(AppSW::TypedDataParsingFail)?;
+            if *tag == NEG_BIG_INT {
+                output.push('-');
+            }
+            output.push_str(&abs.to_string());
+            Ok(rest)
+        }
+        OBJECT => render_object(rest, output),
+        VARIANT => {
+            let (arities, rest) = rlp_decode_bytes(rest)?;
+            let (variant_tag, rest) = rest.split_first().ok_or(AppSW::TypedDataParsingFail)?;
+            let arity = arities
+                .get(usize::from(*variant_tag))
+                .ok_or(AppSW::TypedDataParsingFail)?;
+            let (size, mut rest) = decode_tuple_header(rest)?;
+            if size != usize::from(*arity) {
+                return Err(AppSW::TypedDataParsingFail);
+            }
+            let _ = write!(output, "#{}", variant_tag);
+            if size > 0 {
+                output.push('(');
+                for i in 0..size {
+                    if i > 0 {
+                        output.push_str(", ");
+                    }
+                    rest = render_value(rest, output, depth + 1)?;
+                }
+                output.push(')');
+            }
+            Ok(rest)
+        }
+        MAP => {
+            let (size, mut rest) = rlp_decode_int(rest)?;
+            output.push('{');
+            for i in 0..size {
+                if i > 0 {
+                    output.push_str(", ");
+                }
+                rest = render_value(rest, output, depth + 1)?;
+                output.push_str(": ");
+                rest = render_value(rest, output, depth + 1)?;
+            }
+            output.push('}');
+            Ok(rest)
+        }
+        LONG_LIST => {
+            let (size, rest) = rlp_decode_int(rest)?;
+            render_sequence(
+                rest,
+                size + SHORT_COLLECTION_SIZE,
+                ('[', ']'),
+                output,
+                depth,
+            )
+        }
+        EMPTY_TUPLE | LONG_TUPLE => {
+            let (size, rest) = decode_tuple_header(data)?;
+            render_sequence(rest, size, ('(', ')'), output, depth)
+        }
+        tag if tag & 1 == 0 => {
+            // Small integer: the highest bit is the sign, then 6 bits of value
+            if tag & 0x80 != 0 {
+                output.push('-');
+            }
+            let _ = write!(output, "{}", (tag >> 1) & 0x3F);
+            Ok(rest)
+        }
+        tag if tag & 0b11 == SHORT_STRING => render_string(rest, (tag >> 2).into(), output),
+        tag if tag & 0x0F == SHORT_LIST => {
+            render_sequence(rest, (tag >> 4).into(), ('[', ']'), output, depth)
+        }
+        tag if tag & 0x0F == SHORT_TUPLE => {
+            render_sequence(rest, (tag >> 4).into(), ('(', ')'), output, depth)
+        }
+        _ => Err(AppSW::TypedDataParsingFail),
+    }
+}
+
+fn render_sequence<'a>(
+    mut data: &'a [u8],
+    size: usize,
+    (open, close): (char, char),
+    output: &mut String,
+    depth: usize,
+) -> Result<&'a [u8], AppSW> {
+    output.push(open);
+    for i in 0..size {
+        if i > 0 {
+            output.push_str(", ");
+        }
+        data = render_value(data, output, depth + 1)?;
+    }
+    output.push(close);
+    Ok(data)
+}
+
+fn render_string<'a>(data: &'a [u8], size: usize, output: &mut String) -> Result<&'a [u8], AppSW> {
+    let (bytes, rest) = data
+        .split_at_checked(size)
+        .ok_or(AppSW::TypedDataParsingFail)?;
+
+    match core::str::from_utf8(bytes) {
+        Ok(s) if s.chars().all(|c| c.is_ascii() && !c.is_ascii_control()) => {
+            output.push('"');
+            output.push_str(s);
+            output.push('"');
+        }
+        _ => {
+            output.push_str("0x");
+            output.push_str(&hex::encode(bytes));
+        }
+    }
+
+    Ok(rest)
+}
+
+fn render_object<'a>(data: &'a [u8], output: &mut String) -> Result<&'a [u8], AppSW> {
+    let (object_type, rest) = data.split_first().ok_or(AppSW::TypedDataParsingFail)?;
+    let (bytes, rest) = rlp_decode_bytes(rest)?;
+
+    let encoding = match *object_type {
+        OTYPE_ADDRESS => Some(AeEncoding::AccountAddress),
+        OTYPE_CONTRACT => Some(AeEncoding::ContractAddress),
+        OTYPE_ORACLE => Some(AeEncoding::OracleAddress),
+        OTYPE_CHANNEL => Some(AeEncoding::Channel),
+        OTYPE_BYTES => None,
+        _ => return Err(AppSW::TypedDataParsingFail),
+    };
+
+    match encoding {
+        Some(encoding) => {
+            if bytes.len() != 32 {
+                return Err(AppSW::TypedDataParsingFail);
+            }
+            output.push_str(&encoding::to_ae_string(&bytes, encoding));
+        }
+        None => {
+            output.push_str("0x");
+            output.push_str(&hex::encode(&bytes));
+        }
+    }
+
+    Ok(rest)
+}
+
+fn rlp_decode_bytes(data: &[u8]) -> Result<(Vec<u8>, &[u8]), AppSW> {
+    let (item, rest) = RlpItem::try_deserialize(data).map_err(|_| AppSW::TypedDataParsingFail)?;
+    let bytes = item.byte_array().map_err(|_| AppSW::TypedDataParsingFail)?;
+    Ok((bytes.to_vec(), rest))
+}
+
+fn rlp_decode_int(data: &[u8]) -> Result<(usize, &[u8]), AppSW> {
+    let (bytes, rest) = rlp_decode_bytes(data)?;
+    if bytes.len() > 2 {
+        // Sizes are bounded by the APDU buffers anyway
+        return Err(AppSW::TypedDataParsingFail);
+    }
+    let n = bytes.iter().fold(0, |n, b| (n << 8) | usize::from(*b));
+    Ok((n, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encoded(encode: impl FnOnce(&mut Vec<u8>)) -> Vec<u8> {
+        let mut output = Vec::new();
+        encode(&mut output);
+        output
+    }
+
+    #[test]
+    fn ints() {
+        assert_eq!(encoded(|o| encode_int(o, 0)), [0x00]);
+        assert_eq!(encoded(|o| encode_int(o, 1)), [0x02]);
+        assert_eq!(encoded(|o| encode_int(o, 63)), [0x7E]);
+        assert_eq!(encoded(|o| encode_int(o, 64)), [POS_BIG_INT, 0x00]);
+        assert_eq!(encoded(|o| encode_int(o, 300)), [POS_BIG_INT, 0x81, 0xEC]);
+    }
+
+    #[test]
+    fn strings() {
+        assert_eq!(encoded(|o| encode_string(o, b"")), [EMPTY_STRING]);
+        assert_eq!(encoded(|o| encode_string(o, b"ae")), [0x09, b'a', b'e']);
+
+        let long = [b'a'; 64];
+        let output = encoded(|o| encode_string(o, &long));
+        assert_eq!(output[..2], [LONG_STRING, 0x00]);
+        assert_eq!(output[2..], long);
+    }
+
+    #[test]
+    fn options() {
+        assert_eq!(
+            encoded(|o| encode_option(o, None::<u32>, encode_int)),
+            [VARIANT, 0x82, 0, 1, 0, EMPTY_TUPLE]
+        );
+        assert_eq!(
+            encoded(|o| encode_option(o, Some(2), encode_int)),
+            [VARIANT, 0x82, 0, 1, 1, 0x1B, 0x04]
+        );
+    }
+
+    #[test]
+    fn render_simple_values() {
+        assert_eq!(render(&[TRUE]).as_deref(), Ok("true"));
+        assert_eq!(render(&[0x7E]).as_deref(), Ok("63"));
+        assert_eq!(render(&[0x82]).as_deref(), Ok("-1"));
+        assert_eq!(render(&[0x09, b'a', b'e']).as_deref(), Ok("\"ae\""));
+        assert_eq!(render(&[0x05, 0x00]).as_deref(), Ok("0x00"));
+        assert_eq!(render(&[0x23, 0x02, 0x04]).as_deref(), Ok("[1, 2]"));
+        assert_eq!(
+            render(&[0x2B, FALSE, EMPTY_STRING]).as_deref(),
+            Ok("(false, \"\")")
+        );
+    }
+
+    #[test]
+    fn render_errors() {
+        assert_eq!(render(&[]), Err(AppSW::TypedDataParsingFail));
+        // Trailing data
+        assert_eq!(render(&[TRUE, TRUE]), Err(AppSW::TypedDataParsingFail));
+        // Truncated string
+        assert_eq!(render(&[0x09, b'a']), Err(AppSW::TypedDataParsingFail));
+        // Too deeply nested lists
+        assert_eq!(render(&[0x13; 16]), Err(AppSW::TypedDataParsingFail));
+    }
+
+    #[test]
+    fn tuples() {
+        assert_eq!(
+            render_tuple(&[0x2B, 0x02, 0x05, b'x'], 2),
+            Ok(Vec::from([String::from("1"), String::from("\"x\"")]))
+        );
+        assert_eq!(
+            render_tuple(&[0x2B, 0x02, 0x05, b'x'], 3),
+            Err(AppSW::TypedDataParsingFail)
+        );
+    }
+}
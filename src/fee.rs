@@ -0,0 +1,64 @@
+//! Minimum fees of transactions, computed like the nodes do.
+//!
+//! A transaction uses the base gas of its type, plus some gas for each of its
+//! bytes, and its fee must pay for this gas at the minimum gas price.
+
+use primitive_types::U256;
+
+/// Gas used by each byte of a transaction.
+pub const GAS_PER_BYTE: u64 = 20;
+/// Minimum gas price, in Aettos.
+pub const MIN_GAS_PRICE: u64 = 1_000_000_000;
+/// Fees that are this many times the minimum or more are most likely a
+/// mistake, or a host trying to steal funds.
+pub const OVERPAID_FACTOR: u64 = 10;
+
+/// How a fee compares to the minimum fee of its transaction.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum FeeCheck {
+    #[default]
+    Ok,
+    /// The transaction will be rejected by the nodes
+    BelowMinimum,
+    Overpaid,
+}
+
+/// Minimum fee of a transaction of `tx_len` bytes, whose type uses
+/// `base_gas`.
+pub fn min_fee(base_gas: u64, tx_len: usize) -> U256 {
+    (U256::from(base_gas) + U256::from(tx_len) * GAS_PER_BYTE) * MIN_GAS_PRICE
+}
+
+pub fn check_fee(fee: U256, min_fee: U256) -> FeeCheck {
+    let overpaid = min_fee
+        .checked_mul(OVERPAID_FACTOR.into())
+        .is_some_and(|limit| fee >= limit);
+    if fee < min_fee {
+        FeeCheck::BelowMinimum
+    } else if overpaid {
+        FeeCheck::Overpaid
+    } else {
+        FeeCheck::Ok
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spend_tx_min_fee() {
+        // Base gas of a SpendTx, and a transaction of 100 bytes
+        assert_eq!(min_fee(15_000, 100), U256::from(17_000u64 * MIN_GAS_PRICE));
+    }
+
+    #[test]
+    fn fee_checks() {
+        let min = U256::from(1000);
+        assert_eq!(check_fee(U256::from(999), min), FeeCheck::BelowMinimum);
+        assert_eq!(check_fee(min, min), FeeCheck::Ok);
+        assert_eq!(check_fee(U256::from(9_999), min), FeeCheck::Ok);
+        assert_eq!(check_fee(U256::from(10_000), min), FeeCheck::Overpaid);
+        assert_eq!(check_fee(U256::MAX, U256::MAX), FeeCheck::Ok);
+    }
+}
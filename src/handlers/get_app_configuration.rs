@@ -0,0 +1,32 @@
+use ledger_device_sdk::io::Comm;
+
+use app_aeternity::config::{AppConfiguration, SETTING_BLIND_SIGNING, SETTING_EXPERT_MODE};
+
+use crate::settings::{Setting, Settings};
+use crate::AppSW;
+
+/// Device model the app was built for.
+const MODEL: &str = if cfg!(target_os = "nanox") {
+    "nanox"
+} else if cfg!(target_os = "nanosplus") {
+    "nanosplus"
+} else if cfg!(target_os = "stax") {
+    "stax"
+} else if cfg!(target_os = "flex") {
+    "flex"
+} else {
+    "unknown"
+};
+
+pub fn handler_get_app_configuration(comm: &mut Comm) -> Result<(), AppSW> {
+    let mut settings = 0;
+    if Settings.is_enabled(Setting::BlindSigning) {
+        settings |= SETTING_BLIND_SIGNING;
+    }
+    if Settings.is_enabled(Setting::ExpertMode) {
+        settings |= SETTING_EXPERT_MODE;
+    }
+
+    comm.append(&AppConfiguration::new(MODEL, settings).encode());
+    Ok(())
+}
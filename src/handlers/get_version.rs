@@ -15,7 +15,7 @@
  *  limitations under the License.
  *****************************************************************************/
 use crate::AppSW;
-use core::str::FromStr;
+use app_aeternity::version::parse_version_string;
 use ledger_device_sdk::io;
 
 pub fn handler_get_version(comm: &mut io::Comm) -> Result<(), AppSW> {
@@ -26,14 +26,3 @@ pub fn handler_get_version(comm: &mut io::Comm) -> Result<(), AppSW> {
         Err(AppSW::VersionParsingFail)
     }
 }
-
-fn parse_version_string(input: &str) -> Option<(u8, u8, u8)> {
-    // Split the input string by '.'.
-    // Input should be of the form "major.minor.patch",
-    // where "major", "minor", and "patch" are integers.
-    let mut parts = input.split('.');
-    let major = u8::from_str(parts.next()?).ok()?;
-    let minor = u8::from_str(parts.next()?).ok()?;
-    let patch = u8::from_str(parts.next()?).ok()?;
-    Some((major, minor, patch))
-}
@@ -0,0 +1,17 @@
+use ledger_device_sdk::io::Comm;
+
+use app_aeternity::network::Network;
+use app_aeternity::requests::ProvideNetworkRequest;
+
+use crate::handlers::sign_tx::TxContext;
+use crate::utils;
+use crate::AppSW;
+
+pub fn handler_provide_network(comm: &mut Comm, ctx: &mut TxContext) -> Result<(), AppSW> {
+    let data = comm.get_data().map_err(|_| AppSW::WrongApduLength)?;
+
+    let descriptor = ProvideNetworkRequest::decode(data)?;
+    let network = Network::from_descriptor(&descriptor, utils::verify_network_descriptor)?;
+    ctx.provide_network(network);
+    Ok(())
+}
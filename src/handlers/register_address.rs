@@ -0,0 +1,21 @@
+use ledger_device_sdk::io::Comm;
+
+use app_aeternity::address_book::Entry;
+use app_aeternity::requests::RegisterAddressRequest;
+
+use crate::app_ui::register_address::ui_display_register_address;
+use crate::storage;
+use crate::AppSW;
+
+pub fn handler_register_address(comm: &mut Comm) -> Result<(), AppSW> {
+    let data = comm.get_data().map_err(|_| AppSW::WrongApduLength)?;
+
+    let RegisterAddressRequest { label, address } = RegisterAddressRequest::decode(data)?;
+    let entry = Entry::new(address, label)?;
+
+    if ui_display_register_address(&entry)? {
+        storage::register_address(&entry)
+    } else {
+        Err(AppSW::Deny)
+    }
+}
@@ -1,25 +1,120 @@
+use alloc::vec::Vec;
+
+use ledger_device_sdk::hash::{blake2::Blake2b_256, HashInit};
 use ledger_device_sdk::io::Comm;
 
-use crate::app_ui::sign_data::ui_display_data;
+use app_aeternity::requests::FirstChunk;
+
+use crate::app_ui::sign_data::{ui_display_data, DataReview};
+use crate::settings::{Setting, Settings};
 use crate::{utils, AppSW};
 
-pub fn handler_sign_data(comm: &mut Comm) -> Result<(), AppSW> {
+/// Data received over several chunks.
+#[derive(Default)]
+pub struct DataContext {
+    account_number: u32,
+    remain_len: usize,
+    hash_mode: bool,
+    /// Whole data, kept only when it is signed as is
+    data: Vec<u8>,
+    /// Hash of all the data chunks
+    blake2b: Blake2b_256,
+    data_len: usize,
+    finished: bool,
+}
+
+impl DataContext {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Whether all the chunks were received and the data was reviewed.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn reset(&mut self) {
+        self.account_number = 0;
+        self.remain_len = 0;
+        self.hash_mode = false;
+        self.data = Vec::new();
+        self.blake2b.reset();
+        self.data_len = 0;
+        self.finished = false;
+    }
+}
+
+/// Sign arbitrary data, either as is or as its Blake2b-256 hash.
+///
+/// The first chunk starts with the account number (4 bytes) and the total
+/// length of the data (4 bytes), followed by the first part of the data. The
+/// following chunks contain the rest of the data.
+///
+/// Signing data is refused unless blind signing is enabled in the settings.
+pub fn handler_sign_data(
+    comm: &mut Comm,
+    first_chunk: bool,
+    hash_mode: bool,
+    ctx: &mut DataContext,
+) -> Result<(), AppSW> {
     let data = comm.get_data().map_err(|_| AppSW::WrongApduLength)?;
 
-    let (account_number_bytes, rest) =
-        data.split_first_chunk::<4>().ok_or(AppSW::MsgWrongLength)?;
-    let account_number = u32::from_be_bytes(*account_number_bytes);
+    let chunk = if first_chunk {
+        ctx.reset();
 
-    let (data_length_bytes, actual_data) =
-        rest.split_first_chunk::<4>().ok_or(AppSW::MsgWrongLength)?;
-    let data_length = usize::from_be_bytes(*data_length_bytes);
+        if !Settings.is_enabled(Setting::BlindSigning) {
+            return Err(AppSW::DataSigningDisabled);
+        }
 
-    if data_length != actual_data.len() {
+        let first_chunk = FirstChunk::decode_data(data, hash_mode)?;
+
+        ctx.account_number = first_chunk.account_number;
+        ctx.remain_len = first_chunk.total_len;
+        ctx.hash_mode = hash_mode;
+        first_chunk.chunk
+    } else if ctx.remain_len == 0 || ctx.hash_mode != hash_mode {
+        // There is no data of this mode waiting for more chunks
+        return Err(AppSW::DataWrongLength);
+    } else {
+        data
+    };
+
+    if chunk.len() > ctx.remain_len {
         return Err(AppSW::DataWrongLength);
     }
+    ctx.blake2b.update(chunk).map_err(|_| AppSW::DataHashFail)?;
+    if !ctx.hash_mode {
+        ctx.data.extend_from_slice(chunk);
+    }
+    ctx.data_len += chunk.len();
+    ctx.remain_len -= chunk.len();
+
+    if ctx.remain_len > 0 {
+        return Ok(());
+    }
+
+    ctx.finished = true;
+
+    let mut hash = [0u8; 32];
+    ctx.blake2b
+        .finalize(&mut hash)
+        .map_err(|_| AppSW::DataHashFail)?;
+
+    let review = if ctx.hash_mode {
+        DataReview::Hash {
+            hash: &hash,
+            data_len: ctx.data_len,
+        }
+    } else {
+        DataReview::Raw {
+            data: &ctx.data,
+            hash: &hash,
+        }
+    };
 
-    if ui_display_data(actual_data)? {
-        let sig = utils::sign(account_number, actual_data).ok_or(AppSW::DataSignFail)?;
+    if ui_display_data(review)? {
+        let data_to_sign: &[u8] = if ctx.hash_mode { &hash } else { &ctx.data };
+        let sig = utils::sign(ctx.account_number, data_to_sign).ok_or(AppSW::DataSignFail)?;
         comm.append(&sig);
         Ok(())
     } else {
@@ -0,0 +1,53 @@
+use alloc::vec::Vec;
+
+use ledger_device_sdk::io::Comm;
+
+use app_aeternity::requests::{DelegationType, SignDelegationRequest};
+
+use crate::app_ui::sign_delegation::ui_display_delegation;
+use crate::utils;
+use crate::AppSW;
+
+/// Sign a delegation of the account to a contract.
+///
+/// See [`SignDelegationRequest`] for the format of the data.
+///
+/// The signed payload is always built on the device, so it can only be one of
+/// the delegation formats accepted by the node.
+pub fn handler_sign_delegation(
+    comm: &mut Comm,
+    delegation_type: DelegationType,
+) -> Result<(), AppSW> {
+    let data = comm.get_data().map_err(|_| AppSW::WrongApduLength)?;
+
+    let SignDelegationRequest {
+        account_number,
+        network_id,
+        contract,
+        name,
+    } = SignDelegationRequest::decode(data, delegation_type)?;
+
+    let name_id = name
+        .map(|name| utils::name_id(name).ok_or(AppSW::DelegationParsingFail))
+        .transpose()?;
+    let account = utils::get_public_key(account_number).ok_or(AppSW::KeyDeriveFail)?;
+
+    let data_to_sign = {
+        let mut data = Vec::new();
+        data.extend_from_slice(network_id.as_bytes());
+        data.extend_from_slice(&account);
+        if let Some(name_id) = &name_id {
+            data.extend_from_slice(name_id);
+        }
+        data.extend_from_slice(contract);
+        data
+    };
+
+    if ui_display_delegation(delegation_type, network_id, &account, contract, name)? {
+        let sig = utils::sign(account_number, &data_to_sign).ok_or(AppSW::DelegationSignFail)?;
+        comm.append(&sig);
+        Ok(())
+    } else {
+        Err(AppSW::Deny)
+    }
+}
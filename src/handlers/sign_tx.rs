@@ -1,44 +1,69 @@
-use alloc::{borrow::ToOwned, string::String, vec::Vec};
+use alloc::string::String;
+use alloc::vec::Vec;
 
 use ledger_device_sdk::hash::{blake2::Blake2b_256, HashInit};
 use ledger_device_sdk::io::Comm;
 use ledger_device_sdk::nbgl::NbglHomeAndSettings;
 
-use primitive_types::U256;
-
-use aerlp::{FromRlpItem, RlpItem};
-
-use crate::app_ui::sign_tx::ui_display_tx;
-use crate::utils::{self, AeEncoding};
+use app_aeternity::batch::Batch;
+use app_aeternity::encoding::{to_ae_string, AeEncoding};
+use app_aeternity::instruction::{Instruction, TxResponse};
+use app_aeternity::network::{self, Network};
+use app_aeternity::requests::{BatchTxChunk, SignTxBatchHeader, SignTxHeader};
+use app_aeternity::response::ChunkedResponse;
+use app_aeternity::session::TxSession;
+use app_aeternity::swap::SwapParams;
+use app_aeternity::tx::{encode_signed_tx, is_unknown_layout, ParsedTx, MAX_SIGNED_TX_LEN};
+use app_aeternity::tx_stream::TxStream;
+
+use crate::app_ui::sign_tx::{ui_display_blind_tx, ui_display_tx, ui_display_tx_batch};
+use crate::settings::{Setting, Settings};
+use crate::storage;
+use crate::utils;
 use crate::AppSW;
 
-const NON_INNER_TX_TAG: u8 = 0x00;
-const INNER_TX_TAG: u8 = 0x01;
-const SPEND_TRANSACTION_TAG: u8 = 0x0c;
-const NETWORK_ID_MAX_LENGTH: usize = 32;
-
-#[derive(Default)]
-pub struct TxFirstChunk {
-    pub recipient: String,
-    pub amount: U256,
-    pub fee: U256,
-    pub payload: String,
-}
-
 #[derive(Default)]
 pub struct TxContext {
+    /// Instructions of the SignTx expected next
+    session: TxSession,
     /// Header data
     account_number: u32,
     remain_tx_len: u32,
     inner_tx: bool,
     network_id: Vec<u8>,
-
-    /// Transaction data in the first chunk
-    tx: TxFirstChunk,
+    /// Response asked for by the host
+    tx_response: TxResponse,
+    /// Whether the transaction is short enough to be kept whole
+    keep_tx_bytes: bool,
+
+    /// Decoder of the chunks, which keeps only the fields to review
+    stream: TxStream,
+    /// Fields of the transaction, once all its chunks were received
+    tx: ParsedTx,
+    /// Tag and version of a transaction whose layout is unknown, which is
+    /// blind-signed instead of being reviewed
+    blind_tx_type: Option<(u8, u8)>,
+    /// AENS name of the recipient, checked against its `nm_` id
+    recipient_name: Option<String>,
+    /// Transactions of a SignTxBatch received so far
+    batch: Option<Batch>,
+    /// Transaction bytes, kept when short enough to build the SignedTx, and
+    /// its hash
+    tx_bytes: Vec<u8>,
+    /// Hash of the last SignedTx, once signed
+    tx_hash: Option<[u8; 32]>,
+    /// SignedTx being sent to the host
+    response: ChunkedResponse,
 
     /// Hash of all transaction's chunks
     blake2b: Blake2b_256,
 
+    /// Parameters of the swap when called by the Exchange app, which the
+    /// transaction must match instead of being reviewed
+    swap: Option<SwapParams>,
+    /// Networks described by the host, kept across transactions
+    networks: Vec<Network>,
+
     pub home: NbglHomeAndSettings,
 }
 
@@ -47,141 +72,377 @@ impl TxContext {
         Default::default()
     }
 
-    // TODO: Fix later when long rlp items are supported
+    /// Context to sign the transaction of a swap.
+    pub fn for_swap(params: SwapParams) -> Self {
+        TxContext {
+            swap: Some(params),
+            ..Default::default()
+        }
+    }
+
+    /// Keep a network described by the host, to show the transactions
+    /// signed for it.
+    pub fn provide_network(&mut self, network: Network) {
+        network::provide(&mut self.networks, network);
+    }
+
+    /// Whether the whole response, including all the parts of a SignedTx,
+    /// was sent.
+    pub fn is_response_sent(&self) -> bool {
+        self.response.is_finished()
+    }
+
+    /// Hash of the SignedTx of the transaction just signed, when it was short
+    /// enough to be kept.
+    pub fn tx_hash(&self) -> Option<[u8; 32]> {
+        self.tx_hash
+    }
+
+    /// Whether no more chunk of the transaction is expected.
     pub fn is_finished(&self) -> bool {
-        true
+        !matches!(self.session, TxSession::Chunks { .. })
+    }
+
+    /// Forget the partial state of a SignTx when another instruction is
+    /// received, so that instructions cannot be interleaved with its chunks.
+    pub fn end_session_unless_tx(&mut self, ins: &Instruction) {
+        if !TxSession::is_tx_instruction(ins) {
+            self.reset();
+        }
+    }
+
+    /// Sign the hash of a transaction for the network of the context.
+    fn sign_hash(&self, hash: &[u8; 32]) -> Result<[u8; 64], AppSW> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&self.network_id);
+        if self.inner_tx {
+            data.extend_from_slice("-inner_tx".as_bytes());
+        }
+        data.extend_from_slice(hash);
+        utils::sign(self.account_number, &data).ok_or(AppSW::TxSignFail)
     }
 
     pub fn reset(&mut self) {
+        self.session = TxSession::Idle;
         self.account_number = 0;
         self.remain_tx_len = 0;
         self.inner_tx = false;
         self.network_id = Vec::new();
+        self.tx_response = TxResponse::Signature;
+        self.keep_tx_bytes = false;
+        self.stream = Default::default();
         self.tx = Default::default();
+        self.blind_tx_type = None;
+        self.recipient_name = None;
+        self.batch = None;
+        self.tx_bytes = Vec::new();
+        self.tx_hash = None;
+        self.response = Default::default();
         self.blake2b.reset();
     }
+}
 
-    fn parse_header_data<'a>(&mut self, data: &'a [u8]) -> Result<&'a [u8], AppSW> {
-        let (account_number_bytes, rest) =
-            data.split_first_chunk::<4>().ok_or(AppSW::TxParsingFail)?;
-        let (tx_len_bytes, rest) = rest.split_first_chunk::<4>().ok_or(AppSW::TxParsingFail)?;
+pub fn handler_sign_tx(
+    comm: &mut Comm,
+    first_chunk: bool,
+    response: TxResponse,
+    ctx: &mut TxContext,
+) -> Result<(), AppSW> {
+    let ins = Instruction::SignTx {
+        first_chunk,
+        response,
+    };
+    let result = ctx
+        .session
+        .check(&ins)
+        .and_then(|_| sign_tx(comm, first_chunk, response, ctx));
+
+    // A failed chunk ends the transaction
+    match result {
+        Err(_) => ctx.reset(),
+        // The session already counted the chunk
+        Ok(()) if ctx.remain_tx_len > 0 => {}
+        Ok(()) if !ctx.response.is_finished() => ctx.session = TxSession::Response,
+        Ok(()) => ctx.session = TxSession::Idle,
+    }
+    result
+}
 
-        let (inner_tx_byte, rest) = rest.split_first().ok_or(AppSW::TxParsingFail)?;
-        if [INNER_TX_TAG, NON_INNER_TX_TAG].contains(inner_tx_byte) {
-            self.inner_tx = *inner_tx_byte == INNER_TX_TAG;
-        } else {
-            return Err(AppSW::TxParsingFail);
-        }
+fn sign_tx(
+    comm: &mut Comm,
+    first_chunk: bool,
+    response: TxResponse,
+    ctx: &mut TxContext,
+) -> Result<(), AppSW> {
+    let data = comm.get_data().map_err(|_| AppSW::WrongApduLength)?;
 
-        let (network_id_len_byte, rest) = rest.split_first().ok_or(AppSW::TxParsingFail)?;
-        let network_id_len: usize = (*network_id_len_byte).into();
-        if network_id_len > NETWORK_ID_MAX_LENGTH {
-            return Err(AppSW::TxParsingFail);
+    let tx_bytes = if first_chunk {
+        ctx.reset();
+        let header = SignTxHeader::decode(data)?;
+        if let Some(params) = &ctx.swap {
+            params.check_header(&header)?;
         }
+        // A transaction sent back signed, or with the hash of the SignedTx, is
+        // kept whole until it is signed
+        if response.has_tx_hash() && header.tx_len as usize > MAX_SIGNED_TX_LEN {
+            return Err(AppSW::TxWrongLength);
+        }
+        ctx.account_number = header.account_number;
+        ctx.inner_tx = header.inner_tx;
+        ctx.network_id = header.network_id.to_vec();
+        ctx.tx_response = response;
+        ctx.keep_tx_bytes = header.tx_len as usize <= MAX_SIGNED_TX_LEN;
+        ctx.recipient_name = header.recipient_name.map(String::from);
+        // Transactions of a later hard fork can only be blind-signed
+        let allow_unknown_layout = ctx.swap.is_none() && Settings.is_enabled(Setting::BlindSigning);
+        ctx.stream = TxStream::new(header.tx_len as usize, allow_unknown_layout);
+        // The header checked that the chunk is not longer than the transaction
+        ctx.remain_tx_len = header.tx_len;
+        ctx.session = TxSession::first_chunk(response, false, header.tx_len, header.tx_chunk.len());
+        header.tx_chunk
+    } else {
+        // The session checked that chunks follow a first chunk, and they must
+        // be full until the last one, which must not go beyond the length of
+        // the transaction
+        ctx.session = ctx.session.next_chunk(data.len())?;
+        if data.len() > ctx.remain_tx_len as usize {
+            return Err(AppSW::TxUnexpectedChunk);
+        }
+        data
+    };
 
-        let (network_id, rest) = rest
-            .split_at_checked(network_id_len)
-            .ok_or(AppSW::TxParsingFail)?;
-
-        self.account_number = u32::from_be_bytes(*account_number_bytes);
-        self.remain_tx_len = u32::from_be_bytes(*tx_len_bytes);
-        self.network_id = network_id.to_vec();
-
-        Ok(rest)
+    // Only the fields to review are kept, the rest is hashed as it comes
+    ctx.stream.feed(tx_bytes)?;
+    ctx.blake2b
+        .update(tx_bytes)
+        .map_err(|_| AppSW::TxHashFail)?;
+    if ctx.keep_tx_bytes {
+        ctx.tx_bytes.extend_from_slice(tx_bytes);
+    }
+    ctx.remain_tx_len -= tx_bytes.len() as u32;
+    if ctx.remain_tx_len > 0 {
+        return Ok(());
     }
 
-    fn parse_tx_first_chunk(&mut self, data: &[u8]) -> Result<(), AppSW> {
-        let (rlp_item, _remain) =
-            RlpItem::try_deserialize(data).map_err(|_| AppSW::TxParsingFail)?;
-        // TODO: the rlp item has a length, assert that it's ok
-        // TODO: is it fine if something remains? or should I check if remain.empty() == true
-
-        let list = rlp_item.list().map_err(|_| AppSW::TxParsingFail)?;
+    ctx.tx = match ctx
+        .stream
+        .finish()
+        .and_then(|tx| ParsedTx::from_decoded(&tx))
+    {
+        Ok(tx) => tx,
+        // The VM of a contract is only checked once the whole transaction
+        // was decoded
+        Err(err)
+            if is_unknown_layout(err)
+                && ctx.swap.is_none()
+                && Settings.is_enabled(Setting::BlindSigning) =>
+        {
+            ctx.blind_tx_type = Some(ctx.stream.tx_type().ok_or(AppSW::TxParsingFail)?);
+            Default::default()
+        }
+        Err(err) => return Err(err),
+    };
+    // The inner transaction of a GA meta transaction cannot be shown
+    if ctx.tx.hides_inner_tx() && !Settings.is_enabled(Setting::BlindSigning) {
+        return Err(AppSW::TxBlindSigningDisabled);
+    }
+    if let Some(name) = ctx.recipient_name.take() {
+        let recipient = ctx.tx.recipient.as_deref().ok_or(AppSW::TxNameMismatch)?;
+        check_recipient_name(&name, recipient)?;
+        ctx.recipient_name = Some(name.to_ascii_lowercase());
+    }
 
-        if u8::from_rlp_item(&list[0]).map_err(|_| AppSW::TxParsingFail)? != SPEND_TRANSACTION_TAG {
-            // TODO: this should be changed later. non-spend txns should be signed
-            //       but they should not be treated like spend txns
-            // TODO: use a better status word for the error
-            return Err(AppSW::Deny);
+    let mut hash: [u8; 32] = [0; 32];
+    ctx.blake2b
+        .finalize(&mut hash)
+        .map_err(|_| AppSW::TxHashFail)?;
+
+    // The header checked that the network id is printable
+    let network_id = core::str::from_utf8(&ctx.network_id).unwrap_or_default();
+    let network = Network::find(network_id, &ctx.networks);
+    let approved = match (&ctx.swap, ctx.blind_tx_type) {
+        // The user already reviewed the swap in the Exchange app
+        (Some(params), _) => params.check_tx(&ctx.tx).map(|_| true)?,
+        (None, Some((tag, version))) => ui_display_blind_tx(tag, version, &network, &hash)?,
+        (None, None) => {
+            let label = ctx.tx.recipient.as_deref().and_then(storage::find_label);
+            let nonce_reused = ctx.tx.nonce.is_some_and(|nonce| {
+                storage::is_nonce_reused(ctx.account_number, &ctx.network_id, nonce)
+            });
+            ui_display_tx(
+                &ctx.tx,
+                &network,
+                ctx.recipient_name.as_deref(),
+                label.as_deref(),
+                nonce_reused,
+            )?
         }
-        let _ = parse_address(&list[2].byte_array().map_err(|_| AppSW::TxParsingFail)?)?;
-        let recipient = parse_address(&list[3].byte_array().map_err(|_| AppSW::TxParsingFail)?)?;
-        let amount =
-            U256::from_big_endian(&list[4].byte_array().map_err(|_| AppSW::TxParsingFail)?);
-        let fee = U256::from_big_endian(&list[5].byte_array().map_err(|_| AppSW::TxParsingFail)?);
-        let payload =
-            core::str::from_utf8(&list[8].byte_array().map_err(|_| AppSW::TxParsingFail)?)
-                .unwrap()
-                .to_owned();
-
-        // TODO: extract the rlp items from list in a cleaner way (don't use map_err that many times)
-
-        self.tx = TxFirstChunk {
-            recipient,
-            amount,
-            fee,
-            payload,
-        };
+    };
 
+    if approved {
+        let sig = ctx.sign_hash(&hash)?;
+        if let Some(nonce) = ctx.tx.nonce {
+            storage::record_nonce(ctx.account_number, &ctx.network_id, nonce);
+        }
+        // The hash of the SignedTx identifies the transaction on the chain,
+        // but longer transactions are not kept to compute it
+        let signed = ctx
+            .keep_tx_bytes
+            .then(|| encode_signed_tx(&[sig], &core::mem::take(&mut ctx.tx_bytes)));
+        ctx.tx_hash = signed
+            .as_deref()
+            .map(|signed| utils::blake2b_256(signed).ok_or(AppSW::TxHashFail))
+            .transpose()?;
+        // The header checked that the transaction is kept when its hash is
+        // asked for
+        match (ctx.tx_response, signed, ctx.tx_hash) {
+            (TxResponse::Signature, _, _) => comm.append(&sig),
+            (TxResponse::SignatureAndHash, _, Some(tx_hash)) => {
+                comm.append(&sig);
+                comm.append(&tx_hash);
+            }
+            (TxResponse::SignedTx, Some(signed), Some(tx_hash)) => {
+                ctx.response = ChunkedResponse::new([signed, tx_hash.to_vec()].concat())?;
+                comm.append(&ctx.response.first());
+            }
+            _ => return Err(AppSW::TxWrongLength),
+        }
         Ok(())
+    } else {
+        Err(AppSW::Deny)
     }
 }
 
-pub fn handler_sign_tx(
+pub fn handler_sign_tx_batch(
     comm: &mut Comm,
     first_chunk: bool,
     ctx: &mut TxContext,
 ) -> Result<(), AppSW> {
+    let ins = Instruction::SignTxBatch { first_chunk };
+    let result = ctx
+        .session
+        .check(&ins)
+        .and_then(|_| sign_tx_batch(comm, first_chunk, ctx));
+
+    // A failed chunk ends the whole batch
+    let batch_complete = ctx.batch.as_ref().is_some_and(Batch::is_complete);
+    match result {
+        Err(_) => ctx.reset(),
+        // The session already counted the chunk
+        Ok(()) if !batch_complete => {}
+        Ok(()) if !ctx.response.is_finished() => ctx.session = TxSession::Response,
+        Ok(()) => ctx.session = TxSession::Idle,
+    }
+    result
+}
+
+fn sign_tx_batch(comm: &mut Comm, first_chunk: bool, ctx: &mut TxContext) -> Result<(), AppSW> {
     let data = comm.get_data().map_err(|_| AppSW::WrongApduLength)?;
 
     if first_chunk {
         ctx.reset();
-        let tx_bytes = ctx.parse_header_data(data)?;
-        ctx.parse_tx_first_chunk(tx_bytes)?;
-        ctx.blake2b
-            .update(tx_bytes)
-            .map_err(|_| AppSW::TxHashFail)?;
+        let header = SignTxBatchHeader::decode(data)?;
+        ctx.account_number = header.account_number;
+        ctx.network_id = header.network_id.to_vec();
+        ctx.batch = Some(Batch::new(header.tx_count.into()));
+        // The header is a chunk of its own
+        ctx.session = TxSession::first_chunk(TxResponse::Signature, true, 0, 0);
+        return Ok(());
+    }
+
+    let tx_bytes = if ctx.remain_tx_len == 0 {
+        // Each transaction starts in a new chunk, with its length
+        let chunk = BatchTxChunk::decode(data)?;
+        ctx.stream = TxStream::new(chunk.tx_len as usize, false);
+        ctx.blake2b.reset();
+        ctx.remain_tx_len = chunk.tx_len;
+        ctx.session = TxSession::first_chunk(
+            TxResponse::Signature,
+            true,
+            chunk.tx_len,
+            chunk.tx_chunk.len(),
+        );
+        chunk.tx_chunk
     } else {
-        ctx.blake2b.update(data).map_err(|_| AppSW::TxHashFail)?;
+        ctx.session = ctx.session.next_chunk(data.len())?;
+        if data.len() > ctx.remain_tx_len as usize {
+            return Err(AppSW::TxUnexpectedChunk);
+        }
+        data
+    };
+
+    ctx.stream.feed(tx_bytes)?;
+    ctx.blake2b
+        .update(tx_bytes)
+        .map_err(|_| AppSW::TxHashFail)?;
+    ctx.remain_tx_len -= tx_bytes.len() as u32;
+    if ctx.remain_tx_len > 0 {
         return Ok(());
     }
 
-    if ui_display_tx(&ctx.tx)? {
-        let mut hash: [u8; 32] = [0; 32];
-        ctx.blake2b
-            .finalize(&mut hash)
-            .map_err(|_| AppSW::TxHashFail)?;
-        let data_to_sign = {
-            let mut data = Vec::new();
-            data.extend_from_slice(&ctx.network_id);
-            if ctx.inner_tx {
-                data.extend_from_slice("-inner_tx".as_bytes());
-            }
-            data.extend_from_slice(&hash);
-            data
-        };
-        let sig = utils::sign(ctx.account_number, &data_to_sign).ok_or(AppSW::TxSignFail)?;
-        comm.append(&sig);
+    // Every transaction is decoded and checked like a single SpendTx
+    let tx = ParsedTx::from_decoded(&ctx.stream.finish()?)?;
+    let mut hash: [u8; 32] = [0; 32];
+    ctx.blake2b
+        .finalize(&mut hash)
+        .map_err(|_| AppSW::TxHashFail)?;
+    let batch = ctx.batch.as_mut().ok_or(AppSW::BadState)?;
+    batch.add(tx, hash)?;
+    if !batch.is_complete() {
+        return Ok(());
+    }
+
+    // The header checked that the network id is printable
+    let network_id = core::str::from_utf8(&ctx.network_id).unwrap_or_default();
+    let network = Network::find(network_id, &ctx.networks);
+    let labels = batch
+        .payments
+        .iter()
+        .map(|payment| storage::find_label(&payment.recipient))
+        .collect::<Vec<_>>();
+    // The nonces increase, so only the first one can have been signed
+    let nonce_reused = batch
+        .first_nonce
+        .is_some_and(|nonce| storage::is_nonce_reused(ctx.account_number, &ctx.network_id, nonce));
+    if !ui_display_tx_batch(batch, &network, &labels, nonce_reused)? {
+        return Err(AppSW::Deny);
+    }
+
+    let batch = ctx.batch.as_ref().ok_or(AppSW::BadState)?;
+    let mut signatures = Vec::with_capacity(64 * batch.payments.len());
+    for payment in &batch.payments {
+        signatures.extend_from_slice(&ctx.sign_hash(&payment.hash)?);
+    }
+    if let Some(nonce) = batch.last_nonce {
+        storage::record_nonce(ctx.account_number, &ctx.network_id, nonce);
+    }
+    ctx.response = ChunkedResponse::new(signatures)?;
+    comm.append(&ctx.response.first());
+    Ok(())
+}
+
+/// Make sure that the name given by the host is the name of the `nm_`
+/// recipient, by hashing it like AENS does.
+fn check_recipient_name(name: &str, recipient: &str) -> Result<(), AppSW> {
+    let name_id = utils::name_id(name).ok_or(AppSW::TxNameMismatch)?;
+    if to_ae_string(&name_id, AeEncoding::Name) == recipient {
         Ok(())
     } else {
-        Err(AppSW::Deny)
+        Err(AppSW::TxNameMismatch)
     }
 }
 
-fn parse_address(address: &[u8]) -> Result<String, AppSW> {
-    let (prefix_byte, rest) = address.split_first().ok_or(AppSW::TxParsingFail)?;
-
-    let prefix = match *prefix_byte {
-        1 => AeEncoding::AccountAddress,
-        2 => AeEncoding::Name,
-        3 => AeEncoding::Commitment,
-        4 => AeEncoding::OracleAddress,
-        5 => AeEncoding::ContractAddress,
-        6 => AeEncoding::Channel,
-        _ => Err(AppSW::TxParsingFail)?,
-    };
-
-    let address_bytes: [u8; 32] = rest.try_into().map_err(|_| AppSW::TxParsingFail)?;
-
-    Ok(utils::to_ae_string(&address_bytes, prefix))
+/// Send the next part of the SignedTx built by the last approved SignTx, or
+/// of the signatures of the last approved SignTxBatch.
+pub fn handler_get_signed_tx_chunk(comm: &mut Comm, ctx: &mut TxContext) -> Result<(), AppSW> {
+    if let Err(err) = ctx.session.check(&Instruction::GetSignedTxChunk) {
+        ctx.reset();
+        return Err(err);
+    }
+    let chunk = ctx.response.next_part().ok_or(AppSW::BadState)?;
+    comm.append(chunk);
+    if ctx.response.is_finished() {
+        ctx.session = TxSession::Idle;
+    }
+    Ok(())
 }
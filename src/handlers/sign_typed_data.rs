@@ -0,0 +1,162 @@
+use alloc::{string::String, vec::Vec};
+
+use ledger_device_sdk::io::Comm;
+use serde::Deserialize;
+use serde_json_core::heapless;
+
+use app_aeternity::fate;
+use app_aeternity::requests::{printable_str, Domain, FirstChunk, TypedData};
+
+use crate::app_ui::sign_typed_data::ui_display_typed_data;
+use crate::{utils, AppSW};
+
+const MAX_RECORD_FIELDS: usize = 16;
+
+/// Typed data received over several chunks.
+#[derive(Default)]
+pub struct TypedDataContext {
+    account_number: u32,
+    remain_len: usize,
+    data: Vec<u8>,
+    finished: bool,
+}
+
+impl TypedDataContext {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Whether all the chunks were received and the typed data was reviewed.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn reset(&mut self) {
+        self.account_number = 0;
+        self.remain_len = 0;
+        self.data = Vec::new();
+        self.finished = false;
+    }
+}
+
+#[derive(Deserialize)]
+struct AciRecord<'a> {
+    #[serde(borrow)]
+    record: heapless::Vec<AciField<'a>, MAX_RECORD_FIELDS>,
+}
+
+#[derive(Deserialize)]
+struct AciField<'a> {
+    name: &'a str,
+}
+
+/// Sign typed data the same way `signTypedData` does in the JS SDK.
+///
+/// The first chunk starts with the account number (4 bytes) and the total
+/// length of the typed data (4 bytes). See [`TypedData`] for the format of the
+/// typed data itself.
+pub fn handler_sign_typed_data(
+    comm: &mut Comm,
+    first_chunk: bool,
+    ctx: &mut TypedDataContext,
+) -> Result<(), AppSW> {
+    let data = comm.get_data().map_err(|_| AppSW::WrongApduLength)?;
+
+    let chunk = if first_chunk {
+        ctx.reset();
+
+        let first_chunk = FirstChunk::decode_typed_data(data)?;
+
+        ctx.account_number = first_chunk.account_number;
+        ctx.remain_len = first_chunk.total_len;
+        first_chunk.chunk
+    } else if ctx.remain_len == 0 {
+        // There is no typed data waiting for more chunks
+        return Err(AppSW::TypedDataWrongLength);
+    } else {
+        data
+    };
+
+    if chunk.len() > ctx.remain_len {
+        return Err(AppSW::TypedDataWrongLength);
+    }
+    ctx.data.extend_from_slice(chunk);
+    ctx.remain_len -= chunk.len();
+
+    if ctx.remain_len > 0 {
+        return Ok(());
+    }
+
+    ctx.finished = true;
+    let typed_data = core::mem::take(&mut ctx.data);
+    let TypedData {
+        domain,
+        aci,
+        data: fate_data,
+    } = TypedData::decode(&typed_data)?;
+
+    let fields = match parse_aci_record(aci)? {
+        Some(names) => names
+            .iter()
+            .copied()
+            .zip(fate::render_tuple(fate_data, names.len())?)
+            .collect::<Vec<(&str, String)>>(),
+        None => Vec::from([("Data", fate::render(fate_data)?)]),
+    };
+
+    let hash = hash_typed_data(&domain, aci, fate_data)?;
+
+    if ui_display_typed_data(&domain, &fields)? {
+        let sig = utils::sign(ctx.account_number, &hash).ok_or(AppSW::TypedDataSignFail)?;
+        comm.append(&sig);
+        Ok(())
+    } else {
+        Err(AppSW::Deny)
+    }
+}
+
+/// Field names of the type if it's a record, `None` for any other type.
+fn parse_aci_record(aci: &[u8]) -> Result<Option<Vec<&str>>, AppSW> {
+    match serde_json_core::from_slice::<AciRecord>(aci) {
+        Ok((record, consumed)) if consumed == aci.len() => record
+            .record
+            .iter()
+            .map(|field| printable_str(field.name.as_bytes()).ok_or(AppSW::TypedDataParsingFail))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// Compute `blake2b(hashDomain(domain) + hashJson(aci) + blake2b(data))`.
+///
+/// The ACI is hashed as received, so the host must send it in the canonical
+/// JSON form (RFC 8785) used by `hashJson`.
+fn hash_typed_data(domain: &Domain, aci: &[u8], data: &[u8]) -> Result<[u8; 32], AppSW> {
+    // The domain is encoded as a record of options:
+    // { name: option(string), version: option(int),
+    //   networkId: option(string), contractAddress: option(contract_pubkey) }
+    let mut encoded_domain = Vec::new();
+    fate::encode_tuple_header(&mut encoded_domain, 4);
+    fate::encode_option(&mut encoded_domain, domain.name, |output, name| {
+        fate::encode_string(output, name.as_bytes())
+    });
+    fate::encode_option(&mut encoded_domain, domain.version, fate::encode_int);
+    fate::encode_option(&mut encoded_domain, domain.network_id, |output, id| {
+        fate::encode_string(output, id.as_bytes())
+    });
+    fate::encode_option(
+        &mut encoded_domain,
+        domain.contract_address,
+        fate::encode_contract,
+    );
+
+    let to_hash = [
+        utils::blake2b_256(&encoded_domain).ok_or(AppSW::TypedDataHashFail)?,
+        utils::blake2b_256(aci).ok_or(AppSW::TypedDataHashFail)?,
+        utils::blake2b_256(data).ok_or(AppSW::TypedDataHashFail)?,
+    ]
+    .concat();
+
+    utils::blake2b_256(&to_hash).ok_or(AppSW::TypedDataHashFail)
+}
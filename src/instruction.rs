@@ -0,0 +1,258 @@
+//! Instructions received through APDUs.
+
+use crate::requests::DelegationType;
+use crate::AppSW;
+
+/// Class of all the APDUs of the app.
+pub const CLA: u8 = 0xe0;
+
+// Instruction codes
+pub const INS_GET_ADDRESS: u8 = 0x02;
+pub const INS_SIGN_TX: u8 = 0x04;
+pub const INS_GET_VERSION: u8 = 0x06;
+pub const INS_SIGN_MSG: u8 = 0x08;
+pub const INS_SIGN_DATA: u8 = 0x0a;
+pub const INS_SIGN_TYPED_DATA: u8 = 0x0c;
+pub const INS_SIGN_DELEGATION: u8 = 0x0e;
+pub const INS_GET_APP_CONFIGURATION: u8 = 0x10;
+pub const INS_REGISTER_ADDRESS: u8 = 0x12;
+pub const INS_PROVIDE_NETWORK: u8 = 0x14;
+pub const INS_ABORT: u8 = 0x16;
+pub const INS_SIGN_TX_BATCH: u8 = 0x18;
+
+// P1 for GetAddress
+pub const P1_CONFIRM_NOT_NEEDED: u8 = 0x00;
+pub const P1_CONFIRM_NEEDED: u8 = 0x01;
+// P1 for SignTx, SignTxBatch, SignData and SignTypedData
+pub const P1_SIGN_TX_START: u8 = 0x00;
+pub const P1_SIGN_TX_MORE: u8 = 0x80;
+// P1 for SignTx, to get the next part of a signed transaction
+pub const P1_SIGN_TX_NEXT_RESPONSE: u8 = 0x01;
+// P2 for SignTx
+pub const P2_SIGN_TX_SIGNATURE: u8 = 0x00;
+pub const P2_SIGN_TX_SIGNED_TX: u8 = 0x01;
+pub const P2_SIGN_TX_SIGNATURE_AND_HASH: u8 = 0x02;
+// P2 for SignData
+pub const P2_SIGN_DATA_RAW: u8 = 0x00;
+pub const P2_SIGN_DATA_HASH: u8 = 0x01;
+// P1 for SignDelegation
+pub const P1_DELEGATION_PRECLAIM: u8 = 0x00;
+pub const P1_DELEGATION_ORACLE: u8 = 0x01;
+pub const P1_DELEGATION_NAME: u8 = 0x02;
+
+/// Possible input commands received through APDUs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Instruction {
+    GetVersion,
+    GetAppConfiguration,
+    GetAddress {
+        confirm_needed: bool,
+    },
+    SignData {
+        first_chunk: bool,
+        hash_mode: bool,
+    },
+    /// Sign a transaction, sent in chunks that are all full
+    /// ([`CHUNK_LEN`](crate::session::CHUNK_LEN) bytes) apart from the first
+    /// and the last one. P2 chooses the response to its last chunk.
+    SignTx {
+        first_chunk: bool,
+        response: TxResponse,
+    },
+    /// Get the next part of a SignedTx, or of the signatures of a batch, that
+    /// did not fit in one response
+    GetSignedTxChunk,
+    SignMsg,
+    SignTypedData {
+        first_chunk: bool,
+    },
+    SignDelegation {
+        delegation_type: DelegationType,
+    },
+    /// Add a trusted recipient to the address book, after the user confirmed
+    /// it
+    RegisterAddress,
+    /// Describe a network that is not known by the app, with a descriptor
+    /// signed by the trusted key
+    ProvideNetwork,
+    /// Forget the partial state of the instructions sent over several APDUs
+    Abort,
+    /// Sign several SpendTx after a single review of all of them. Each
+    /// transaction is chunked like the ones of SignTx.
+    SignTxBatch {
+        first_chunk: bool,
+    },
+}
+
+/// Response to the last chunk of a SignTx, chosen with P2.
+///
+/// The hash of the SignedTx needs the whole transaction, so the transactions
+/// longer than [`MAX_SIGNED_TX_LEN`](crate::tx::MAX_SIGNED_TX_LEN) are refused
+/// with [`AppSW::TxWrongLength`] when it is asked for.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TxResponse {
+    /// [`P2_SIGN_TX_SIGNATURE`]: the signature (64 bytes)
+    #[default]
+    Signature,
+    /// [`P2_SIGN_TX_SIGNATURE_AND_HASH`]: the signature followed by the hash
+    /// of the SignedTx (32 bytes)
+    SignatureAndHash,
+    /// [`P2_SIGN_TX_SIGNED_TX`]: the RLP-encoded SignedTx followed by its
+    /// hash, in parts of a [`ChunkedResponse`](crate::response::ChunkedResponse)
+    SignedTx,
+}
+
+impl TxResponse {
+    /// Whether the response holds the hash of the SignedTx.
+    pub fn has_tx_hash(&self) -> bool {
+        *self != TxResponse::Signature
+    }
+}
+
+impl Instruction {
+    /// APDU parsing logic.
+    ///
+    /// Parses INS, P1 and P2 bytes to build an [`Instruction`]. P1 and P2 are translated to
+    /// strongly typed variables depending on the APDU instruction code. Invalid INS, P1 or P2
+    /// values result in errors with a status word, which are automatically sent to the host by the
+    /// SDK.
+    ///
+    /// This design allows a clear separation of the APDU parsing logic and commands handling.
+    ///
+    /// Note that CLA is not checked here. Instead the method `Comm::set_expected_cla` is used in
+    /// `sample_main` to have this verification automatically performed by the SDK.
+    pub fn from_apdu_header(ins: u8, p1: u8, p2: u8) -> Result<Self, AppSW> {
+        match (ins, p1, p2) {
+            (INS_GET_ADDRESS, P1_CONFIRM_NOT_NEEDED | P1_CONFIRM_NEEDED, 0) => {
+                Ok(Instruction::GetAddress {
+                    confirm_needed: p1 == P1_CONFIRM_NEEDED,
+                })
+            }
+            (
+                INS_SIGN_TX,
+                P1_SIGN_TX_START | P1_SIGN_TX_MORE,
+                P2_SIGN_TX_SIGNATURE | P2_SIGN_TX_SIGNED_TX | P2_SIGN_TX_SIGNATURE_AND_HASH,
+            ) => Ok(Instruction::SignTx {
+                first_chunk: p1 == P1_SIGN_TX_START,
+                response: match p2 {
+                    P2_SIGN_TX_SIGNED_TX => TxResponse::SignedTx,
+                    P2_SIGN_TX_SIGNATURE_AND_HASH => TxResponse::SignatureAndHash,
+                    _ => TxResponse::Signature,
+                },
+            }),
+            (INS_SIGN_TX, P1_SIGN_TX_NEXT_RESPONSE, 0) => Ok(Instruction::GetSignedTxChunk),
+            (INS_GET_VERSION, 0, 0) => Ok(Instruction::GetVersion),
+            (INS_GET_APP_CONFIGURATION, 0, 0) => Ok(Instruction::GetAppConfiguration),
+            (INS_SIGN_MSG, 0, 0) => Ok(Instruction::SignMsg),
+            (
+                INS_SIGN_DATA,
+                P1_SIGN_TX_START | P1_SIGN_TX_MORE,
+                P2_SIGN_DATA_RAW | P2_SIGN_DATA_HASH,
+            ) => Ok(Instruction::SignData {
+                first_chunk: p1 == P1_SIGN_TX_START,
+                hash_mode: p2 == P2_SIGN_DATA_HASH,
+            }),
+            (INS_SIGN_TYPED_DATA, P1_SIGN_TX_START | P1_SIGN_TX_MORE, 0) => {
+                Ok(Instruction::SignTypedData {
+                    first_chunk: p1 == P1_SIGN_TX_START,
+                })
+            }
+            (INS_SIGN_DELEGATION, P1_DELEGATION_PRECLAIM, 0) => Ok(Instruction::SignDelegation {
+                delegation_type: DelegationType::Preclaim,
+            }),
+            (INS_SIGN_DELEGATION, P1_DELEGATION_ORACLE, 0) => Ok(Instruction::SignDelegation {
+                delegation_type: DelegationType::Oracle,
+            }),
+            (INS_SIGN_DELEGATION, P1_DELEGATION_NAME, 0) => Ok(Instruction::SignDelegation {
+                delegation_type: DelegationType::Name,
+            }),
+            (INS_REGISTER_ADDRESS, 0, 0) => Ok(Instruction::RegisterAddress),
+            (INS_PROVIDE_NETWORK, 0, 0) => Ok(Instruction::ProvideNetwork),
+            (INS_ABORT, 0, 0) => Ok(Instruction::Abort),
+            (INS_SIGN_TX_BATCH, P1_SIGN_TX_START | P1_SIGN_TX_MORE, 0) => {
+                Ok(Instruction::SignTxBatch {
+                    first_chunk: p1 == P1_SIGN_TX_START,
+                })
+            }
+            (
+                INS_GET_ADDRESS
+                | INS_SIGN_TX
+                | INS_GET_VERSION
+                | INS_SIGN_DATA
+                | INS_SIGN_TYPED_DATA
+                | INS_SIGN_DELEGATION
+                | INS_GET_APP_CONFIGURATION
+                | INS_REGISTER_ADDRESS
+                | INS_PROVIDE_NETWORK
+                | INS_ABORT
+                | INS_SIGN_TX_BATCH,
+                _,
+                _,
+            ) => Err(AppSW::WrongP1P2),
+            (_, _, _) => Err(AppSW::InsNotSupported),
+        }
+    }
+}
+
+#[cfg(feature = "device")]
+impl TryFrom<ledger_device_sdk::io::ApduHeader> for Instruction {
+    type Error = AppSW;
+
+    fn try_from(value: ledger_device_sdk::io::ApduHeader) -> Result<Self, Self::Error> {
+        Instruction::from_apdu_header(value.ins, value.p1, value.p2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sign_tx_modes() {
+        assert_eq!(
+            Instruction::from_apdu_header(INS_SIGN_TX, P1_SIGN_TX_START, P2_SIGN_TX_SIGNATURE),
+            Ok(Instruction::SignTx {
+                first_chunk: true,
+                response: TxResponse::Signature
+            })
+        );
+        assert_eq!(
+            Instruction::from_apdu_header(INS_SIGN_TX, P1_SIGN_TX_MORE, P2_SIGN_TX_SIGNED_TX),
+            Ok(Instruction::SignTx {
+                first_chunk: false,
+                response: TxResponse::SignedTx
+            })
+        );
+        assert_eq!(
+            Instruction::from_apdu_header(
+                INS_SIGN_TX,
+                P1_SIGN_TX_START,
+                P2_SIGN_TX_SIGNATURE_AND_HASH
+            ),
+            Ok(Instruction::SignTx {
+                first_chunk: true,
+                response: TxResponse::SignatureAndHash
+            })
+        );
+        assert_eq!(
+            Instruction::from_apdu_header(INS_SIGN_TX, P1_SIGN_TX_NEXT_RESPONSE, 0),
+            Ok(Instruction::GetSignedTxChunk)
+        );
+        assert_eq!(
+            Instruction::from_apdu_header(INS_SIGN_TX, P1_SIGN_TX_NEXT_RESPONSE, 1),
+            Err(AppSW::WrongP1P2)
+        );
+        assert_eq!(
+            Instruction::from_apdu_header(INS_SIGN_TX, P1_SIGN_TX_START, 3),
+            Err(AppSW::WrongP1P2)
+        );
+        assert_eq!(
+            Instruction::from_apdu_header(INS_SIGN_TX_BATCH, P1_SIGN_TX_MORE, 0),
+            Ok(Instruction::SignTxBatch { first_chunk: false })
+        );
+        assert_eq!(
+            Instruction::from_apdu_header(INS_SIGN_TX_BATCH, P1_SIGN_TX_START, 1),
+            Err(AppSW::WrongP1P2)
+        );
+    }
+}
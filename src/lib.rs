@@ -0,0 +1,236 @@
+/*****************************************************************************
+ *   Ledger App Boilerplate Rust.
+ *   (c) 2023 Ledger SAS.
+ *
+ *  Licensed under the Apache License, Version 2.0 (the "License");
+ *  you may not use this file except in compliance with the License.
+ *  You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ *  Unless required by applicable law or agreed to in writing, software
+ *  distributed under the License is distributed on an "AS IS" BASIS,
+ *  WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ *  See the License for the specific language governing permissions and
+ *  limitations under the License.
+ *****************************************************************************/
+
+//! Parts of the app that do not depend on the device SDK.
+//!
+//! They are built with the app for the device, and can also be built and
+//! tested on the host with the `std` feature:
+//!
+//! ```sh
+//! cargo +stable test-host
+//! ```
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+pub mod address_book;
+pub mod amount;
+pub mod batch;
+pub mod config;
+pub mod data;
+pub mod encoding;
+pub mod fate;
+pub mod fee;
+pub mod instruction;
+pub mod message;
+pub mod network;
+pub mod nonce;
+pub mod requests;
+pub mod response;
+pub mod session;
+pub mod swap;
+pub mod tx;
+pub mod tx_stream;
+pub mod version;
+
+// Application status words.
+#[repr(u16)]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum AppSW {
+    Deny = 0x6985,
+    WrongP1P2 = 0x6A86,
+    InsNotSupported = 0x6D00,
+    ClaNotSupported = 0x6E00,
+    TxDisplayFail = 0xB001,
+    AddrDisplayFail = 0xB002,
+    TxWrongLength = 0xB004,
+    TxParsingFail = 0xB005,
+    TxHashFail = 0xB006,
+    /// The instruction is not the one expected next by a chunked SignTx
+    BadState = 0xB007,
+    TxSignFail = 0xB008,
+    KeyDeriveFail = 0xB009,
+    VersionParsingFail = 0xB00A,
+    /// The transaction type is not supported
+    TxUnsupportedTag = 0xB010,
+    /// The version of the transaction type is not supported
+    TxUnsupportedVersion = 0xB011,
+    /// An id of the transaction has an unknown type
+    TxBadAddressPrefix = 0xB012,
+    /// An id of the transaction is not 32 bytes long
+    TxBadAddressLength = 0xB013,
+    /// The network id is empty, too long or not printable
+    TxInvalidNetworkId = 0xB014,
+    /// The transaction length does not match the data that was sent
+    TxLengthMismatch = 0xB015,
+    /// A chunk goes beyond the length of the transaction, or is not full
+    /// while more chunks are expected
+    TxUnexpectedChunk = 0xB016,
+    /// The payload, or another bytes field, is not an RLP byte array
+    TxInvalidPayload = 0xB017,
+    /// The name of the recipient does not match its `nm_` id
+    TxNameMismatch = 0xB018,
+    /// The amounts of the transaction add up to more than 256 bits
+    TxCostOverflow = 0xB019,
+    /// The VM or the ABI of a contract is not one that can be used anymore
+    TxUnsupportedVm = 0xB01A,
+    /// The VM or the ABI of a contract is newer than the ones that are known
+    TxUnknownVm = 0xB01B,
+    /// The transaction can only be blind-signed, which is disabled in the
+    /// settings
+    TxBlindSigningDisabled = 0xB01C,
+    MsgWrongLength = 0xB100,
+    MsgHashFail = 0xB101,
+    MsgSignFail = 0xB102,
+    MsgParsingFail = 0xB103,
+    GetAddressParsingFail = 0xB200,
+    DataWrongLength = 0xB300,
+    DataSignFail = 0xB301,
+    DataSigningDisabled = 0xB302,
+    DataHashFail = 0xB303,
+    DataParsingFail = 0xB304,
+    TypedDataWrongLength = 0xB400,
+    TypedDataParsingFail = 0xB401,
+    TypedDataHashFail = 0xB402,
+    TypedDataSignFail = 0xB403,
+    DelegationParsingFail = 0xB500,
+    DelegationSignFail = 0xB501,
+    /// The transaction does not match the parameters of the swap
+    SwapCheckFail = 0xB600,
+    /// The address or the label to register is invalid
+    AddressBookParsingFail = 0xB700,
+    AddressBookFull = 0xB701,
+    /// The MAC of the entry could not be computed
+    AddressBookFail = 0xB702,
+    /// The network descriptor is invalid, or describes a known network
+    NetworkParsingFail = 0xB800,
+    /// The network descriptor is not signed by the trusted key
+    NetworkSignatureFail = 0xB801,
+    /// The header of a batch is invalid, or more transactions are sent than
+    /// announced
+    BatchParsingFail = 0xB900,
+    /// The nonces of the transactions of a batch do not increase
+    BatchNonceFail = 0xB901,
+    /// Same value as `StatusWords::BadLen` of the SDK
+    WrongApduLength = 0x6E03,
+    Ok = 0x9000,
+}
+
+impl AppSW {
+    /// Every status word, so that hosts can recognize the ones they receive.
+    pub const ALL: &'static [AppSW] = &[
+        AppSW::Deny,
+        AppSW::WrongP1P2,
+        AppSW::InsNotSupported,
+        AppSW::ClaNotSupported,
+        AppSW::TxDisplayFail,
+        AppSW::AddrDisplayFail,
+        AppSW::TxWrongLength,
+        AppSW::TxParsingFail,
+        AppSW::TxHashFail,
+        AppSW::BadState,
+        AppSW::TxSignFail,
+        AppSW::KeyDeriveFail,
+        AppSW::VersionParsingFail,
+        AppSW::TxUnsupportedTag,
+        AppSW::TxUnsupportedVersion,
+        AppSW::TxBadAddressPrefix,
+        AppSW::TxBadAddressLength,
+        AppSW::TxInvalidNetworkId,
+        AppSW::TxLengthMismatch,
+        AppSW::TxUnexpectedChunk,
+        AppSW::TxInvalidPayload,
+        AppSW::TxNameMismatch,
+        AppSW::TxCostOverflow,
+        AppSW::TxUnsupportedVm,
+        AppSW::TxUnknownVm,
+        AppSW::TxBlindSigningDisabled,
+        AppSW::MsgWrongLength,
+        AppSW::MsgHashFail,
+        AppSW::MsgSignFail,
+        AppSW::MsgParsingFail,
+        AppSW::GetAddressParsingFail,
+        AppSW::DataWrongLength,
+        AppSW::DataSignFail,
+        AppSW::DataSigningDisabled,
+        AppSW::DataHashFail,
+        AppSW::DataParsingFail,
+        AppSW::TypedDataWrongLength,
+        AppSW::TypedDataParsingFail,
+        AppSW::TypedDataHashFail,
+        AppSW::TypedDataSignFail,
+        AppSW::DelegationParsingFail,
+        AppSW::DelegationSignFail,
+        AppSW::SwapCheckFail,
+        AppSW::AddressBookParsingFail,
+        AppSW::AddressBookFull,
+        AppSW::AddressBookFail,
+        AppSW::NetworkParsingFail,
+        AppSW::NetworkSignatureFail,
+        AppSW::BatchParsingFail,
+        AppSW::BatchNonceFail,
+        AppSW::WrongApduLength,
+        AppSW::Ok,
+    ];
+}
+
+/// Recognize a status word received by a host, or give it back if it's not
+/// one of the app.
+impl TryFrom<u16> for AppSW {
+    type Error = u16;
+
+    fn try_from(sw: u16) -> Result<Self, Self::Error> {
+        AppSW::ALL
+            .iter()
+            .copied()
+            .find(|known| *known as u16 == sw)
+            .ok_or(sw)
+    }
+}
+
+#[cfg(feature = "device")]
+impl From<AppSW> for ledger_device_sdk::io::Reply {
+    fn from(sw: AppSW) -> ledger_device_sdk::io::Reply {
+        ledger_device_sdk::io::Reply(sw as u16)
+    }
+}
+
+#[cfg(feature = "std")]
+impl core::fmt::Display for AppSW {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:?} (0x{:04X})", self, *self as u16)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AppSW {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_words_round_trip() {
+        for (i, sw) in AppSW::ALL.iter().enumerate() {
+            assert_eq!(AppSW::try_from(*sw as u16), Ok(*sw));
+            assert!(!AppSW::ALL[..i].contains(sw));
+        }
+        assert_eq!(AppSW::try_from(0x9000), Ok(AppSW::Ok));
+        assert_eq!(AppSW::try_from(0x1234), Err(0x1234));
+    }
+}
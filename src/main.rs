@@ -18,31 +18,52 @@
 #![no_std]
 #![no_main]
 
+mod exchange;
+mod settings;
+mod storage;
 mod utils;
 mod app_ui {
     pub mod address;
     pub mod menu;
+    pub mod register_address;
     pub mod sign_data;
+    pub mod sign_delegation;
     pub mod sign_msg;
     pub mod sign_tx;
+    pub mod sign_typed_data;
 }
 mod handlers {
     pub mod get_address;
+    pub mod get_app_configuration;
     pub mod get_version;
+    pub mod provide_network;
+    pub mod register_address;
     pub mod sign_data;
+    pub mod sign_delegation;
     pub mod sign_msg;
     pub mod sign_tx;
+    pub mod sign_typed_data;
 }
 
-use app_ui::menu::ui_menu_main;
+use app_aeternity::instruction::{Instruction, CLA};
+use app_aeternity::AppSW;
+use app_ui::menu::{ui_menu_main, ui_reset_nonces};
+use app_ui::sign_tx::ui_display_tx_hash;
+use exchange::swap_main;
 use handlers::{
     get_address::handler_get_address,
+    get_app_configuration::handler_get_app_configuration,
     get_version::handler_get_version,
-    sign_data::handler_sign_data,
+    provide_network::handler_provide_network,
+    register_address::handler_register_address,
+    sign_data::{handler_sign_data, DataContext},
+    sign_delegation::handler_sign_delegation,
     sign_msg::handler_sign_message,
-    sign_tx::{handler_sign_tx, TxContext},
+    sign_tx::{handler_get_signed_tx_chunk, handler_sign_tx, handler_sign_tx_batch, TxContext},
+    sign_typed_data::{handler_sign_typed_data, TypedDataContext},
 };
-use ledger_device_sdk::io::{ApduHeader, Comm, Reply, StatusWords};
+use ledger_device_sdk::io::{Comm, Event};
+use settings::{Setting, Settings};
 
 ledger_device_sdk::set_panic!(ledger_device_sdk::exiting_panic);
 
@@ -51,86 +72,13 @@ extern crate alloc;
 
 use ledger_device_sdk::nbgl::{init_comm, NbglReviewStatus, StatusType};
 
-// P1 for GetAddress
-const P1_CONFIRM_NOT_NEEDED: u8 = 0x00;
-const P1_CONFIRM_NEEDED: u8 = 0x01;
-// P1 for SignTx
-const P1_SIGN_TX_START: u8 = 0x00;
-const P1_SIGN_TX_MORE: u8 = 0x80;
-
-// Application status words.
-#[repr(u16)]
-#[derive(Clone, Copy, PartialEq)]
-pub enum AppSW {
-    Deny = 0x6985,
-    WrongP1P2 = 0x6A86,
-    InsNotSupported = 0x6D00,
-    ClaNotSupported = 0x6E00,
-    TxDisplayFail = 0xB001,
-    AddrDisplayFail = 0xB002,
-    TxWrongLength = 0xB004,
-    TxParsingFail = 0xB005,
-    TxHashFail = 0xB006,
-    TxSignFail = 0xB008,
-    KeyDeriveFail = 0xB009,
-    VersionParsingFail = 0xB00A,
-    MsgWrongLength = 0xB100,
-    MsgHashFail = 0xB101,
-    MsgSignFail = 0xB102,
-    GetAddressParsingFail = 0xB200,
-    DataWrongLength = 0xB300,
-    DataSignFail = 0xB301,
-    WrongApduLength = StatusWords::BadLen as u16,
-    Ok = 0x9000,
-}
-
-impl From<AppSW> for Reply {
-    fn from(sw: AppSW) -> Reply {
-        Reply(sw as u16)
-    }
-}
-
-/// Possible input commands received through APDUs.
-pub enum Instruction {
-    GetVersion,
-    GetAddress { confirm_needed: bool },
-    SignData,
-    SignTx { first_chunk: bool },
-    SignMsg,
-}
-
-impl TryFrom<ApduHeader> for Instruction {
-    type Error = AppSW;
-
-    /// APDU parsing logic.
-    ///
-    /// Parses INS, P1 and P2 bytes to build an [`Instruction`]. P1 and P2 are translated to
-    /// strongly typed variables depending on the APDU instruction code. Invalid INS, P1 or P2
-    /// values result in errors with a status word, which are automatically sent to the host by the
-    /// SDK.
-    ///
-    /// This design allows a clear separation of the APDU parsing logic and commands handling.
-    ///
-    /// Note that CLA is not checked here. Instead the method [`Comm::set_expected_cla`] is used in
-    /// [`sample_main`] to have this verification automatically performed by the SDK.
-    fn try_from(value: ApduHeader) -> Result<Self, Self::Error> {
-        match (value.ins, value.p1, value.p2) {
-            (2, P1_CONFIRM_NOT_NEEDED | P1_CONFIRM_NEEDED, 0) => Ok(Instruction::GetAddress {
-                confirm_needed: value.p1 == P1_CONFIRM_NEEDED,
-            }),
-            (4, P1_SIGN_TX_START | P1_SIGN_TX_MORE, 0) => Ok(Instruction::SignTx {
-                first_chunk: value.p1 == P1_SIGN_TX_START,
-            }),
-            (6, 0, 0) => Ok(Instruction::GetVersion),
-            (8, 0, 0) => Ok(Instruction::SignMsg),
-            (10, 0, 0) => Ok(Instruction::SignData),
-            (2 | 4 | 6, _, _) => Err(AppSW::WrongP1P2),
-            (_, _, _) => Err(AppSW::InsNotSupported),
-        }
-    }
-}
-
-fn show_status_and_home_if_needed(ins: &Instruction, tx_ctx: &mut TxContext, status: &AppSW) {
+fn show_status_and_home_if_needed(
+    ins: &Instruction,
+    tx_ctx: &mut TxContext,
+    data_ctx: &DataContext,
+    typed_data_ctx: &TypedDataContext,
+    status: &AppSW,
+) {
     let (show_status, status_type) = match (ins, status) {
         (
             Instruction::GetAddress {
@@ -138,12 +86,23 @@ fn show_status_and_home_if_needed(ins: &Instruction, tx_ctx: &mut TxContext, sta
             },
             AppSW::Deny | AppSW::Ok,
         ) => (true, StatusType::Address),
-        (Instruction::SignData, AppSW::Deny | AppSW::Ok) => {
+        (Instruction::SignData { .. }, AppSW::Deny | AppSW::Ok) if data_ctx.is_finished() => {
             // TODO: should I change the StatusType?
             (true, StatusType::Message)
         }
         (Instruction::SignMsg, AppSW::Deny | AppSW::Ok) => (true, StatusType::Message),
-        (Instruction::SignTx { .. }, AppSW::Deny | AppSW::Ok) if tx_ctx.is_finished() => {
+        (Instruction::RegisterAddress, AppSW::Deny | AppSW::Ok) => (true, StatusType::Address),
+        (Instruction::SignDelegation { .. }, AppSW::Deny | AppSW::Ok) => {
+            (true, StatusType::Operation)
+        }
+        (Instruction::SignTypedData { .. }, AppSW::Deny | AppSW::Ok)
+            if typed_data_ctx.is_finished() =>
+        {
+            (true, StatusType::Message)
+        }
+        (Instruction::SignTx { .. } | Instruction::SignTxBatch { .. }, AppSW::Deny | AppSW::Ok)
+            if tx_ctx.is_finished() =>
+        {
             (true, StatusType::Transaction)
         }
         (_, _) => (false, StatusType::Transaction),
@@ -151,9 +110,14 @@ fn show_status_and_home_if_needed(ins: &Instruction, tx_ctx: &mut TxContext, sta
 
     if show_status {
         let success = *status == AppSW::Ok;
-        NbglReviewStatus::new()
-            .status_type(status_type)
-            .show(success);
+        match tx_ctx.tx_hash() {
+            Some(tx_hash) if success && matches!(ins, Instruction::SignTx { .. }) => {
+                ui_display_tx_hash(&tx_hash)
+            }
+            _ => NbglReviewStatus::new()
+                .status_type(status_type)
+                .show(success),
+        }
 
         // call home.show_and_return() to show home and setting screen
         tx_ctx.home.show_and_return();
@@ -161,13 +125,22 @@ fn show_status_and_home_if_needed(ins: &Instruction, tx_ctx: &mut TxContext, sta
 }
 
 #[no_mangle]
-extern "C" fn sample_main() {
+extern "C" fn sample_main(arg0: u32) {
+    // A non-zero argument means the app is called as a library by the
+    // Exchange app
+    if arg0 != 0 {
+        swap_main(arg0);
+        return;
+    }
+
     // Create the communication manager, and configure it to accept only APDU from the 0xe0 class.
     // If any APDU with a wrong class value is received, comm will respond automatically with
     // BadCla status word.
-    let mut comm = Comm::new().set_expected_cla(0xe0);
+    let mut comm = Comm::new().set_expected_cla(CLA);
 
     let mut tx_ctx = TxContext::new();
+    let mut data_ctx = DataContext::new();
+    let mut typed_data_ctx = TypedDataContext::new();
 
     // Initialize reference to Comm instance for NBGL
     // API calls.
@@ -176,9 +149,25 @@ extern "C" fn sample_main() {
     tx_ctx.home.show_and_return();
 
     loop {
-        let ins: Instruction = comm.next_command();
-
-        let _status = match handle_apdu(&mut comm, &ins, &mut tx_ctx) {
+        let ins: Instruction = match comm.next_event() {
+            Event::Command(ins) => ins,
+            // The reset of the nonces is asked for with its switch
+            Event::Ticker if Settings.is_enabled(Setting::ResetNonces) => {
+                ui_reset_nonces();
+                tx_ctx.home.show_and_return();
+                continue;
+            }
+            _ => continue,
+        };
+        tx_ctx.end_session_unless_tx(&ins);
+
+        let _status = match handle_apdu(
+            &mut comm,
+            &ins,
+            &mut tx_ctx,
+            &mut data_ctx,
+            &mut typed_data_ctx,
+        ) {
             Ok(()) => {
                 comm.reply_ok();
                 AppSW::Ok
@@ -188,16 +177,45 @@ extern "C" fn sample_main() {
                 sw
             }
         };
-        show_status_and_home_if_needed(&ins, &mut tx_ctx, &_status);
+        show_status_and_home_if_needed(&ins, &mut tx_ctx, &data_ctx, &typed_data_ctx, &_status);
     }
 }
 
-fn handle_apdu(comm: &mut Comm, ins: &Instruction, ctx: &mut TxContext) -> Result<(), AppSW> {
+fn handle_apdu(
+    comm: &mut Comm,
+    ins: &Instruction,
+    ctx: &mut TxContext,
+    data_ctx: &mut DataContext,
+    typed_data_ctx: &mut TypedDataContext,
+) -> Result<(), AppSW> {
     match ins {
-        Instruction::SignTx { first_chunk } => handler_sign_tx(comm, *first_chunk, ctx),
+        Instruction::SignTx {
+            first_chunk,
+            response,
+        } => handler_sign_tx(comm, *first_chunk, *response, ctx),
+        Instruction::SignTxBatch { first_chunk } => handler_sign_tx_batch(comm, *first_chunk, ctx),
+        Instruction::GetSignedTxChunk => handler_get_signed_tx_chunk(comm, ctx),
         Instruction::GetAddress { confirm_needed } => handler_get_address(comm, *confirm_needed),
         Instruction::GetVersion => handler_get_version(comm),
+        Instruction::GetAppConfiguration => handler_get_app_configuration(comm),
         Instruction::SignMsg => handler_sign_message(comm),
-        Instruction::SignData => handler_sign_data(comm),
+        Instruction::RegisterAddress => handler_register_address(comm),
+        Instruction::ProvideNetwork => handler_provide_network(comm, ctx),
+        Instruction::Abort => {
+            // The state of SignTx was already reset when receiving Abort
+            data_ctx.reset();
+            typed_data_ctx.reset();
+            Ok(())
+        }
+        Instruction::SignData {
+            first_chunk,
+            hash_mode,
+        } => handler_sign_data(comm, *first_chunk, *hash_mode, data_ctx),
+        Instruction::SignDelegation { delegation_type } => {
+            handler_sign_delegation(comm, *delegation_type)
+        }
+        Instruction::SignTypedData { first_chunk } => {
+            handler_sign_typed_data(comm, *first_chunk, typed_data_ctx)
+        }
     }
 }
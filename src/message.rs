@@ -0,0 +1,118 @@
+//! Rendering of the messages signed with SignMsg.
+
+use alloc::string::String;
+use core::fmt::Write;
+
+/// Messages are reviewed by hash when their rendered form is longer than this.
+pub const MAX_MESSAGE_DISPLAY_LEN: usize = 512;
+
+/// How a message is rendered on the review screen.
+#[derive(Debug, PartialEq)]
+pub enum MessageDisplay {
+    /// Printable ASCII text, with control characters and newlines escaped.
+    Text { text: String, escaped: bool },
+    /// Content that is not printable text, rendered as hex.
+    Hex(String),
+    /// Content too long to be reviewed, rendered as the hash that gets signed.
+    Hash(String),
+}
+
+/// Render a message as text if possible, or as hex, or as its hash when it's
+/// too long to be reviewed.
+pub fn format_message(message: &[u8], hash: &[u8; 32]) -> MessageDisplay {
+    let display = match core::str::from_utf8(message).ok().and_then(escape_message) {
+        Some((text, _)) if text.len() > MAX_MESSAGE_DISPLAY_LEN => None,
+        Some((text, escaped)) => Some(MessageDisplay::Text { text, escaped }),
+        None if 2 * message.len() > MAX_MESSAGE_DISPLAY_LEN => None,
+        None => Some(MessageDisplay::Hex(hex::encode(message))),
+    };
+
+    display.unwrap_or_else(|| MessageDisplay::Hash(hex::encode(hash)))
+}
+
+/// Escape newlines, control characters and backslashes so that they are visible on the screen.
+///
+/// Returns the escaped message and whether anything other than a backslash had to be escaped,
+/// or `None` if the message contains characters that the device fonts cannot render.
+pub fn escape_message(message: &str) -> Option<(String, bool)> {
+    let mut output = String::new();
+    let mut escaped = false;
+
+    for c in message.chars() {
+        match c {
+            '\\' => output.push_str("\\\\"),
+            '\n' | '\r' | '\t' => {
+                escaped = true;
+                output.push_str(match c {
+                    '\n' => "\\n",
+                    '\r' => "\\r",
+                    _ => "\\t",
+                });
+            }
+            c if c.is_ascii_control() => {
+                escaped = true;
+                // Writing to a String never fails
+                let _ = write!(output, "\\x{:02x}", c as u8);
+            }
+            c if c.is_ascii() => output.push(c),
+            _ => return None,
+        }
+    }
+
+    Some((output, escaped))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HASH: [u8; 32] = [0xAB; 32];
+
+    fn text(text: &str, escaped: bool) -> MessageDisplay {
+        MessageDisplay::Text {
+            text: text.into(),
+            escaped,
+        }
+    }
+
+    #[test]
+    fn printable_text() {
+        assert_eq!(format_message(b"Hello", &HASH), text("Hello", false));
+        assert_eq!(format_message(b"a\\b", &HASH), text("a\\\\b", false));
+    }
+
+    #[test]
+    fn escaped_text() {
+        assert_eq!(
+            format_message(b"one\ntwo\tthree\r", &HASH),
+            text("one\\ntwo\\tthree\\r", true)
+        );
+        assert_eq!(format_message(b"nul\0", &HASH), text("nul\\x00", true));
+    }
+
+    #[test]
+    fn not_text() {
+        assert_eq!(
+            format_message(&[0xff, 0x00], &HASH),
+            MessageDisplay::Hex("ff00".into())
+        );
+        assert_eq!(
+            format_message("été".as_bytes(), &HASH),
+            MessageDisplay::Hex("c3a974c3a9".into())
+        );
+    }
+
+    #[test]
+    fn too_long() {
+        let long_text = [b'a'; MAX_MESSAGE_DISPLAY_LEN + 1];
+        assert_eq!(
+            format_message(&long_text, &HASH),
+            MessageDisplay::Hash(hex::encode(HASH))
+        );
+        let long_binary = [0xff; MAX_MESSAGE_DISPLAY_LEN / 2 + 1];
+        assert_eq!(
+            format_message(&long_binary, &HASH),
+            MessageDisplay::Hash(hex::encode(HASH))
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//! Networks that transactions are signed for, with the name and the token of
+//! each one.
+//!
+//! Mainnet and testnet are known by the app. Other networks, like
+//! Hyperchains, use the same transactions with their own network ids and
+//! tokens. The host describes them with descriptors signed by a trusted key,
+//! which are kept until the app exits.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use primitive_types::U256;
+
+use crate::amount::{display_amount_with_decimals, AE_DECIMALS};
+use crate::requests::ProvideNetworkRequest;
+use crate::AppSW;
+
+pub const MAINNET_ID: &str = "ae_mainnet";
+/// Number of provided networks that are kept. Providing another one forgets
+/// the oldest.
+pub const MAX_PROVIDED_NETWORKS: usize = 4;
+/// More decimals would not leave room for any amount in 256 bits.
+pub const MAX_DECIMALS: u8 = 36;
+pub const MAX_TICKER_LEN: usize = 10;
+
+/// Networks of the Aeternity blockchain, with their names.
+const KNOWN_NETWORKS: &[(&str, &str)] = &[(MAINNET_ID, "Mainnet"), ("ae_uat", "Testnet")];
+const AE_TICKER: &str = "AE";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Network {
+    pub id: String,
+    pub name: String,
+    pub ticker: String,
+    pub decimals: u8,
+}
+
+impl Network {
+    /// Network of a `network_id`, either known by the app or provided by the
+    /// host. Other networks are shown by their id, with amounts in AE.
+    pub fn find(network_id: &str, provided: &[Network]) -> Network {
+        let name = KNOWN_NETWORKS
+            .iter()
+            .find(|(id, _)| *id == network_id)
+            .map(|(_, name)| *name);
+        if name.is_none() {
+            if let Some(network) = provided.iter().find(|network| network.id == network_id) {
+                return network.clone();
+            }
+        }
+
+        Network {
+            id: network_id.into(),
+            name: name.unwrap_or(network_id).into(),
+            ticker: AE_TICKER.into(),
+            decimals: AE_DECIMALS,
+        }
+    }
+
+    /// Network of a descriptor sent by the host, which must be signed by the
+    /// trusted key. The networks known by the app cannot be redefined.
+    pub fn from_descriptor(
+        descriptor: &ProvideNetworkRequest,
+        verify: impl Fn(&[u8], &[u8]) -> bool,
+    ) -> Result<Network, AppSW> {
+        if KNOWN_NETWORKS
+            .iter()
+            .any(|(id, _)| *id == descriptor.network_id)
+            || descriptor.network_id.is_empty()
+            || descriptor.name.is_empty()
+            || descriptor.ticker.is_empty()
+            || descriptor.ticker.len() > MAX_TICKER_LEN
+            || descriptor.decimals > MAX_DECIMALS
+        {
+            return Err(AppSW::NetworkParsingFail);
+        }
+        if !verify(descriptor.signed_data, descriptor.signature) {
+            return Err(AppSW::NetworkSignatureFail);
+        }
+
+        Ok(Network {
+            id: descriptor.network_id.into(),
+            name: descriptor.name.into(),
+            ticker: descriptor.ticker.into(),
+            decimals: descriptor.decimals,
+        })
+    }
+
+    pub fn is_mainnet(&self) -> bool {
+        self.id == MAINNET_ID
+    }
+
+    /// Amount in the smallest unit of the token, with the ticker.
+    pub fn format_amount(&self, amount: U256) -> String {
+        format!(
+            "{} {}",
+            display_amount_with_decimals(amount, self.decimals),
+            self.ticker
+        )
+    }
+}
+
+/// Keep a provided network, replacing the one with the same id if any.
+pub fn provide(networks: &mut Vec<Network>, network: Network) {
+    networks.retain(|provided| provided.id != network.id);
+    if networks.len() == MAX_PROVIDED_NETWORKS {
+        networks.remove(0);
+    }
+    networks.push(network);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn descriptor<'a>(network_id: &'a str, ticker: &'a str) -> ProvideNetworkRequest<'a> {
+        ProvideNetworkRequest {
+            network_id,
+            name: "My Hyperchain",
+            ticker,
+            decimals: 3,
+            signed_data: b"signed",
+            signature: b"signature",
+        }
+    }
+
+    /// Stand-in for the check of the signature with the trusted key.
+    fn verify(data: &[u8], signature: &[u8]) -> bool {
+        data == b"signed" && signature == b"signature"
+    }
+
+    #[test]
+    fn known_networks() {
+        let mainnet = Network::find("ae_mainnet", &[]);
+        assert!(mainnet.is_mainnet());
+        assert_eq!(mainnet.name, "Mainnet");
+        assert_eq!(
+            mainnet.format_amount(U256::from(1_500_000_000_000_000_000u64)),
+            "1.5 AE"
+        );
+        assert_eq!(Network::find("ae_uat", &[]).name, "Testnet");
+
+        let unknown = Network::find("my_hc", &[]);
+        assert!(!unknown.is_mainnet());
+        assert_eq!(
+            (unknown.name.as_str(), unknown.ticker.as_str()),
+            ("my_hc", "AE")
+        );
+    }
+
+    #[test]
+    fn provided_networks() {
+        let network = Network::from_descriptor(&descriptor("my_hc", "HCT"), verify).unwrap();
+        let mut networks = Vec::new();
+        provide(&mut networks, network.clone());
+
+        let found = Network::find("my_hc", &networks);
+        assert_eq!(found, network);
+        assert_eq!(found.format_amount(U256::from(12_500)), "12.5 HCT");
+        // A provided network cannot replace a known one
+        assert_eq!(
+            Network::from_descriptor(&descriptor("ae_mainnet", "HCT"), verify),
+            Err(AppSW::NetworkParsingFail)
+        );
+    }
+
+    #[test]
+    fn invalid_descriptors() {
+        assert_eq!(
+            Network::from_descriptor(&descriptor("my_hc", ""), verify),
+            Err(AppSW::NetworkParsingFail)
+        );
+        assert_eq!(
+            Network::from_descriptor(&descriptor("my_hc", "LONGTICKER1"), verify),
+            Err(AppSW::NetworkParsingFail)
+        );
+        let forged = ProvideNetworkRequest {
+            ticker: "AE",
+            signed_data: b"forged",
+            ..descriptor("my_hc", "HCT")
+        };
+        assert_eq!(
+            Network::from_descriptor(&forged, verify),
+            Err(AppSW::NetworkSignatureFail)
+        );
+    }
+
+    #[test]
+    fn oldest_network_is_forgotten() {
+        let mut networks = Vec::new();
+        for id in ["hc_1", "hc_2", "hc_3", "hc_4", "hc_2", "hc_5"] {
+            let network = Network::from_descriptor(&descriptor(id, "HCT"), verify).unwrap();
+            provide(&mut networks, network);
+        }
+        let ids = networks.iter().map(|network| network.id.as_str());
+        assert_eq!(ids.collect::<Vec<_>>(), ["hc_3", "hc_4", "hc_2", "hc_5"]);
+    }
+}
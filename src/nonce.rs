@@ -0,0 +1,135 @@
+//! Highest nonce signed for each account, to warn about transactions that
+//! reuse a nonce.
+//!
+//! A transaction with a nonce that was already signed replaces the one signed
+//! before, or spends the same funds twice if both are published. Accounts have
+//! their own nonces on each network, so the nonces are kept per account and
+//! per network.
+
+use primitive_types::U256;
+use sha2::{Digest, Sha256};
+
+pub const MAX_ACCOUNTS: usize = 16;
+
+/// Networks are told apart by the first bytes of the SHA-256 hash of their id.
+const NETWORK_TAG_LEN: usize = 4;
+/// An entry is a marker telling that it is used (1 byte), the account number
+/// (4 bytes), the tag of the network and the nonce (8 bytes).
+const ENTRY_LEN: usize = 1 + 4 + NETWORK_TAG_LEN + 8;
+pub const STORAGE_LEN: usize = MAX_ACCOUNTS * ENTRY_LEN;
+
+const USED: u8 = 1;
+
+/// Storage of the nonces, from the least to the most recently signed. Empty
+/// entries are filled with zeros.
+pub type Storage = [u8; STORAGE_LEN];
+
+/// Key of the entry of an account on a network: every byte of an entry but
+/// the nonce.
+fn entry_key(account: u32, network_id: &[u8]) -> [u8; ENTRY_LEN - 8] {
+    let mut key = [USED; ENTRY_LEN - 8];
+    key[1..5].copy_from_slice(&account.to_be_bytes());
+    key[5..].copy_from_slice(&Sha256::digest(network_id)[..NETWORK_TAG_LEN]);
+    key
+}
+
+fn find(storage: &Storage, account: u32, network_id: &[u8]) -> Option<usize> {
+    let key = entry_key(account, network_id);
+    storage
+        .chunks_exact(ENTRY_LEN)
+        .position(|entry| entry[..key.len()] == key)
+}
+
+/// Highest nonce signed for an account on a network.
+pub fn highest_nonce(storage: &Storage, account: u32, network_id: &[u8]) -> Option<u64> {
+    let slot = find(storage, account, network_id)?;
+    let nonce = &storage[slot * ENTRY_LEN..][ENTRY_LEN - 8..ENTRY_LEN];
+    Some(u64::from_be_bytes(nonce.try_into().ok()?))
+}
+
+/// Whether a nonce is lower than or equal to one that was already signed for
+/// the account.
+pub fn is_reused(storage: &Storage, account: u32, network_id: &[u8], nonce: U256) -> bool {
+    highest_nonce(storage, account, network_id).is_some_and(|highest| nonce <= highest.into())
+}
+
+/// Record that a nonce was signed for an account. The entry of the account
+/// becomes the most recent one, and the least recent entry is forgotten when
+/// the storage is full.
+pub fn record(storage: &mut Storage, account: u32, network_id: &[u8], nonce: U256) {
+    // Nonces never get that high, keeping the highest value is enough
+    let nonce = u64::try_from(nonce).unwrap_or(u64::MAX);
+    let highest = highest_nonce(storage, account, network_id).unwrap_or_default();
+
+    let mut entry = [0; ENTRY_LEN];
+    entry[..ENTRY_LEN - 8].copy_from_slice(&entry_key(account, network_id));
+    entry[ENTRY_LEN - 8..].copy_from_slice(&nonce.max(highest).to_be_bytes());
+
+    // Remove the entry of the account, or the least recent one, and append
+    // the new entry after the others
+    let slot = find(storage, account, network_id)
+        .or_else(|| {
+            storage
+                .chunks_exact(ENTRY_LEN)
+                .position(|entry| entry[0] != USED)
+        })
+        .unwrap_or(0);
+    storage.copy_within((slot + 1) * ENTRY_LEN.., slot * ENTRY_LEN);
+    let used = storage
+        .chunks_exact(ENTRY_LEN)
+        .take_while(|entry| entry[0] == USED)
+        .count()
+        .min(MAX_ACCOUNTS - 1);
+    storage[used * ENTRY_LEN..].fill(0);
+    storage[used * ENTRY_LEN..][..ENTRY_LEN].copy_from_slice(&entry);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAINNET: &[u8] = b"ae_mainnet";
+
+    #[test]
+    fn reused_nonces() {
+        let mut storage = [0; STORAGE_LEN];
+        assert!(!is_reused(&storage, 0, MAINNET, U256::one()));
+
+        record(&mut storage, 0, MAINNET, U256::from(5));
+        assert_eq!(highest_nonce(&storage, 0, MAINNET), Some(5));
+        for nonce in [0, 4, 5] {
+            assert!(is_reused(&storage, 0, MAINNET, nonce.into()));
+        }
+        assert!(!is_reused(&storage, 0, MAINNET, U256::from(6)));
+
+        // Other accounts and networks have their own nonces
+        assert!(!is_reused(&storage, 1, MAINNET, U256::one()));
+        assert!(!is_reused(&storage, 0, b"ae_uat", U256::one()));
+
+        // Signing a lower nonce keeps the highest one
+        record(&mut storage, 0, MAINNET, U256::from(3));
+        assert_eq!(highest_nonce(&storage, 0, MAINNET), Some(5));
+        record(&mut storage, 0, MAINNET, U256::from(8));
+        assert_eq!(highest_nonce(&storage, 0, MAINNET), Some(8));
+        assert_eq!(storage[ENTRY_LEN..], [0; STORAGE_LEN - ENTRY_LEN]);
+
+        record(&mut storage, 0, MAINNET, U256::MAX);
+        assert_eq!(highest_nonce(&storage, 0, MAINNET), Some(u64::MAX));
+    }
+
+    #[test]
+    fn least_recent_account_is_forgotten() {
+        let mut storage = [0; STORAGE_LEN];
+        for account in 0..MAX_ACCOUNTS as u32 {
+            record(&mut storage, account, MAINNET, U256::one());
+        }
+        // Account 0 becomes the most recent one, so account 1 is forgotten
+        record(&mut storage, 0, MAINNET, U256::from(2));
+        record(&mut storage, 100, MAINNET, U256::one());
+
+        assert_eq!(highest_nonce(&storage, 0, MAINNET), Some(2));
+        assert_eq!(highest_nonce(&storage, 1, MAINNET), None);
+        assert_eq!(highest_nonce(&storage, 2, MAINNET), Some(1));
+        assert_eq!(highest_nonce(&storage, 100, MAINNET), Some(1));
+    }
+}
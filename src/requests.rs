@@ -0,0 +1,872 @@
+//! Decoding of the data sent with each instruction.
+//!
+//! Every decoder checks that all the fields fit in the received data and that
+//! nothing is left after them, and fails with the status word of the
+//! instruction it belongs to.
+
+use crate::batch::MAX_BATCH_LEN;
+use crate::AppSW;
+
+pub const NON_INNER_TX_TAG: u8 = 0x00;
+pub const INNER_TX_TAG: u8 = 0x01;
+/// Flag of the inner transaction byte of SignTx, set when the plaintext name
+/// of the recipient follows the network id
+pub const RECIPIENT_NAME_FLAG: u8 = 0x80;
+pub const NETWORK_ID_MAX_LENGTH: usize = 32;
+
+/// Maximum length of a message, which is sent in one APDU after the account
+/// number and the length.
+pub const MAX_MESSAGE_LEN: usize = 255 - 8;
+/// Maximum size of data that is signed as is. Larger data must be hashed.
+pub const MAX_RAW_DATA_LEN: usize = 2048;
+pub const MAX_TYPED_DATA_LEN: usize = 2048;
+
+// Bits of the domain flags byte, telling which domain fields are present
+const DOMAIN_NAME_FLAG: u8 = 0x01;
+const DOMAIN_VERSION_FLAG: u8 = 0x02;
+const DOMAIN_NETWORK_ID_FLAG: u8 = 0x04;
+const DOMAIN_CONTRACT_FLAG: u8 = 0x08;
+
+/// Cursor over the received data. Reading past its end fails with the status
+/// word it was created with.
+struct Reader<'a> {
+    data: &'a [u8],
+    error: AppSW,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8], error: AppSW) -> Self {
+        Reader { data, error }
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<&'a [u8; N], AppSW> {
+        let (array, rest) = self.data.split_first_chunk::<N>().ok_or(self.error)?;
+        self.data = rest;
+        Ok(array)
+    }
+
+    fn u8(&mut self) -> Result<u8, AppSW> {
+        self.array::<1>().map(|bytes| bytes[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, AppSW> {
+        self.array().map(|bytes| u16::from_be_bytes(*bytes))
+    }
+
+    fn u32(&mut self) -> Result<u32, AppSW> {
+        self.array().map(|bytes| u32::from_be_bytes(*bytes))
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], AppSW> {
+        let (bytes, rest) = self.data.split_at_checked(len).ok_or(self.error)?;
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    /// Bytes prefixed with their length on 1 byte.
+    fn short_bytes(&mut self) -> Result<&'a [u8], AppSW> {
+        let len = self.u8()?;
+        self.bytes(len.into())
+    }
+
+    /// Bytes prefixed with their length on 2 bytes.
+    fn long_bytes(&mut self) -> Result<&'a [u8], AppSW> {
+        let len = self.u16()?;
+        self.bytes(len.into())
+    }
+
+    /// Printable ASCII string prefixed with its length on 1 byte.
+    fn short_str(&mut self) -> Result<&'a str, AppSW> {
+        let bytes = self.short_bytes()?;
+        printable_str(bytes).ok_or(self.error)
+    }
+
+    /// All the remaining bytes.
+    fn rest(self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Make sure all the data was read.
+    fn finish(self) -> Result<(), AppSW> {
+        if self.data.is_empty() {
+            Ok(())
+        } else {
+            Err(self.error)
+        }
+    }
+}
+
+/// Convert a length received on 4 bytes.
+fn to_usize(len: u32, error: AppSW) -> Result<usize, AppSW> {
+    usize::try_from(len).map_err(|_| error)
+}
+
+/// The string if it's only made of printable ASCII characters.
+pub fn printable_str(bytes: &[u8]) -> Option<&str> {
+    match core::str::from_utf8(bytes) {
+        Ok(s) if s.chars().all(|c| c.is_ascii() && !c.is_ascii_control()) => Some(s),
+        _ => None,
+    }
+}
+
+/// Data of GetAddress: the account number (4 bytes).
+#[derive(Debug, PartialEq)]
+pub struct GetAddressRequest {
+    pub account_number: u32,
+}
+
+impl GetAddressRequest {
+    pub fn decode(data: &[u8]) -> Result<Self, AppSW> {
+        let mut reader = Reader::new(data, AppSW::GetAddressParsingFail);
+        let account_number = reader.u32()?;
+        reader.finish()?;
+
+        Ok(GetAddressRequest { account_number })
+    }
+}
+
+/// Data of RegisterAddress: the label and the `ak_` or `ct_` address, each
+/// prefixed with its length (1 byte).
+#[derive(Debug, PartialEq)]
+pub struct RegisterAddressRequest<'a> {
+    pub label: &'a str,
+    pub address: &'a str,
+}
+
+impl<'a> RegisterAddressRequest<'a> {
+    pub fn decode(data: &'a [u8]) -> Result<Self, AppSW> {
+        let mut reader = Reader::new(data, AppSW::AddressBookParsingFail);
+        let label = reader.short_str()?;
+        let address = reader.short_str()?;
+        reader.finish()?;
+
+        Ok(RegisterAddressRequest { label, address })
+    }
+}
+
+/// Data of ProvideNetwork: the network id, the name and the token ticker of
+/// the network, each prefixed with its length (1 byte), the decimals of the
+/// token (1 byte), and a DER-encoded signature of all of them.
+#[derive(Debug, PartialEq)]
+pub struct ProvideNetworkRequest<'a> {
+    pub network_id: &'a str,
+    pub name: &'a str,
+    pub ticker: &'a str,
+    pub decimals: u8,
+    /// The data that is signed, which is everything but the signature
+    pub signed_data: &'a [u8],
+    pub signature: &'a [u8],
+}
+
+impl<'a> ProvideNetworkRequest<'a> {
+    pub fn decode(data: &'a [u8]) -> Result<Self, AppSW> {
+        let mut reader = Reader::new(data, AppSW::NetworkParsingFail);
+        let network_id = reader.short_str()?;
+        let name = reader.short_str()?;
+        let ticker = reader.short_str()?;
+        let decimals = reader.u8()?;
+        let signature = reader.rest();
+
+        Ok(ProvideNetworkRequest {
+            network_id,
+            name,
+            ticker,
+            decimals,
+            signed_data: &data[..data.len() - signature.len()],
+            signature,
+        })
+    }
+}
+
+/// Data of SignMsg: the account number (4 bytes), the length of the message
+/// (4 bytes) and the message.
+#[derive(Debug, PartialEq)]
+pub struct SignMsgRequest<'a> {
+    pub account_number: u32,
+    pub message: &'a [u8],
+}
+
+impl<'a> SignMsgRequest<'a> {
+    pub fn decode(data: &'a [u8]) -> Result<Self, AppSW> {
+        let mut reader = Reader::new(data, AppSW::MsgParsingFail);
+        let account_number = reader.u32()?;
+        let message_length = to_usize(reader.u32()?, AppSW::MsgWrongLength)?;
+        let message = reader.rest();
+
+        if message_length != message.len() {
+            return Err(AppSW::MsgWrongLength);
+        }
+
+        Ok(SignMsgRequest {
+            account_number,
+            message,
+        })
+    }
+}
+
+/// Header of the first chunk of data sent over several APDUs: the account
+/// number (4 bytes) and the total length of the data (4 bytes), followed by
+/// the first part of the data.
+#[derive(Debug, PartialEq)]
+pub struct FirstChunk<'a> {
+    pub account_number: u32,
+    pub total_len: usize,
+    pub chunk: &'a [u8],
+}
+
+impl<'a> FirstChunk<'a> {
+    fn decode(
+        data: &'a [u8],
+        max_len: Option<usize>,
+        parsing_error: AppSW,
+        length_error: AppSW,
+    ) -> Result<Self, AppSW> {
+        let mut reader = Reader::new(data, parsing_error);
+        let account_number = reader.u32()?;
+        let total_len = to_usize(reader.u32()?, length_error)?;
+        let chunk = reader.rest();
+
+        if max_len.is_some_and(|max_len| total_len > max_len) || chunk.len() > total_len {
+            return Err(length_error);
+        }
+
+        Ok(FirstChunk {
+            account_number,
+            total_len,
+            chunk,
+        })
+    }
+
+    /// First chunk of SignData. Data signed as is must not be longer than
+    /// [`MAX_RAW_DATA_LEN`].
+    pub fn decode_data(data: &'a [u8], hash_mode: bool) -> Result<Self, AppSW> {
+        let max_len = if hash_mode {
+            None
+        } else {
+            Some(MAX_RAW_DATA_LEN)
+        };
+        Self::decode(
+            data,
+            max_len,
+            AppSW::DataParsingFail,
+            AppSW::DataWrongLength,
+        )
+    }
+
+    /// First chunk of SignTypedData, which must not be longer than
+    /// [`MAX_TYPED_DATA_LEN`].
+    pub fn decode_typed_data(data: &'a [u8]) -> Result<Self, AppSW> {
+        Self::decode(
+            data,
+            Some(MAX_TYPED_DATA_LEN),
+            AppSW::TypedDataParsingFail,
+            AppSW::TypedDataWrongLength,
+        )
+    }
+}
+
+/// Header of the first chunk of SignTx: the account number (4 bytes), the
+/// length of the transaction (4 bytes), whether it's an inner transaction
+/// (1 byte) and the network id (1 byte length + string), followed by the
+/// first part of the transaction.
+///
+/// When [`RECIPIENT_NAME_FLAG`] is set in the inner transaction byte, the
+/// AENS name of an `nm_` recipient (1 byte length + string) follows the
+/// network id, so that it can be shown instead of the name hash.
+#[derive(Debug, PartialEq)]
+pub struct SignTxHeader<'a> {
+    pub account_number: u32,
+    pub tx_len: u32,
+    pub inner_tx: bool,
+    pub network_id: &'a [u8],
+    pub recipient_name: Option<&'a str>,
+    pub tx_chunk: &'a [u8],
+}
+
+impl<'a> SignTxHeader<'a> {
+    pub fn decode(data: &'a [u8]) -> Result<Self, AppSW> {
+        let mut reader = Reader::new(data, AppSW::TxWrongLength);
+        let account_number = reader.u32()?;
+        let tx_len = reader.u32()?;
+
+        let flags = reader.u8()?;
+        let inner_tx = match flags & !RECIPIENT_NAME_FLAG {
+            INNER_TX_TAG => true,
+            NON_INNER_TX_TAG => false,
+            _ => return Err(AppSW::TxParsingFail),
+        };
+
+        let network_id = reader.short_bytes()?;
+        check_network_id(network_id)?;
+
+        let recipient_name = if flags & RECIPIENT_NAME_FLAG != 0 {
+            Some(reader.short_str()?)
+        } else {
+            None
+        };
+
+        let tx_chunk = reader.rest();
+        if to_usize(tx_len, AppSW::TxLengthMismatch)? < tx_chunk.len() {
+            return Err(AppSW::TxLengthMismatch);
+        }
+
+        Ok(SignTxHeader {
+            account_number,
+            tx_len,
+            inner_tx,
+            network_id,
+            recipient_name,
+            tx_chunk,
+        })
+    }
+}
+
+fn check_network_id(network_id: &[u8]) -> Result<(), AppSW> {
+    if network_id.is_empty()
+        || network_id.len() > NETWORK_ID_MAX_LENGTH
+        || printable_str(network_id).is_none()
+    {
+        return Err(AppSW::TxInvalidNetworkId);
+    }
+    Ok(())
+}
+
+/// Data of the first SignTxBatch APDU: the account number (4 bytes), the
+/// number of transactions (1 byte) and the network id (1 byte length +
+/// string). The transactions follow in the next APDUs, each one starting
+/// with a [`BatchTxChunk`].
+#[derive(Debug, PartialEq)]
+pub struct SignTxBatchHeader<'a> {
+    pub account_number: u32,
+    pub tx_count: u8,
+    pub network_id: &'a [u8],
+}
+
+impl<'a> SignTxBatchHeader<'a> {
+    pub fn decode(data: &'a [u8]) -> Result<Self, AppSW> {
+        let mut reader = Reader::new(data, AppSW::BatchParsingFail);
+        let account_number = reader.u32()?;
+        let tx_count = reader.u8()?;
+        if tx_count == 0 || usize::from(tx_count) > MAX_BATCH_LEN {
+            return Err(AppSW::BatchParsingFail);
+        }
+        let network_id = reader.short_bytes()?;
+        check_network_id(network_id)?;
+        reader.finish()?;
+
+        Ok(SignTxBatchHeader {
+            account_number,
+            tx_count,
+            network_id,
+        })
+    }
+}
+
+/// First chunk of a transaction of a batch: the length of the transaction
+/// (4 bytes) followed by its first part. A chunk never holds parts of two
+/// transactions.
+#[derive(Debug, PartialEq)]
+pub struct BatchTxChunk<'a> {
+    pub tx_len: u32,
+    pub tx_chunk: &'a [u8],
+}
+
+impl<'a> BatchTxChunk<'a> {
+    pub fn decode(data: &'a [u8]) -> Result<Self, AppSW> {
+        let mut reader = Reader::new(data, AppSW::TxWrongLength);
+        let tx_len = reader.u32()?;
+        let tx_chunk = reader.rest();
+        if tx_len == 0 || to_usize(tx_len, AppSW::TxLengthMismatch)? < tx_chunk.len() {
+            return Err(AppSW::TxLengthMismatch);
+        }
+        Ok(BatchTxChunk { tx_len, tx_chunk })
+    }
+}
+
+/// Domain of the typed data, every field is optional.
+///
+/// This mirrors the `Domain` type used by `hashTypedData` in the JS SDK.
+#[derive(Debug, PartialEq)]
+pub struct Domain<'a> {
+    pub name: Option<&'a str>,
+    pub version: Option<u32>,
+    pub network_id: Option<&'a str>,
+    pub contract_address: Option<&'a [u8; 32]>,
+}
+
+/// Typed data, once all its chunks are received:
+///
+/// * Domain flags (1 byte), followed by the domain fields that are present:
+///   name (1 byte length + string), version (4 bytes), network id (1 byte
+///   length + string) and contract address (32 bytes)
+/// * ACI of the data type in canonical JSON (2 bytes length + JSON)
+/// * FATE-encoded data (2 bytes length + data)
+#[derive(Debug, PartialEq)]
+pub struct TypedData<'a> {
+    pub domain: Domain<'a>,
+    pub aci: &'a [u8],
+    pub data: &'a [u8],
+}
+
+impl<'a> TypedData<'a> {
+    pub fn decode(data: &'a [u8]) -> Result<Self, AppSW> {
+        let mut reader = Reader::new(data, AppSW::TypedDataParsingFail);
+        let flags = reader.u8()?;
+
+        let name = (flags & DOMAIN_NAME_FLAG != 0)
+            .then(|| reader.short_str())
+            .transpose()?;
+        let version = (flags & DOMAIN_VERSION_FLAG != 0)
+            .then(|| reader.u32())
+            .transpose()?;
+        let network_id = (flags & DOMAIN_NETWORK_ID_FLAG != 0)
+            .then(|| reader.short_str())
+            .transpose()?;
+        let contract_address = (flags & DOMAIN_CONTRACT_FLAG != 0)
+            .then(|| reader.array::<32>())
+            .transpose()?;
+
+        let aci = reader.long_bytes()?;
+        let fate_data = reader.long_bytes()?;
+        reader.finish()?;
+
+        Ok(TypedData {
+            domain: Domain {
+                name,
+                version,
+                network_id,
+                contract_address,
+            },
+            aci,
+            data: fate_data,
+        })
+    }
+}
+
+/// What a contract is allowed to do on behalf of the account.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DelegationType {
+    /// Preclaim names, signed over `network_id + account + contract`
+    Preclaim,
+    /// Manage the account's oracle, signed over `network_id + account + contract`
+    Oracle,
+    /// Claim, update, transfer or revoke a name, signed over
+    /// `network_id + account + name_id + contract`
+    Name,
+}
+
+/// Data of SignDelegation: the account number (4 bytes), the network id
+/// (1 byte length + string), the contract public key (32 bytes) and, for name
+/// delegations only, the name (1 byte length + string).
+#[derive(Debug, PartialEq)]
+pub struct SignDelegationRequest<'a> {
+    pub account_number: u32,
+    pub network_id: &'a str,
+    pub contract: &'a [u8; 32],
+    pub name: Option<&'a str>,
+}
+
+impl<'a> SignDelegationRequest<'a> {
+    pub fn decode(data: &'a [u8], delegation_type: DelegationType) -> Result<Self, AppSW> {
+        let mut reader = Reader::new(data, AppSW::DelegationParsingFail);
+        let account_number = reader.u32()?;
+
+        let network_id = reader.short_str()?;
+        if network_id.is_empty() || network_id.len() > NETWORK_ID_MAX_LENGTH {
+            return Err(AppSW::DelegationParsingFail);
+        }
+
+        let contract = reader.array::<32>()?;
+
+        let name = match delegation_type {
+            DelegationType::Name => Some(reader.short_str()?),
+            DelegationType::Preclaim | DelegationType::Oracle => None,
+        };
+
+        reader.finish()?;
+
+        Ok(SignDelegationRequest {
+            account_number,
+            network_id,
+            contract,
+            name,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn with_lengths(account_number: u32, len: u32, rest: &[u8]) -> std::vec::Vec<u8> {
+        [&account_number.to_be_bytes()[..], &len.to_be_bytes(), rest].concat()
+    }
+
+    #[test]
+    fn get_address() {
+        assert_eq!(
+            GetAddressRequest::decode(&[0, 0, 1, 2]),
+            Ok(GetAddressRequest {
+                account_number: 0x102
+            })
+        );
+        assert_eq!(
+            GetAddressRequest::decode(&[0, 0, 1]),
+            Err(AppSW::GetAddressParsingFail)
+        );
+        assert_eq!(
+            GetAddressRequest::decode(&[0, 0, 1, 2, 3]),
+            Err(AppSW::GetAddressParsingFail)
+        );
+    }
+
+    #[test]
+    fn provide_network() {
+        let data = b"\x05my_hc\x0aHyperchain\x03HCT\x12sig";
+        assert_eq!(
+            ProvideNetworkRequest::decode(data),
+            Ok(ProvideNetworkRequest {
+                network_id: "my_hc",
+                name: "Hyperchain",
+                ticker: "HCT",
+                decimals: 18,
+                signed_data: &data[..data.len() - 3],
+                signature: b"sig",
+            })
+        );
+        assert_eq!(
+            ProvideNetworkRequest::decode(b"\x05my_hc\x0aHyperchain\x03HCT"),
+            Err(AppSW::NetworkParsingFail)
+        );
+    }
+
+    #[test]
+    fn register_address() {
+        assert_eq!(
+            RegisterAddressRequest::decode(b"\x03Foo\x05ak_ab"),
+            Ok(RegisterAddressRequest {
+                label: "Foo",
+                address: "ak_ab"
+            })
+        );
+        for data in [
+            &b"\x03Foo\x05ak_a"[..],
+            b"\x03Foo\x05ak_abc",
+            b"\x03F\no\x01a",
+        ] {
+            assert_eq!(
+                RegisterAddressRequest::decode(data),
+                Err(AppSW::AddressBookParsingFail)
+            );
+        }
+    }
+
+    #[test]
+    fn sign_msg() {
+        let data = with_lengths(7, 5, b"hello");
+        assert_eq!(
+            SignMsgRequest::decode(&data),
+            Ok(SignMsgRequest {
+                account_number: 7,
+                message: b"hello"
+            })
+        );
+
+        let empty = with_lengths(7, 0, b"");
+        assert_eq!(SignMsgRequest::decode(&empty).unwrap().message, b"");
+    }
+
+    #[test]
+    fn sign_msg_errors() {
+        assert_eq!(SignMsgRequest::decode(&[]), Err(AppSW::MsgParsingFail));
+        assert_eq!(
+            SignMsgRequest::decode(&[0, 0, 0, 7, 0, 0]),
+            Err(AppSW::MsgParsingFail)
+        );
+        assert_eq!(
+            SignMsgRequest::decode(&with_lengths(7, 6, b"hello")),
+            Err(AppSW::MsgWrongLength)
+        );
+        assert_eq!(
+            SignMsgRequest::decode(&with_lengths(7, 4, b"hello")),
+            Err(AppSW::MsgWrongLength)
+        );
+        assert_eq!(
+            SignMsgRequest::decode(&with_lengths(7, u32::MAX, b"hello")),
+            Err(AppSW::MsgWrongLength)
+        );
+    }
+
+    #[test]
+    fn data_first_chunk() {
+        let data = with_lengths(1, 10, b"hello");
+        assert_eq!(
+            FirstChunk::decode_data(&data, false),
+            Ok(FirstChunk {
+                account_number: 1,
+                total_len: 10,
+                chunk: b"hello"
+            })
+        );
+
+        assert_eq!(
+            FirstChunk::decode_data(&[0, 0, 0, 1, 0], false),
+            Err(AppSW::DataParsingFail)
+        );
+        assert_eq!(
+            FirstChunk::decode_data(&with_lengths(1, 4, b"hello"), false),
+            Err(AppSW::DataWrongLength)
+        );
+
+        let too_long = with_lengths(1, MAX_RAW_DATA_LEN as u32 + 1, b"hello");
+        assert_eq!(
+            FirstChunk::decode_data(&too_long, false),
+            Err(AppSW::DataWrongLength)
+        );
+        assert!(FirstChunk::decode_data(&too_long, true).is_ok());
+    }
+
+    #[test]
+    fn typed_data_first_chunk() {
+        assert_eq!(
+            FirstChunk::decode_typed_data(&[0, 0]),
+            Err(AppSW::TypedDataParsingFail)
+        );
+        let too_long = with_lengths(1, MAX_TYPED_DATA_LEN as u32 + 1, b"");
+        assert_eq!(
+            FirstChunk::decode_typed_data(&too_long),
+            Err(AppSW::TypedDataWrongLength)
+        );
+    }
+
+    #[test]
+    fn sign_tx_header() {
+        let data = [
+            &with_lengths(3, 4, &[INNER_TX_TAG, 2])[..],
+            b"ae",
+            &[0xf8, 0x01, 0x02, 0x03],
+        ]
+        .concat();
+        assert_eq!(
+            SignTxHeader::decode(&data),
+            Ok(SignTxHeader {
+                account_number: 3,
+                tx_len: 4,
+                inner_tx: true,
+                network_id: b"ae",
+                recipient_name: None,
+                tx_chunk: &[0xf8, 0x01, 0x02, 0x03],
+            })
+        );
+    }
+
+    #[test]
+    fn sign_tx_header_with_name() {
+        let data = [
+            &with_lengths(0, 2, &[RECIPIENT_NAME_FLAG, 2])[..],
+            b"ae\x0balice.chain",
+            &[0xc2, 0x0c],
+        ]
+        .concat();
+        let header = SignTxHeader::decode(&data).unwrap();
+        assert!(!header.inner_tx);
+        assert_eq!(header.recipient_name, Some("alice.chain"));
+        assert_eq!(header.tx_chunk, [0xc2, 0x0c]);
+
+        let flags = RECIPIENT_NAME_FLAG | INNER_TX_TAG;
+        let data = [&with_lengths(0, 2, &[flags, 2])[..], b"ae\x0balice"].concat();
+        assert_eq!(SignTxHeader::decode(&data), Err(AppSW::TxWrongLength));
+    }
+
+    #[test]
+    fn sign_tx_batch_header() {
+        let data = [&7u32.to_be_bytes()[..], &[3, 2], b"ae"].concat();
+        assert_eq!(
+            SignTxBatchHeader::decode(&data),
+            Ok(SignTxBatchHeader {
+                account_number: 7,
+                tx_count: 3,
+                network_id: b"ae",
+            })
+        );
+
+        for tx_count in [0, MAX_BATCH_LEN as u8 + 1] {
+            let data = [&[0; 4][..], &[tx_count, 2], b"ae"].concat();
+            assert_eq!(
+                SignTxBatchHeader::decode(&data),
+                Err(AppSW::BatchParsingFail)
+            );
+        }
+        let trailing = [&data[..], &[0]].concat();
+        assert_eq!(
+            SignTxBatchHeader::decode(&trailing),
+            Err(AppSW::BatchParsingFail)
+        );
+        let no_network_id = [&[0; 4][..], &[3, 0]].concat();
+        assert_eq!(
+            SignTxBatchHeader::decode(&no_network_id),
+            Err(AppSW::TxInvalidNetworkId)
+        );
+    }
+
+    #[test]
+    fn batch_tx_chunk() {
+        assert_eq!(
+            BatchTxChunk::decode(&[0, 0, 0, 3, 0xc2, 0x0c]),
+            Ok(BatchTxChunk {
+                tx_len: 3,
+                tx_chunk: &[0xc2, 0x0c],
+            })
+        );
+        assert_eq!(
+            BatchTxChunk::decode(&[0, 0, 0, 1, 0xc2, 0x0c]),
+            Err(AppSW::TxLengthMismatch)
+        );
+        assert_eq!(
+            BatchTxChunk::decode(&[0, 0, 0, 0]),
+            Err(AppSW::TxLengthMismatch)
+        );
+        assert_eq!(BatchTxChunk::decode(&[0, 0]), Err(AppSW::TxWrongLength));
+    }
+
+    #[test]
+    fn sign_tx_header_errors() {
+        assert_eq!(
+            SignTxHeader::decode(&with_lengths(3, 4, &[])),
+            Err(AppSW::TxWrongLength)
+        );
+        assert_eq!(
+            SignTxHeader::decode(&with_lengths(3, 4, &[2, 0])),
+            Err(AppSW::TxParsingFail)
+        );
+        assert_eq!(
+            SignTxHeader::decode(&with_lengths(3, 4, &[0, 3, b'a'])),
+            Err(AppSW::TxWrongLength)
+        );
+        assert_eq!(
+            SignTxHeader::decode(&with_lengths(3, 4, &[0, 33])),
+            Err(AppSW::TxWrongLength)
+        );
+        let long_network_id = [&with_lengths(3, 0, &[0, 33])[..], &[b'a'; 33]].concat();
+        assert_eq!(
+            SignTxHeader::decode(&long_network_id),
+            Err(AppSW::TxInvalidNetworkId)
+        );
+        assert_eq!(
+            SignTxHeader::decode(&with_lengths(3, 4, &[0, 0])),
+            Err(AppSW::TxInvalidNetworkId)
+        );
+        assert_eq!(
+            SignTxHeader::decode(&with_lengths(3, 4, &[0, 2, b'a', 0])),
+            Err(AppSW::TxInvalidNetworkId)
+        );
+        assert_eq!(
+            SignTxHeader::decode(&with_lengths(3, 1, &[0, 2, b'a', b'e', 1, 2])),
+            Err(AppSW::TxLengthMismatch)
+        );
+    }
+
+    #[test]
+    fn typed_data() {
+        let contract = [7u8; 32];
+        let data = [
+            &[0x0f, 4][..],
+            b"test",
+            &2u32.to_be_bytes(),
+            &[6],
+            b"ae_uat",
+            &contract,
+            &[0, 2],
+            b"{}",
+            &[0, 1, 0x3f],
+        ]
+        .concat();
+        let expected_domain = Domain {
+            name: Some("test"),
+            version: Some(2),
+            network_id: Some("ae_uat"),
+            contract_address: Some(&contract),
+        };
+
+        assert_eq!(
+            TypedData::decode(&data),
+            Ok(TypedData {
+                domain: expected_domain,
+                aci: b"{}",
+                data: &[0x3f],
+            })
+        );
+    }
+
+    #[test]
+    fn typed_data_without_domain() {
+        assert_eq!(
+            TypedData::decode(&[0, 0, 0, 0, 1, 0x3f]),
+            Ok(TypedData {
+                domain: Domain {
+                    name: None,
+                    version: None,
+                    network_id: None,
+                    contract_address: None,
+                },
+                aci: b"",
+                data: &[0x3f],
+            })
+        );
+        assert_eq!(
+            TypedData::decode(&[0, 0, 0, 0, 1, 0x3f, 0]),
+            Err(AppSW::TypedDataParsingFail)
+        );
+        assert_eq!(
+            TypedData::decode(&[0x01, 1, b'\n', 0, 0, 0, 0]),
+            Err(AppSW::TypedDataParsingFail)
+        );
+    }
+
+    #[test]
+    fn sign_delegation() {
+        let contract = [5u8; 32];
+        let data = [&[0, 0, 0, 1, 2][..], b"ae", &contract].concat();
+        let expected = SignDelegationRequest {
+            account_number: 1,
+            network_id: "ae",
+            contract: &contract,
+            name: None,
+        };
+        assert_eq!(
+            SignDelegationRequest::decode(&data, DelegationType::Preclaim),
+            Ok(expected)
+        );
+        assert_eq!(
+            SignDelegationRequest::decode(&data, DelegationType::Name),
+            Err(AppSW::DelegationParsingFail)
+        );
+
+        let data = [&data[..], &[8], b"ae.chain"].concat();
+        assert_eq!(
+            SignDelegationRequest::decode(&data, DelegationType::Name)
+                .unwrap()
+                .name,
+            Some("ae.chain")
+        );
+        assert_eq!(
+            SignDelegationRequest::decode(&data, DelegationType::Oracle),
+            Err(AppSW::DelegationParsingFail)
+        );
+    }
+
+    #[test]
+    fn sign_delegation_network_id() {
+        let contract = [5u8; 32];
+        let empty = [&[0, 0, 0, 1, 0][..], &contract].concat();
+        assert_eq!(
+            SignDelegationRequest::decode(&empty, DelegationType::Oracle),
+            Err(AppSW::DelegationParsingFail)
+        );
+        let long = [&[0, 0, 0, 1, 33][..], &[b'a'; 33], &contract].concat();
+        assert_eq!(
+            SignDelegationRequest::decode(&long, DelegationType::Oracle),
+            Err(AppSW::DelegationParsingFail)
+        );
+    }
+}
@@ -0,0 +1,83 @@
+//! Responses too long to fit in a single APDU.
+
+use alloc::vec::Vec;
+
+use crate::AppSW;
+
+/// Maximum length of the data of a response APDU.
+pub const MAX_RESPONSE_LEN: usize = 255;
+
+/// Response sent in several parts. The first part starts with the total
+/// length on 2 bytes, and the host asks for the next parts until it received
+/// all of them.
+#[derive(Default)]
+pub struct ChunkedResponse {
+    data: Vec<u8>,
+    sent: usize,
+}
+
+impl ChunkedResponse {
+    pub fn new(data: Vec<u8>) -> Result<Self, AppSW> {
+        // Make sure the length fits in the 2 bytes of the first part
+        u16::try_from(data.len()).map_err(|_| AppSW::TxWrongLength)?;
+        Ok(ChunkedResponse { data, sent: 0 })
+    }
+
+    /// The first part: the total length followed by as much data as fits.
+    pub fn first(&mut self) -> Vec<u8> {
+        let len = (self.data.len() as u16).to_be_bytes();
+        let end = self.data.len().min(MAX_RESPONSE_LEN - len.len());
+        self.sent = end;
+        [&len[..], &self.data[..end]].concat()
+    }
+
+    /// The next part, if some data was not sent yet.
+    pub fn next_part(&mut self) -> Option<&[u8]> {
+        if self.is_finished() {
+            return None;
+        }
+        let start = self.sent;
+        self.sent = self.data.len().min(start + MAX_RESPONSE_LEN);
+        Some(&self.data[start..self.sent])
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.sent == self.data.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_response() {
+        let mut response = ChunkedResponse::new([1, 2, 3].to_vec()).unwrap();
+        assert_eq!(response.first(), [0, 3, 1, 2, 3]);
+        assert!(response.is_finished());
+        assert_eq!(response.next_part(), None);
+    }
+
+    #[test]
+    fn long_response() {
+        let data = (0..600).map(|i| i as u8).collect::<Vec<_>>();
+        let mut response = ChunkedResponse::new(data.clone()).unwrap();
+
+        let first = response.first();
+        assert_eq!(first.len(), MAX_RESPONSE_LEN);
+        assert_eq!(first[..2], [0x02, 0x58]);
+
+        let mut received = first[2..].to_vec();
+        while let Some(part) = response.next_part() {
+            assert!(part.len() <= MAX_RESPONSE_LEN);
+            received.extend_from_slice(part);
+        }
+        assert_eq!(received, data);
+        assert!(response.is_finished());
+    }
+
+    #[test]
+    fn too_long() {
+        assert!(ChunkedResponse::new([0; 0x10000].to_vec()).is_err());
+    }
+}
@@ -0,0 +1,237 @@
+//! State of a SignTx sent over several APDUs.
+//!
+//! Between the first chunk of a transaction and the last part of its
+//! response, only the instructions of the same SignTx are expected. Any other
+//! instruction ends it, so that a host cannot interleave instructions to mix
+//! the state of several transactions.
+//!
+//! The chunks of a transaction are counted: all of them but the first and the
+//! last one must be full, so the number of chunks is known from the length of
+//! the transaction.
+
+use crate::instruction::{Instruction, TxResponse};
+use crate::AppSW;
+
+/// Length of the chunks of a transaction, apart from the first and the last
+/// one: the longest data of an APDU.
+pub const CHUNK_LEN: usize = 255;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TxSession {
+    #[default]
+    Idle,
+    /// The next chunks of a transaction, or of the transactions of a batch,
+    /// are expected
+    Chunks {
+        response: TxResponse,
+        batch: bool,
+        /// Number of chunks of the current transaction received so far,
+        /// including the first one
+        received: u32,
+        /// Number of chunks of the current transaction, from its length
+        expected: u32,
+    },
+    /// The next parts of a SignedTx, or of the signatures of a batch, are to
+    /// be sent
+    Response,
+}
+
+impl TxSession {
+    /// Whether an instruction belongs to a SignTx, and so does not end the
+    /// session.
+    pub fn is_tx_instruction(ins: &Instruction) -> bool {
+        matches!(
+            ins,
+            Instruction::SignTx { .. }
+                | Instruction::SignTxBatch { .. }
+                | Instruction::GetSignedTxChunk
+        )
+    }
+
+    /// Check that an instruction of a SignTx is the one expected next, before
+    /// handling it.
+    pub fn check(&self, ins: &Instruction) -> Result<(), AppSW> {
+        match (self, ins) {
+            // A transaction cannot start before the previous one was
+            // completely received
+            (
+                TxSession::Idle | TxSession::Response,
+                Instruction::SignTx {
+                    first_chunk: true, ..
+                }
+                | Instruction::SignTxBatch { first_chunk: true },
+            ) => Ok(()),
+            (
+                TxSession::Chunks {
+                    response,
+                    batch: false,
+                    ..
+                },
+                Instruction::SignTx {
+                    first_chunk: false,
+                    response: chunk_response,
+                },
+            ) if response == chunk_response => Ok(()),
+            (
+                TxSession::Chunks { batch: true, .. },
+                Instruction::SignTxBatch { first_chunk: false },
+            ) => Ok(()),
+            (TxSession::Response, Instruction::GetSignedTxChunk) => Ok(()),
+            (
+                _,
+                Instruction::SignTx { .. }
+                | Instruction::SignTxBatch { .. }
+                | Instruction::GetSignedTxChunk,
+            ) => Err(AppSW::BadState),
+            _ => Ok(()),
+        }
+    }
+
+    /// Session after the first chunk of a transaction, which holds
+    /// `first_len` of its `tx_len` bytes.
+    pub fn first_chunk(
+        response: TxResponse,
+        batch: bool,
+        tx_len: u32,
+        first_len: usize,
+    ) -> TxSession {
+        let rest_len = (tx_len as usize).saturating_sub(first_len);
+        TxSession::Chunks {
+            response,
+            batch,
+            received: 1,
+            expected: u32::try_from(rest_len.div_ceil(CHUNK_LEN))
+                .map_or(u32::MAX, |rest| rest.saturating_add(1)),
+        }
+    }
+
+    /// Session after the next chunk of the current transaction, holding `len`
+    /// bytes. Only the last expected chunk may be shorter than the others.
+    pub fn next_chunk(&self, len: usize) -> Result<TxSession, AppSW> {
+        let TxSession::Chunks {
+            response,
+            batch,
+            received,
+            expected,
+        } = *self
+        else {
+            return Err(AppSW::BadState);
+        };
+        let received = received + 1;
+        if received > expected || (received < expected && len != CHUNK_LEN) {
+            return Err(AppSW::TxUnexpectedChunk);
+        }
+        Ok(TxSession::Chunks {
+            response,
+            batch,
+            received,
+            expected,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIRST_CHUNK: Instruction = Instruction::SignTx {
+        first_chunk: true,
+        response: TxResponse::Signature,
+    };
+    const NEXT_CHUNK: Instruction = Instruction::SignTx {
+        first_chunk: false,
+        response: TxResponse::Signature,
+    };
+
+    #[test]
+    fn expected_instructions() {
+        let idle = TxSession::Idle;
+        assert_eq!(idle.check(&FIRST_CHUNK), Ok(()));
+        assert_eq!(idle.check(&NEXT_CHUNK), Err(AppSW::BadState));
+        assert_eq!(
+            idle.check(&Instruction::GetSignedTxChunk),
+            Err(AppSW::BadState)
+        );
+
+        let chunks = TxSession::first_chunk(TxResponse::Signature, false, 300, 200);
+        assert_eq!(chunks.check(&NEXT_CHUNK), Ok(()));
+        assert_eq!(chunks.check(&FIRST_CHUNK), Err(AppSW::BadState));
+        // The chunks must all ask for the same response
+        let signed_tx_chunk = Instruction::SignTx {
+            first_chunk: false,
+            response: TxResponse::SignedTx,
+        };
+        assert_eq!(chunks.check(&signed_tx_chunk), Err(AppSW::BadState));
+        assert_eq!(
+            chunks.check(&Instruction::GetSignedTxChunk),
+            Err(AppSW::BadState)
+        );
+
+        let response = TxSession::Response;
+        assert_eq!(response.check(&Instruction::GetSignedTxChunk), Ok(()));
+        assert_eq!(response.check(&FIRST_CHUNK), Ok(()));
+        assert_eq!(response.check(&NEXT_CHUNK), Err(AppSW::BadState));
+    }
+
+    #[test]
+    fn batch_chunks() {
+        let batch_chunk = Instruction::SignTxBatch { first_chunk: false };
+        let first_batch_chunk = Instruction::SignTxBatch { first_chunk: true };
+        assert_eq!(TxSession::Idle.check(&first_batch_chunk), Ok(()));
+        assert_eq!(TxSession::Idle.check(&batch_chunk), Err(AppSW::BadState));
+
+        // The chunks of a batch and of a single transaction cannot be mixed
+        let batch = TxSession::first_chunk(TxResponse::Signature, true, 300, 200);
+        assert_eq!(batch.check(&batch_chunk), Ok(()));
+        assert_eq!(batch.check(&NEXT_CHUNK), Err(AppSW::BadState));
+        let single = TxSession::first_chunk(TxResponse::Signature, false, 300, 200);
+        assert_eq!(single.check(&batch_chunk), Err(AppSW::BadState));
+        assert!(TxSession::is_tx_instruction(&batch_chunk));
+    }
+
+    #[test]
+    fn chunk_counter() {
+        // 200 bytes in the first chunk, then 2 full chunks and the last 10
+        // bytes
+        let mut session = TxSession::first_chunk(TxResponse::Signature, false, 720, 200);
+        assert_eq!(
+            session,
+            TxSession::Chunks {
+                response: TxResponse::Signature,
+                batch: false,
+                received: 1,
+                expected: 4
+            }
+        );
+        // Only the last chunk may be shorter
+        assert_eq!(session.next_chunk(100), Err(AppSW::TxUnexpectedChunk));
+        for len in [CHUNK_LEN, CHUNK_LEN, 10] {
+            session = session.next_chunk(len).unwrap();
+        }
+        assert_eq!(session.next_chunk(1), Err(AppSW::TxUnexpectedChunk));
+
+        // A transaction that fits in its first chunk
+        let whole = TxSession::first_chunk(TxResponse::SignedTx, false, 100, 100);
+        assert_eq!(whole.next_chunk(0), Err(AppSW::TxUnexpectedChunk));
+        assert_eq!(TxSession::Idle.next_chunk(CHUNK_LEN), Err(AppSW::BadState));
+    }
+
+    #[test]
+    fn other_instructions() {
+        assert!(TxSession::is_tx_instruction(&NEXT_CHUNK));
+        assert!(TxSession::is_tx_instruction(&Instruction::GetSignedTxChunk));
+        for ins in [
+            Instruction::GetVersion,
+            Instruction::GetAddress {
+                confirm_needed: false,
+            },
+            Instruction::Abort,
+        ] {
+            assert!(!TxSession::is_tx_instruction(&ins));
+            assert_eq!(
+                TxSession::first_chunk(TxResponse::Signature, false, 300, 200).check(&ins),
+                Ok(())
+            );
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use ledger_device_sdk::nbgl::SETTINGS_SIZE;
+use ledger_device_sdk::nvm::*;
+use ledger_device_sdk::NVMData;
+
+// This is necessary to store the object in NVM and not in RAM
+#[link_section = ".nvm_data"]
+static mut DATA: NVMData<AtomicStorage<[u8; SETTINGS_SIZE]>> =
+    NVMData::new(AtomicStorage::new(&[0u8; SETTINGS_SIZE]));
+
+/// Switches shown in the settings page, in the order of their indexes.
+pub const SETTINGS_STRINGS: [[&str; 2]; 3] = [
+    [
+        "Blind signing",
+        "Allow signing raw data, generalized account transactions, and unknown transaction types or versions by their hash",
+    ],
+    ["Expert mode", "Show all transaction fields"],
+    [
+        "Reset nonces",
+        "Forget the nonces signed so far, after a confirmation",
+    ],
+];
+
+/// Index of each switch in the settings storage.
+#[derive(Clone, Copy)]
+pub enum Setting {
+    BlindSigning = 0,
+    ExpertMode = 1,
+    /// Not a setting but a button: switching it on asks to confirm the reset
+    /// of the nonce tracker, and it is switched off again right away
+    ResetNonces = 2,
+}
+
+#[derive(Clone, Copy, Default)]
+pub struct Settings;
+
+impl Settings {
+    #[inline(never)]
+    pub fn get_mut(&mut self) -> &mut AtomicStorage<[u8; SETTINGS_SIZE]> {
+        let data = &raw mut DATA;
+        unsafe { (*data).get_mut() }
+    }
+
+    pub fn get_element(&self, index: usize) -> u8 {
+        let data = &raw const DATA;
+        let storage = unsafe { (*data).get_ref() };
+        let settings = storage.get_ref();
+        settings[index]
+    }
+
+    pub fn is_enabled(&self, setting: Setting) -> bool {
+        self.get_element(setting as usize) != 0
+    }
+
+    pub fn disable(&mut self, setting: Setting) {
+        let storage = self.get_mut();
+        let mut settings = *storage.get_ref();
+        settings[setting as usize] = 0;
+        storage.update(&settings);
+    }
+}
@@ -0,0 +1,85 @@
+//! Data kept in NVM, apart from the settings.
+
+use alloc::string::String;
+
+use ledger_device_sdk::ecc::{bip32_derive, make_bip32_path, CurvesId, Secret};
+use ledger_device_sdk::hmac::{sha2::Sha2_256, HMACInit};
+use ledger_device_sdk::nvm::*;
+use ledger_device_sdk::NVMData;
+
+use primitive_types::U256;
+
+use app_aeternity::address_book::{self, Entry, Storage, MAC_LEN, STORAGE_LEN};
+use app_aeternity::nonce;
+
+use crate::AppSW;
+
+// This is necessary to store the object in NVM and not in RAM
+#[link_section = ".nvm_data"]
+static mut ADDRESS_BOOK: NVMData<AtomicStorage<Storage>> =
+    NVMData::new(AtomicStorage::new(&[0u8; STORAGE_LEN]));
+
+#[link_section = ".nvm_data"]
+static mut NONCES: NVMData<AtomicStorage<nonce::Storage>> =
+    NVMData::new(AtomicStorage::new(&[0u8; nonce::STORAGE_LEN]));
+
+/// Path of the key of the MACs of the address book. It is not the path of an
+/// account, so the key is never used to sign anything.
+const MAC_KEY_PATH: [u32; 4] = make_bip32_path(b"m/44'/457'/0'/1'");
+
+/// Key of the MACs, derived from the seed so that entries cannot be forged
+/// without the device.
+fn mac_key() -> Option<Secret<64>> {
+    let mut key = Secret::<64>::new();
+    bip32_derive(CurvesId::Ed25519, &MAC_KEY_PATH, key.as_mut(), None).ok()?;
+    Some(key)
+}
+
+/// HMAC-SHA256 of an entry of the address book.
+fn mac(key: &Secret<64>, data: &[u8]) -> Option<[u8; MAC_LEN]> {
+    let mut output = [0; MAC_LEN];
+    Sha2_256::new(&key.as_ref()[..32])
+        .hmac(data, &mut output)
+        .ok()?;
+    Some(output)
+}
+
+/// Label of an address, if the user registered it.
+pub fn find_label(address: &str) -> Option<String> {
+    let data = &raw const ADDRESS_BOOK;
+    let storage = unsafe { (*data).get_ref() };
+    let key = mac_key()?;
+    address_book::find(storage.get_ref(), address, |data| mac(&key, data))
+}
+
+pub fn register_address(entry: &Entry) -> Result<(), AppSW> {
+    let data = &raw mut ADDRESS_BOOK;
+    let storage = unsafe { (*data).get_mut() };
+    let mut updated = *storage.get_ref();
+    let key = mac_key().ok_or(AppSW::AddressBookFail)?;
+    address_book::insert(&mut updated, entry, |data| mac(&key, data))?;
+    storage.update(&updated);
+    Ok(())
+}
+
+/// Forget all the signed nonces, once the user confirmed it.
+pub fn reset_nonces() {
+    let data = &raw mut NONCES;
+    let storage = unsafe { (*data).get_mut() };
+    storage.update(&[0; nonce::STORAGE_LEN]);
+}
+
+/// Whether a nonce was already signed for an account, or a higher one.
+pub fn is_nonce_reused(account_number: u32, network_id: &[u8], nonce: U256) -> bool {
+    let data = &raw const NONCES;
+    let storage = unsafe { (*data).get_ref() };
+    nonce::is_reused(storage.get_ref(), account_number, network_id, nonce)
+}
+
+pub fn record_nonce(account_number: u32, network_id: &[u8], nonce: U256) {
+    let data = &raw mut NONCES;
+    let storage = unsafe { (*data).get_mut() };
+    let mut updated = *storage.get_ref();
+    nonce::record(&mut updated, account_number, network_id, nonce);
+    storage.update(&updated);
+}
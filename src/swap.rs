@@ -0,0 +1,236 @@
+//! Checks done when the app is called as a library by the Exchange app, to
+//! take part in a swap.
+//!
+//! The Exchange app shows the swap to the user, so the transaction is signed
+//! without a second review. This is only safe if the transaction is exactly
+//! the one of the swap.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use primitive_types::U256;
+
+use crate::amount::display_amount;
+use crate::encoding::{from_ae_string, AeEncoding};
+use crate::network::MAINNET_ID;
+use crate::requests::{printable_str, SignTxHeader};
+use crate::tx::{ParsedTx, TxKind};
+use crate::AppSW;
+
+const HARDENED: u32 = 0x8000_0000;
+
+/// Account number of a BIP32 path, which must be `m/44'/457'/account'/0'/0'`
+/// like the paths of the addresses of the app. The path is given as 4 bytes
+/// big-endian integers.
+pub fn account_from_path(path: &[u8]) -> Option<u32> {
+    let path = path
+        .chunks(4)
+        .map(|index| Some(u32::from_be_bytes(index.try_into().ok()?)))
+        .collect::<Option<Vec<_>>>()?;
+
+    match path[..] {
+        [purpose, coin, account, change, index]
+            if purpose == 44 | HARDENED
+                && coin == 457 | HARDENED
+                && account & HARDENED != 0
+                && change == HARDENED
+                && index == HARDENED =>
+        {
+            Some(account & !HARDENED)
+        }
+        _ => None,
+    }
+}
+
+/// Amount of AE as shown by the Exchange app, given as a big-endian integer
+/// in Aettos.
+pub fn printable_amount(amount: &[u8]) -> Option<String> {
+    if amount.len() > 32 {
+        return None;
+    }
+    Some(format!(
+        "{} AE",
+        display_amount(U256::from_big_endian(amount))
+    ))
+}
+
+/// Parameters of the swap that the transaction must match.
+#[derive(Debug, PartialEq)]
+pub struct SwapParams {
+    pub amount: U256,
+    pub fee: U256,
+    pub destination: String,
+}
+
+impl SwapParams {
+    /// Check the parameters given by the Exchange app: amounts as big-endian
+    /// integers, and the destination as an `ak_` address.
+    pub fn new(amount: &[u8], fee: &[u8], destination: &[u8]) -> Option<Self> {
+        if amount.len() > 32 || fee.len() > 32 {
+            return None;
+        }
+        let destination = printable_str(destination)?;
+        if from_ae_string(destination, AeEncoding::AccountAddress)?.len() != 32 {
+            return None;
+        }
+
+        Some(SwapParams {
+            amount: U256::from_big_endian(amount),
+            fee: U256::from_big_endian(fee),
+            destination: destination.into(),
+        })
+    }
+
+    /// Make sure that the signature covers a plain mainnet transaction. The
+    /// Exchange app shows neither the network nor the other options of the
+    /// header, so the host cannot choose them.
+    pub fn check_header(&self, header: &SignTxHeader) -> Result<(), AppSW> {
+        if header.network_id == MAINNET_ID.as_bytes()
+            && !header.inner_tx
+            && header.recipient_name.is_none()
+        {
+            Ok(())
+        } else {
+            Err(AppSW::SwapCheckFail)
+        }
+    }
+
+    /// Make sure that the transaction is a spend of exactly the amount of the
+    /// swap, with the same fee, to the same destination. The Exchange app
+    /// gives no payload, so the transaction must not carry one that the user
+    /// never saw.
+    pub fn check_tx(&self, tx: &ParsedTx) -> Result<(), AppSW> {
+        if tx.kind == TxKind::Spend
+            && tx.amount == self.amount
+            && tx.fee == self.fee
+            && tx.recipient.as_deref() == Some(self.destination.as_str())
+            && tx.payload.is_empty()
+        {
+            Ok(())
+        } else {
+            Err(AppSW::SwapCheckFail)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::to_ae_string;
+
+    fn path(indexes: &[u32]) -> Vec<u8> {
+        indexes.iter().flat_map(|i| i.to_be_bytes()).collect()
+    }
+
+    #[test]
+    fn account_paths() {
+        let h = HARDENED;
+        assert_eq!(
+            account_from_path(&path(&[44 | h, 457 | h, h, h, h])),
+            Some(0)
+        );
+        assert_eq!(
+            account_from_path(&path(&[44 | h, 457 | h, 7 | h, h, h])),
+            Some(7)
+        );
+
+        assert_eq!(account_from_path(&path(&[44 | h, 457 | h, 7, h, h])), None);
+        assert_eq!(account_from_path(&path(&[44 | h, 60 | h, h, h, h])), None);
+        assert_eq!(account_from_path(&path(&[44 | h, 457 | h, h, h])), None);
+        assert_eq!(account_from_path(&path(&[44 | h, 457 | h, h, h, 1])), None);
+        assert_eq!(account_from_path(&[0x80, 0, 0]), None);
+    }
+
+    #[test]
+    fn amounts() {
+        assert_eq!(printable_amount(&[]).unwrap(), "0 AE");
+        let amount = 1_500_000_000_000_000_000u64.to_be_bytes();
+        assert_eq!(printable_amount(&amount).unwrap(), "1.5 AE");
+        assert_eq!(printable_amount(&[0; 33]), None);
+    }
+
+    #[test]
+    fn check_tx() {
+        let destination = to_ae_string(&[7; 32], AeEncoding::AccountAddress);
+        let params = SwapParams::new(&[0x10, 0], &[0x20], destination.as_bytes()).unwrap();
+        assert_eq!(params.amount, U256::from(0x1000));
+        assert_eq!(params.fee, U256::from(0x20));
+
+        let tx = tx_with(&destination, &params);
+        assert_eq!(params.check_tx(&tx), Ok(()));
+
+        let other = to_ae_string(&[8; 32], AeEncoding::AccountAddress);
+        for wrong in [
+            ParsedTx {
+                amount: params.amount + 1,
+                ..tx_with(&destination, &params)
+            },
+            ParsedTx {
+                fee: params.fee - 1,
+                ..tx_with(&destination, &params)
+            },
+            tx_with(&other, &params),
+            ParsedTx {
+                kind: TxKind::ContractCall,
+                ..tx_with(&destination, &params)
+            },
+            ParsedTx {
+                payload: "swap".into(),
+                ..tx_with(&destination, &params)
+            },
+        ] {
+            assert_eq!(params.check_tx(&wrong), Err(AppSW::SwapCheckFail));
+        }
+    }
+
+    #[test]
+    fn check_header() {
+        let destination = to_ae_string(&[7; 32], AeEncoding::AccountAddress);
+        let params = SwapParams::new(&[0x10], &[0x20], destination.as_bytes()).unwrap();
+        let header = SignTxHeader {
+            account_number: 0,
+            tx_len: 3,
+            inner_tx: false,
+            network_id: MAINNET_ID.as_bytes(),
+            recipient_name: None,
+            tx_chunk: &[0xc2, 0x0c, 0x01],
+        };
+        assert_eq!(params.check_header(&header), Ok(()));
+
+        for wrong in [
+            SignTxHeader {
+                network_id: b"ae_uat",
+                ..header
+            },
+            SignTxHeader {
+                inner_tx: true,
+                ..header
+            },
+            SignTxHeader {
+                recipient_name: Some("bob.chain"),
+                ..header
+            },
+        ] {
+            assert_eq!(params.check_header(&wrong), Err(AppSW::SwapCheckFail));
+        }
+    }
+
+    fn tx_with(recipient: &str, params: &SwapParams) -> ParsedTx {
+        ParsedTx {
+            recipient: Some(recipient.into()),
+            amount: params.amount,
+            fee: params.fee,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn invalid_params() {
+        let destination = to_ae_string(&[7; 32], AeEncoding::AccountAddress);
+        assert!(SwapParams::new(&[0; 33], &[], destination.as_bytes()).is_none());
+        assert!(SwapParams::new(&[], &[], b"ak_invalid").is_none());
+        let contract = to_ae_string(&[7; 32], AeEncoding::ContractAddress);
+        assert!(SwapParams::new(&[], &[], contract.as_bytes()).is_none());
+    }
+}
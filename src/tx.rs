@@ -0,0 +1,798 @@
+//! Decoding of the RLP-encoded transactions.
+
+use alloc::{borrow::ToOwned, format, string::String, vec::Vec};
+
+use primitive_types::U256;
+
+use crate::encoding::parse_address;
+use crate::fee::{check_fee, min_fee, FeeCheck};
+use crate::requests::printable_str;
+use crate::tx_stream::TxStream;
+use crate::AppSW;
+
+const SIGNED_TRANSACTION_TAG: u8 = 0x0b;
+const SIGNED_TRANSACTION_VERSION: u8 = 0x01;
+const SPEND_TRANSACTION_TAG: u8 = 0x0c;
+const CONTRACT_CREATE_TRANSACTION_TAG: u8 = 0x2a;
+const CONTRACT_CALL_TRANSACTION_TAG: u8 = 0x2b;
+const GA_META_TRANSACTION_TAG: u8 = 0x51;
+/// Gas of a SpendTx, which the base gas of the other types is a multiple of.
+const BASE_GAS: u64 = 15_000;
+/// Number of bytes of a payload that are kept to be shown. The rest is only
+/// hashed.
+pub const MAX_PAYLOAD_LEN: usize = 256;
+/// Longest transaction that can be sent back in a SignedTx. Such a
+/// transaction is kept whole until it is signed, while the others are only
+/// hashed as they are received.
+pub const MAX_SIGNED_TX_LEN: usize = 1024;
+
+/// Kind of the value of a transaction field, checked when decoding it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FieldKind {
+    /// Unsigned integer of up to 256 bits, without leading zeros
+    Int,
+    /// Id of an account, a name, a commitment, an oracle, a contract or a
+    /// channel
+    Id,
+    /// Arbitrary bytes, like the code of a contract, which are hashed but
+    /// not kept
+    Bytes,
+    /// Arbitrary bytes shown to the user, of which at most `MAX_PAYLOAD_LEN`
+    /// are kept
+    Payload,
+}
+
+/// Layout of a version of a transaction type: the name and kind of each field
+/// following the tag and the version, and the gas used by the type to compute
+/// the minimum fee.
+///
+/// Hard forks change the layouts by bumping the versions of the types, so a
+/// transaction is only decoded if the app knows the layout of its version.
+pub struct TxSchema {
+    pub tag: u8,
+    pub version: u8,
+    pub base_gas: u64,
+    pub fields: &'static [(&'static str, FieldKind)],
+}
+
+/// Versions of the transaction types that can be decoded. A type may appear
+/// several times, once for each of its versions.
+pub const TX_SCHEMAS: &[TxSchema] = &[
+    TxSchema {
+        tag: SPEND_TRANSACTION_TAG,
+        version: 1,
+        base_gas: BASE_GAS,
+        fields: &[
+            ("sender_id", FieldKind::Id),
+            ("recipient_id", FieldKind::Id),
+            ("amount", FieldKind::Int),
+            ("fee", FieldKind::Int),
+            ("ttl", FieldKind::Int),
+            ("nonce", FieldKind::Int),
+            ("payload", FieldKind::Payload),
+        ],
+    },
+    TxSchema {
+        tag: CONTRACT_CREATE_TRANSACTION_TAG,
+        version: 1,
+        base_gas: 5 * BASE_GAS,
+        fields: &[
+            ("owner_id", FieldKind::Id),
+            ("nonce", FieldKind::Int),
+            ("code", FieldKind::Bytes),
+            ("ct_version", FieldKind::Int),
+            ("fee", FieldKind::Int),
+            ("ttl", FieldKind::Int),
+            ("deposit", FieldKind::Int),
+            ("amount", FieldKind::Int),
+            ("gas", FieldKind::Int),
+            ("gas_price", FieldKind::Int),
+            ("call_data", FieldKind::Bytes),
+        ],
+    },
+    TxSchema {
+        tag: CONTRACT_CALL_TRANSACTION_TAG,
+        version: 1,
+        base_gas: 12 * BASE_GAS,
+        fields: &[
+            ("caller_id", FieldKind::Id),
+            ("nonce", FieldKind::Int),
+            ("contract_id", FieldKind::Id),
+            ("abi_version", FieldKind::Int),
+            ("fee", FieldKind::Int),
+            ("ttl", FieldKind::Int),
+            ("amount", FieldKind::Int),
+            ("gas", FieldKind::Int),
+            ("gas_price", FieldKind::Int),
+            ("call_data", FieldKind::Bytes),
+        ],
+    },
+    TxSchema {
+        tag: GA_META_TRANSACTION_TAG,
+        version: 2,
+        base_gas: 5 * BASE_GAS,
+        fields: &[
+            ("ga_id", FieldKind::Id),
+            ("auth_data", FieldKind::Bytes),
+            ("abi_version", FieldKind::Int),
+            ("fee", FieldKind::Int),
+            ("gas", FieldKind::Int),
+            ("gas_price", FieldKind::Int),
+            ("tx", FieldKind::Bytes),
+        ],
+    },
+];
+
+/// Version of the virtual machine of a contract, and of the ABI of its calls.
+pub struct VmVersion {
+    pub vm: u32,
+    pub abi: u32,
+    /// Name of the VM, with the protocol that introduced it
+    pub name: &'static str,
+}
+
+/// Versions of contracts that can be created. The AEVM and the first FATE
+/// cannot be used anymore since Iris.
+pub const VM_VERSIONS: &[VmVersion] = &[
+    VmVersion {
+        vm: 7,
+        abi: 3,
+        name: "FATE 2 (Iris)",
+    },
+    VmVersion {
+        vm: 8,
+        abi: 3,
+        name: "FATE 3 (Ceres)",
+    },
+];
+
+/// Error for a VM or an ABI that is not one of [`VM_VERSIONS`]: the ones
+/// newer than all of them most likely come from a later hard fork, while the
+/// older ones cannot be used anymore.
+fn vm_error(is_newer: bool) -> AppSW {
+    if is_newer {
+        AppSW::TxUnknownVm
+    } else {
+        AppSW::TxUnsupportedVm
+    }
+}
+
+/// Find the VM version of a contract, given as `vm << 16 | abi` like in the
+/// `ct_version` field.
+fn vm_version(ct_version: U256) -> Result<&'static VmVersion, AppSW> {
+    let ct_version = u32::try_from(ct_version).map_err(|_| AppSW::TxUnknownVm)?;
+    let (vm, abi) = (ct_version >> 16, ct_version & 0xffff);
+    VM_VERSIONS
+        .iter()
+        .find(|version| version.vm == vm && version.abi == abi)
+        .ok_or_else(|| vm_error(VM_VERSIONS.iter().all(|version| vm > version.vm)))
+}
+
+/// Make sure that the ABI of a call is the one of a VM that can be used.
+fn check_abi_version(abi: U256) -> Result<(), AppSW> {
+    if VM_VERSIONS.iter().any(|version| abi == version.abi.into()) {
+        Ok(())
+    } else {
+        Err(vm_error(
+            VM_VERSIONS.iter().all(|version| abi > version.abi.into()),
+        ))
+    }
+}
+
+/// Tags of the transaction types that can be decoded, in at least one version.
+pub fn supported_tags() -> Vec<u8> {
+    let mut tags = TX_SCHEMAS
+        .iter()
+        .map(|schema| schema.tag)
+        .collect::<Vec<_>>();
+    tags.sort_unstable();
+    tags.dedup();
+    tags
+}
+
+/// Schema of a version of a transaction type.
+pub(crate) fn find_schema(tag: u8, version: u8) -> Result<&'static TxSchema, AppSW> {
+    let mut schemas = TX_SCHEMAS
+        .iter()
+        .filter(|schema| schema.tag == tag)
+        .peekable();
+    if schemas.peek().is_none() {
+        return Err(AppSW::TxUnsupportedTag);
+    }
+    schemas
+        .find(|schema| schema.version == version)
+        .ok_or(AppSW::TxUnsupportedVersion)
+}
+
+/// Whether a transaction could not be decoded only because its layout is
+/// unknown, most likely because it comes from a later hard fork. Such a
+/// transaction can still be blind-signed.
+pub fn is_unknown_layout(error: AppSW) -> bool {
+    matches!(
+        error,
+        AppSW::TxUnsupportedTag | AppSW::TxUnsupportedVersion | AppSW::TxUnknownVm
+    )
+}
+
+/// Value of a decoded transaction field.
+#[derive(Debug, PartialEq)]
+pub enum FieldValue {
+    Int(U256),
+    Id(String),
+    /// The bytes that were kept, and the length of the whole field
+    Bytes(Vec<u8>, usize),
+}
+
+/// A transaction whose fields were checked against the schema of its type.
+pub struct DecodedTx {
+    pub schema: &'static TxSchema,
+    /// Length of the whole transaction
+    pub len: usize,
+    pub(crate) values: Vec<FieldValue>,
+}
+
+impl DecodedTx {
+    /// Decode a whole RLP-encoded transaction.
+    ///
+    /// The transaction must be a list starting with a known tag and the
+    /// version of its schema, followed by exactly the fields of the schema,
+    /// and nothing may follow it.
+    pub fn decode(data: &[u8]) -> Result<Self, AppSW> {
+        let mut stream = TxStream::new(data.len(), false);
+        stream.feed(data)?;
+        stream.finish()
+    }
+
+    fn value(&self, name: &str) -> Option<&FieldValue> {
+        self.schema
+            .fields
+            .iter()
+            .position(|(field_name, _)| *field_name == name)
+            .and_then(|index| self.values.get(index))
+    }
+
+    /// Value of an integer field of the schema.
+    pub fn int(&self, name: &str) -> Result<U256, AppSW> {
+        match self.value(name) {
+            Some(FieldValue::Int(value)) => Ok(*value),
+            _ => Err(AppSW::TxParsingFail),
+        }
+    }
+
+    /// Value of an id field of the schema.
+    pub fn id(&self, name: &str) -> Result<&str, AppSW> {
+        match self.value(name) {
+            Some(FieldValue::Id(value)) => Ok(value),
+            _ => Err(AppSW::TxParsingFail),
+        }
+    }
+
+    /// Kept bytes of a bytes field of the schema, and the length of the
+    /// whole field.
+    pub fn bytes(&self, name: &str) -> Result<(&[u8], usize), AppSW> {
+        match self.value(name) {
+            Some(FieldValue::Bytes(value, len)) => Ok((value, *len)),
+            _ => Err(AppSW::TxParsingFail),
+        }
+    }
+}
+
+/// Decode the value of a field, of which only the first bytes were kept if it
+/// is a bytes field.
+pub(crate) fn decode_field(kind: FieldKind, kept: &[u8], len: usize) -> Result<FieldValue, AppSW> {
+    match kind {
+        FieldKind::Int => decode_int(kept).map(FieldValue::Int),
+        FieldKind::Id => parse_address(kept).map(FieldValue::Id),
+        FieldKind::Bytes | FieldKind::Payload => Ok(FieldValue::Bytes(kept.to_vec(), len)),
+    }
+}
+
+/// Decode an integer, which must be encoded without leading zeros.
+fn decode_int(bytes: &[u8]) -> Result<U256, AppSW> {
+    if bytes.len() > 32 || bytes.first() == Some(&0) {
+        return Err(AppSW::TxParsingFail);
+    }
+    Ok(U256::from_big_endian(bytes))
+}
+
+/// Decode the tag or the version of a transaction.
+pub(crate) fn decode_u8(bytes: &[u8]) -> Result<u8, AppSW> {
+    match bytes {
+        [] => Ok(0),
+        [value] if *value != 0 => Ok(*value),
+        _ => Err(AppSW::TxParsingFail),
+    }
+}
+
+/// Transaction types that can be reviewed and signed.
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub enum TxKind {
+    #[default]
+    Spend,
+    ContractCreate,
+    ContractCall,
+    GaMeta,
+}
+
+/// Fields of a transaction that are reviewed by the user.
+#[derive(Default, Debug, PartialEq)]
+pub struct ParsedTx {
+    pub kind: TxKind,
+    /// Recipient of a spend, or the called contract
+    pub recipient: Option<String>,
+    /// Nonce of the sender, which GA meta transactions do not have
+    pub nonce: Option<U256>,
+    pub amount: U256,
+    pub fee: U256,
+    pub payload: String,
+    /// Minimum fee, computed from the type and the length of the transaction
+    pub min_fee: U256,
+    /// Deposit of a created contract
+    pub deposit: U256,
+    pub gas: U256,
+    pub gas_price: U256,
+    /// VM of a created contract
+    pub vm: Option<&'static str>,
+}
+
+impl ParsedTx {
+    /// Fields to review of a decoded transaction.
+    pub fn from_decoded(tx: &DecodedTx) -> Result<ParsedTx, AppSW> {
+        let min_fee = min_fee(tx.schema.base_gas, tx.len);
+        if matches!(
+            tx.schema.tag,
+            CONTRACT_CALL_TRANSACTION_TAG | GA_META_TRANSACTION_TAG
+        ) {
+            check_abi_version(tx.int("abi_version")?)?;
+        }
+        let parsed = match tx.schema.tag {
+            SPEND_TRANSACTION_TAG => ParsedTx {
+                kind: TxKind::Spend,
+                nonce: Some(tx.int("nonce")?),
+                recipient: Some(tx.id("recipient_id")?.to_owned()),
+                amount: tx.int("amount")?,
+                fee: tx.int("fee")?,
+                payload: format_payload(tx.bytes("payload")?),
+                min_fee,
+                ..Default::default()
+            },
+            CONTRACT_CREATE_TRANSACTION_TAG => ParsedTx {
+                kind: TxKind::ContractCreate,
+                nonce: Some(tx.int("nonce")?),
+                amount: tx.int("amount")?,
+                fee: tx.int("fee")?,
+                min_fee,
+                deposit: tx.int("deposit")?,
+                gas: tx.int("gas")?,
+                gas_price: tx.int("gas_price")?,
+                vm: Some(vm_version(tx.int("ct_version")?)?.name),
+                ..Default::default()
+            },
+            CONTRACT_CALL_TRANSACTION_TAG => ParsedTx {
+                kind: TxKind::ContractCall,
+                nonce: Some(tx.int("nonce")?),
+                recipient: Some(tx.id("contract_id")?.to_owned()),
+                amount: tx.int("amount")?,
+                fee: tx.int("fee")?,
+                min_fee,
+                gas: tx.int("gas")?,
+                gas_price: tx.int("gas_price")?,
+                ..Default::default()
+            },
+            GA_META_TRANSACTION_TAG => ParsedTx {
+                kind: TxKind::GaMeta,
+                fee: tx.int("fee")?,
+                min_fee,
+                gas: tx.int("gas")?,
+                gas_price: tx.int("gas_price")?,
+                ..Default::default()
+            },
+            _ => return Err(AppSW::TxUnsupportedTag),
+        };
+        // Reject transactions whose cost cannot even be shown
+        parsed.max_total_cost()?;
+        Ok(parsed)
+    }
+
+    pub fn fee_check(&self) -> FeeCheck {
+        check_fee(self.fee, self.min_fee)
+    }
+
+    /// Whether the transaction signs another one, which is not decoded and
+    /// so cannot be shown. It can only be blind-signed.
+    pub fn hides_inner_tx(&self) -> bool {
+        self.kind == TxKind::GaMeta
+    }
+
+    /// Whether the transaction can use gas, whose cost is paid on top of the
+    /// fee.
+    pub fn uses_gas(&self) -> bool {
+        self.kind != TxKind::Spend
+    }
+
+    /// Most that the gas of the transaction can cost, if all of it is used.
+    pub fn max_gas_cost(&self) -> Result<U256, AppSW> {
+        self.gas
+            .checked_mul(self.gas_price)
+            .ok_or(AppSW::TxCostOverflow)
+    }
+
+    /// Most that the sender can lose with the transaction:
+    /// amount + deposit + fee + gas × gas price.
+    pub fn max_total_cost(&self) -> Result<U256, AppSW> {
+        [self.deposit, self.fee, self.max_gas_cost()?]
+            .into_iter()
+            .try_fold(self.amount, U256::checked_add)
+            .ok_or(AppSW::TxCostOverflow)
+    }
+}
+
+/// Decode a whole transaction, and get the fields to review.
+pub fn parse_tx(data: &[u8]) -> Result<ParsedTx, AppSW> {
+    ParsedTx::from_decoded(&DecodedTx::decode(data)?)
+}
+
+/// The payload as is if it's printable text, or in hex otherwise, so that it
+/// can always be shown on the screen. A payload too long to be kept whole is
+/// cut, followed by its length.
+fn format_payload((payload, len): (&[u8], usize)) -> String {
+    let shown = match printable_str(payload) {
+        Some(text) => text.to_owned(),
+        None => ["0x", &hex::encode(payload)].concat(),
+    };
+    if len > payload.len() {
+        format!("{}... ({} bytes)", shown, len)
+    } else {
+        shown
+    }
+}
+
+/// RLP-encode a SignedTx, made of the signatures and the transaction they
+/// sign.
+pub fn encode_signed_tx(signatures: &[[u8; 64]], tx: &[u8]) -> Vec<u8> {
+    let signatures = signatures
+        .iter()
+        .flat_map(|signature| rlp_bytes(signature))
+        .collect::<Vec<_>>();
+    rlp_list(
+        &[
+            rlp_bytes(&[SIGNED_TRANSACTION_TAG]),
+            rlp_bytes(&[SIGNED_TRANSACTION_VERSION]),
+            rlp_list(&signatures),
+            rlp_bytes(tx),
+        ]
+        .concat(),
+    )
+}
+
+pub(crate) fn rlp_bytes(bytes: &[u8]) -> Vec<u8> {
+    match bytes {
+        [byte] if *byte < 0x80 => bytes.to_vec(),
+        _ => [rlp_len(0x80, bytes.len()), bytes.to_vec()].concat(),
+    }
+}
+
+pub(crate) fn rlp_list(payload: &[u8]) -> Vec<u8> {
+    [rlp_len(0xc0, payload.len()), payload.to_vec()].concat()
+}
+
+/// RLP-encode an integer field of a test transaction.
+#[cfg(test)]
+pub(crate) fn rlp_int(value: u64) -> Vec<u8> {
+    let bytes = value.to_be_bytes();
+    let start = bytes.iter().take_while(|byte| **byte == 0).count();
+    rlp_bytes(&bytes[start..])
+}
+
+/// RLP-encode an id field of a test transaction, whose 32 bytes are all
+/// `byte`.
+#[cfg(test)]
+pub(crate) fn rlp_id(id_type: u8, byte: u8) -> Vec<u8> {
+    rlp_bytes(&[&[id_type][..], &[byte; 32]].concat())
+}
+
+/// Prefix of an RLP byte array (offset 0x80) or list (offset 0xc0) holding
+/// `len` bytes.
+fn rlp_len(offset: u8, len: usize) -> Vec<u8> {
+    if len < 56 {
+        return [offset + len as u8].to_vec();
+    }
+    let len_bytes = len.to_be_bytes();
+    let start = len_bytes.iter().take_while(|byte| **byte == 0).count();
+    let len_len = (len_bytes.len() - start) as u8;
+    [&[offset + 55 + len_len][..], &len_bytes[start..]].concat()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amount::display_amount;
+    use aerlp::RlpItem;
+    use std::vec::Vec;
+
+    /// SpendTx of 20 AE with a fee of 0.00001684 AE and a "test" payload,
+    /// from `ak_11111111111111111111111111111111273Yts` to
+    /// `ak_16qJFWMMHFy3xDdLmvUeyc2S6FrWRhJP51HsvDYdz9d1FsYG`.
+    const SPEND_TX: &str = "f85e0c01a1010000000000000000000000000000000000000000000000000000000000000000a101000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f8901158e460913d00000860f50de37d00080018474657374";
+
+    fn spend_tx() -> Vec<u8> {
+        hex::decode(SPEND_TX).unwrap()
+    }
+
+    #[test]
+    fn spend_tx_fields() {
+        let tx = parse_tx(&spend_tx()).unwrap();
+        assert_eq!(tx.kind, TxKind::Spend);
+        assert_eq!(
+            tx.recipient.as_deref(),
+            Some("ak_16qJFWMMHFy3xDdLmvUeyc2S6FrWRhJP51HsvDYdz9d1FsYG")
+        );
+        assert_eq!(display_amount(tx.amount), "20");
+        assert_eq!(display_amount(tx.fee), "0.00001684");
+        assert_eq!(tx.payload, "test");
+        assert_eq!(tx.nonce, Some(U256::one()));
+        // 15000 gas, and 20 gas for each of the 96 bytes
+        assert_eq!(display_amount(tx.min_fee), "0.00001692");
+        assert_eq!(tx.fee_check(), FeeCheck::BelowMinimum);
+    }
+
+    /// ContractCallTx of 1 AE with a fee of 0.0002 AE, using at most 100000
+    /// gas at 0.000000001 AE each, with the given gas price if any.
+    fn contract_call_tx(abi_version: u64, gas_price: &[u8]) -> Vec<u8> {
+        let gas_price = match gas_price {
+            [] => rlp_int(1_000_000_000),
+            _ => rlp_bytes(gas_price),
+        };
+        rlp_list(
+            &[
+                rlp_int(43),
+                rlp_int(1),
+                rlp_id(1, 1),
+                rlp_int(1),
+                rlp_id(5, 2),
+                rlp_int(abi_version),
+                rlp_int(200_000_000_000_000),
+                rlp_int(0),
+                rlp_int(1_000_000_000_000_000_000),
+                rlp_int(100_000),
+                gas_price,
+                rlp_bytes(b"call data"),
+            ]
+            .concat(),
+        )
+    }
+
+    #[test]
+    fn contract_call_tx_fields() {
+        let tx = parse_tx(&contract_call_tx(3, &[])).unwrap();
+        assert_eq!(tx.kind, TxKind::ContractCall);
+        assert!(tx.uses_gas());
+        assert!(tx.recipient.as_ref().unwrap().starts_with("ct_"));
+        assert_eq!(display_amount(tx.amount), "1");
+        assert_eq!(display_amount(tx.fee), "0.0002");
+        assert_eq!(tx.gas, U256::from(100_000));
+        assert_eq!(display_amount(tx.max_gas_cost().unwrap()), "0.0001");
+        assert_eq!(display_amount(tx.max_total_cost().unwrap()), "1.0003");
+        assert_eq!(tx.payload, "");
+    }
+
+    #[test]
+    fn ga_meta_tx_fields() {
+        let inner_tx = rlp_list(&[rlp_int(12), rlp_int(1)].concat());
+        let tx = rlp_list(
+            &[
+                rlp_int(81),
+                rlp_int(2),
+                rlp_id(1, 1),
+                rlp_bytes(b"auth"),
+                rlp_int(3),
+                rlp_int(100_000_000_000_000),
+                rlp_int(50_000),
+                rlp_int(1_000_000_000),
+                rlp_bytes(&inner_tx),
+            ]
+            .concat(),
+        );
+        let tx = parse_tx(&tx).unwrap();
+        assert_eq!(tx.kind, TxKind::GaMeta);
+        assert_eq!(tx.nonce, None);
+        assert!(tx.hides_inner_tx());
+        // Only the fee and the gas of the GA meta transaction are known
+        assert_eq!(display_amount(tx.max_total_cost().unwrap()), "0.00015");
+        assert!(!parse_tx(&spend_tx()).unwrap().hides_inner_tx());
+    }
+
+    #[test]
+    fn cost_overflow() {
+        // A gas price of 2^248 makes gas × gas price overflow
+        let mut gas_price = [0; 32];
+        gas_price[0] = 1;
+        assert_eq!(
+            parse_tx(&contract_call_tx(3, &gas_price)),
+            Err(AppSW::TxCostOverflow)
+        );
+
+        // The sum overflows even though each part fits
+        let tx = ParsedTx {
+            kind: TxKind::ContractCreate,
+            amount: U256::MAX,
+            fee: U256::one(),
+            ..Default::default()
+        };
+        assert_eq!(tx.max_total_cost(), Err(AppSW::TxCostOverflow));
+    }
+
+    #[test]
+    fn vm_versions() {
+        let ct_version = |vm: u32, abi: u32| U256::from(vm << 16 | abi);
+        assert_eq!(vm_version(ct_version(7, 3)).unwrap().name, "FATE 2 (Iris)");
+        assert_eq!(vm_version(ct_version(8, 3)).unwrap().name, "FATE 3 (Ceres)");
+        // AEVM, the first FATE, and an ABI that does not match the VM
+        for (vm, abi) in [(6, 1), (5, 3), (8, 1)] {
+            assert_eq!(
+                vm_version(ct_version(vm, abi)).err(),
+                Some(AppSW::TxUnsupportedVm)
+            );
+        }
+        // VMs of a later hard fork
+        for ct_version in [ct_version(9, 3), ct_version(9, 4), U256::MAX] {
+            assert_eq!(vm_version(ct_version).err(), Some(AppSW::TxUnknownVm));
+        }
+
+        // A call to an AEVM contract, and with an ABI of a later hard fork
+        assert_eq!(
+            parse_tx(&contract_call_tx(1, &[])),
+            Err(AppSW::TxUnsupportedVm)
+        );
+        assert_eq!(parse_tx(&contract_call_tx(4, &[])), Err(AppSW::TxUnknownVm));
+    }
+
+    #[test]
+    fn schema_versions() {
+        assert_eq!(find_schema(0x0c, 1).unwrap().fields.len(), 7);
+        assert_eq!(find_schema(0x51, 2).unwrap().base_gas, 75_000);
+        // The first version of GAMetaTx cannot be used anymore
+        assert_eq!(
+            find_schema(0x51, 1).err(),
+            Some(AppSW::TxUnsupportedVersion)
+        );
+        assert_eq!(find_schema(0x20, 1).err(), Some(AppSW::TxUnsupportedTag));
+        assert_eq!(supported_tags(), [0x0c, 0x2a, 0x2b, 0x51]);
+
+        for error in [
+            AppSW::TxUnsupportedTag,
+            AppSW::TxUnsupportedVersion,
+            AppSW::TxUnknownVm,
+        ] {
+            assert!(is_unknown_layout(error));
+        }
+        assert!(!is_unknown_layout(AppSW::TxParsingFail));
+        assert!(!is_unknown_layout(AppSW::TxUnsupportedVm));
+    }
+
+    #[test]
+    fn other_tx_type() {
+        let mut tx = spend_tx();
+        // Turn the tag into the one of a NameClaimTx
+        tx[2] = 0x20;
+        assert_eq!(parse_tx(&tx), Err(AppSW::TxUnsupportedTag));
+    }
+
+    #[test]
+    fn binary_payload() {
+        let mut tx = spend_tx();
+        // Replace the "test" payload by 4 bytes that are not printable
+        let len = tx.len();
+        tx[len - 4..].copy_from_slice(&[0x00, 0x0a, 0xff, 0x41]);
+        assert_eq!(parse_tx(&tx).unwrap().payload, "0x000aff41");
+    }
+
+    #[test]
+    fn missing_fields() {
+        // A list with only the tag and the version of a spend tx
+        assert_eq!(parse_tx(&[0xc2, 0x0c, 0x01]), Err(AppSW::TxParsingFail));
+        assert_eq!(parse_tx(&[0xc0]), Err(AppSW::TxParsingFail));
+    }
+
+    #[test]
+    fn wrong_version() {
+        let mut tx = spend_tx();
+        tx[3] = 0x02;
+        assert_eq!(parse_tx(&tx), Err(AppSW::TxUnsupportedVersion));
+    }
+
+    #[test]
+    fn extra_field() {
+        // Append an empty byte array to the list
+        let mut tx = spend_tx();
+        tx[1] += 1;
+        tx.push(0x80);
+        assert_eq!(parse_tx(&tx), Err(AppSW::TxParsingFail));
+    }
+
+    #[test]
+    fn trailing_data() {
+        let mut tx = spend_tx();
+        tx.push(0x00);
+        assert_eq!(parse_tx(&tx), Err(AppSW::TxLengthMismatch));
+    }
+
+    #[test]
+    fn non_canonical_int() {
+        // Prefix the fee with a zero byte
+        let mut tx = spend_tx();
+        let fee = SPEND_TX.find("860f50de37d000").unwrap() / 2;
+        tx[1] += 1;
+        tx[fee] += 1;
+        tx.insert(fee + 1, 0x00);
+        assert_eq!(parse_tx(&tx), Err(AppSW::TxParsingFail));
+    }
+
+    #[test]
+    fn invalid_recipient() {
+        // Use an unknown tag for the recipient id
+        let mut tx = spend_tx();
+        let recipient = SPEND_TX.find("a101000102").unwrap() / 2;
+        tx[recipient + 1] = 0x07;
+        assert_eq!(parse_tx(&tx), Err(AppSW::TxBadAddressPrefix));
+    }
+
+    #[test]
+    fn list_payload() {
+        // Replace the "test" payload by a list of the same length
+        let mut tx = spend_tx();
+        let len = tx.len();
+        tx[len - 5] = 0xc4;
+        assert_eq!(parse_tx(&tx), Err(AppSW::TxInvalidPayload));
+    }
+
+    #[test]
+    fn field_accessors() {
+        let tx = DecodedTx::decode(&spend_tx()).unwrap();
+        assert_eq!(tx.int("nonce"), Ok(U256::one()));
+        assert_eq!(tx.int("ttl"), Ok(U256::zero()));
+        assert_eq!(
+            tx.id("sender_id"),
+            Ok("ak_11111111111111111111111111111111273Yts")
+        );
+        // Wrong kind or unknown field
+        assert_eq!(tx.int("payload"), Err(AppSW::TxParsingFail));
+        assert_eq!(tx.bytes("gas"), Err(AppSW::TxParsingFail));
+    }
+
+    #[test]
+    fn signed_tx() {
+        let tx = spend_tx();
+        let signed = encode_signed_tx(&[[7; 64]], &tx);
+
+        // 1 + 1 + (2 + 2 + 64) + (2 + 96) bytes of fields
+        assert_eq!(
+            &signed[..8],
+            [0xf8, 0xa8, 0x0b, 0x01, 0xf8, 0x42, 0xb8, 0x40]
+        );
+        assert_eq!(&signed[8..72], [7; 64]);
+        assert_eq!(&signed[72..74], [0xb8, 0x60]);
+        assert_eq!(&signed[74..], tx);
+
+        let (item, rest) = RlpItem::try_deserialize(&signed).unwrap();
+        assert!(rest.is_empty());
+        assert_eq!(item.list().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn rlp_lengths() {
+        assert_eq!(rlp_bytes(&[0x7f]), [0x7f]);
+        assert_eq!(rlp_bytes(&[0x80]), [0x81, 0x80]);
+        assert_eq!(rlp_bytes(&[]), [0x80]);
+        assert_eq!(rlp_len(0x80, 55), [0xb7]);
+        assert_eq!(rlp_len(0x80, 56), [0xb8, 56]);
+        assert_eq!(rlp_len(0xc0, 0x1234), [0xf9, 0x12, 0x34]);
+    }
+
+    #[test]
+    fn invalid_rlp() {
+        assert_eq!(parse_tx(&[]), Err(AppSW::TxParsingFail));
+        // A byte array instead of a list
+        assert_eq!(parse_tx(&[0x82, 0x0c, 0x01]), Err(AppSW::TxParsingFail));
+    }
+}
@@ -0,0 +1,580 @@
+//! Decoding of RLP-encoded transactions received in several chunks.
+//!
+//! A transaction can be much larger than the heap, mostly because of the code
+//! of a created contract. The chunks are decoded as they are received, and
+//! only the fields that are reviewed are kept: integers, ids and the start of
+//! the payload. The code and the call data are only hashed by the caller.
+
+use alloc::vec::Vec;
+
+use crate::tx::{
+    decode_field, decode_u8, find_schema, is_unknown_layout, DecodedTx, FieldKind, FieldValue,
+    TxSchema, MAX_PAYLOAD_LEN,
+};
+use crate::AppSW;
+
+/// The longest header has 1 byte followed by a length of up to 8 bytes.
+const MAX_HEADER_LEN: usize = 9;
+
+/// Header of an RLP item.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Header {
+    is_list: bool,
+    /// Length of the payload of the item
+    len: usize,
+    /// Value of a single byte, which is its own payload below 0x80, and
+    /// follows 0x81 otherwise
+    byte: Option<u8>,
+}
+
+/// Part of the transaction expected next.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum State {
+    /// Header of the list of the transaction
+    #[default]
+    ListHeader,
+    /// Header of the next item of the list
+    ItemHeader,
+    /// Payload of an item, of which `remain` bytes are still expected
+    Item {
+        kind: Option<FieldKind>,
+        remain: usize,
+    },
+    /// Rest of a transaction whose layout is unknown, which is only hashed
+    Opaque,
+    /// The whole transaction was received
+    Done,
+}
+
+/// Decoder of a transaction fed with its chunks.
+///
+/// The transaction must be a list of exactly `tx_len` bytes, starting with a
+/// tag and a version. Transactions whose layout is unknown are refused as soon
+/// as their version is received, unless `allow_unknown_layout` is set, in
+/// which case the rest of them is skipped.
+#[derive(Default)]
+pub struct TxStream {
+    tx_len: usize,
+    received: usize,
+    allow_unknown_layout: bool,
+    state: State,
+    /// Bytes received so far of the header being read
+    header: [u8; MAX_HEADER_LEN],
+    header_len: usize,
+    /// Number of items of the list that were read, including the tag and the
+    /// version
+    items: usize,
+    tag: Option<u8>,
+    version: Option<u8>,
+    schema: Option<&'static TxSchema>,
+    /// Why the layout of the transaction is unknown
+    layout_error: Option<AppSW>,
+    /// Kept bytes of the item being read, and the length of its payload
+    item: Vec<u8>,
+    item_len: usize,
+    values: Vec<FieldValue>,
+}
+
+impl TxStream {
+    pub fn new(tx_len: usize, allow_unknown_layout: bool) -> Self {
+        TxStream {
+            tx_len,
+            allow_unknown_layout,
+            ..Default::default()
+        }
+    }
+
+    /// Tag and version of the transaction, once they were received.
+    pub fn tx_type(&self) -> Option<(u8, u8)> {
+        self.tag.zip(self.version)
+    }
+
+    /// Decode the next chunk of the transaction.
+    pub fn feed(&mut self, mut data: &[u8]) -> Result<(), AppSW> {
+        if data.len() > self.tx_len - self.received {
+            return Err(AppSW::TxLengthMismatch);
+        }
+
+        while !data.is_empty() {
+            match self.state {
+                State::ListHeader | State::ItemHeader => {
+                    if let Some(header) = self.read_header(&mut data)? {
+                        self.start_item(header)?;
+                    }
+                }
+                State::Item { kind, remain } => {
+                    let (bytes, rest) = data.split_at(remain.min(data.len()));
+                    let keep = max_kept_len(kind).saturating_sub(self.item.len());
+                    self.item.extend_from_slice(&bytes[..keep.min(bytes.len())]);
+                    self.received += bytes.len();
+                    data = rest;
+
+                    let remain = remain - bytes.len();
+                    self.state = State::Item { kind, remain };
+                    if remain == 0 {
+                        self.end_item()?;
+                    }
+                }
+                State::Opaque => {
+                    self.received += data.len();
+                    data = &[];
+                }
+                // Feeding more than the length of the transaction was refused
+                State::Done => return Err(AppSW::TxLengthMismatch),
+            }
+        }
+        Ok(())
+    }
+
+    /// The decoded transaction, once all of it was received.
+    pub fn finish(&mut self) -> Result<DecodedTx, AppSW> {
+        match (self.state, self.schema, self.layout_error) {
+            (State::Done, Some(schema), _) => Ok(DecodedTx {
+                schema,
+                len: self.tx_len,
+                values: core::mem::take(&mut self.values),
+            }),
+            (State::Opaque, _, Some(error)) if self.received == self.tx_len => Err(error),
+            _ => Err(AppSW::TxParsingFail),
+        }
+    }
+
+    /// Read the bytes of a header, and decode it once all of them were read.
+    fn read_header(&mut self, data: &mut &[u8]) -> Result<Option<Header>, AppSW> {
+        while let Some((&byte, rest)) = data.split_first() {
+            self.header[self.header_len] = byte;
+            self.header_len += 1;
+            self.received += 1;
+            *data = rest;
+
+            if let Some(header) = decode_header(&self.header[..self.header_len])? {
+                self.header_len = 0;
+                return Ok(Some(header));
+            }
+        }
+        Ok(None)
+    }
+
+    fn start_item(&mut self, header: Header) -> Result<(), AppSW> {
+        let remain_tx_len = self.tx_len - self.received;
+        if self.state == State::ListHeader {
+            if !header.is_list || header.len > remain_tx_len {
+                return Err(AppSW::TxParsingFail);
+            }
+            if header.len < remain_tx_len {
+                return Err(AppSW::TxLengthMismatch);
+            }
+            return self.end_of_list();
+        }
+
+        if header.len > remain_tx_len {
+            return Err(AppSW::TxParsingFail);
+        }
+        let kind = match self.items {
+            0 | 1 => None,
+            index => {
+                // The schema was found once the version was read
+                let fields = self.schema.map(|schema| schema.fields);
+                let (_, kind) = fields
+                    .and_then(|fields| fields.get(index - 2))
+                    .ok_or(AppSW::TxParsingFail)?;
+                Some(*kind)
+            }
+        };
+        check_item(kind, &header)?;
+
+        self.item = match header.byte {
+            Some(byte) => [byte].to_vec(),
+            None => Vec::new(),
+        };
+        self.item_len = header.byte.map_or(header.len, |_| 1);
+        self.state = State::Item {
+            kind,
+            remain: header.len,
+        };
+        if header.len == 0 {
+            self.end_item()?;
+        }
+        Ok(())
+    }
+
+    fn end_item(&mut self) -> Result<(), AppSW> {
+        let item = core::mem::take(&mut self.item);
+        match self.items {
+            0 => self.tag = Some(decode_u8(&item)?),
+            1 => {
+                let version = decode_u8(&item)?;
+                self.version = Some(version);
+                let tag = self.tag.unwrap_or_default();
+                match find_schema(tag, version) {
+                    Ok(schema) => self.schema = Some(schema),
+                    Err(error) if is_unknown_layout(error) && self.allow_unknown_layout => {
+                        self.layout_error = Some(error);
+                        self.state = State::Opaque;
+                        return Ok(());
+                    }
+                    Err(error) => return Err(error),
+                }
+            }
+            _ => {
+                let State::Item {
+                    kind: Some(kind), ..
+                } = self.state
+                else {
+                    return Err(AppSW::TxParsingFail);
+                };
+                self.values.push(decode_field(kind, &item, self.item_len)?);
+            }
+        }
+        self.items += 1;
+        self.end_of_list()
+    }
+
+    /// Expect the next item, or make sure that all the fields were read at
+    /// the end of the list.
+    fn end_of_list(&mut self) -> Result<(), AppSW> {
+        if self.received < self.tx_len {
+            self.state = State::ItemHeader;
+            return Ok(());
+        }
+
+        let schema = self.schema.ok_or(AppSW::TxParsingFail)?;
+        if self.values.len() != schema.fields.len() {
+            return Err(AppSW::TxParsingFail);
+        }
+        self.state = State::Done;
+        Ok(())
+    }
+}
+
+/// Decode a header from its first bytes, if there are enough of them.
+///
+/// The header must be the shortest one for its payload, like the node
+/// requires, or the transaction could not be the one that is signed: a single
+/// byte below 0x80 is not prefixed, and long lengths are only used from 56
+/// bytes, without leading zeros. A single byte after 0x81 is read with its
+/// header to check it.
+fn decode_header(bytes: &[u8]) -> Result<Option<Header>, AppSW> {
+    let Some((&first, len_bytes)) = bytes.split_first() else {
+        return Ok(None);
+    };
+    let (is_list, short_len, len_len) = match first {
+        0..=0x7f => {
+            return Ok(Some(Header {
+                is_list: false,
+                len: 0,
+                byte: Some(first),
+            }))
+        }
+        0x81 => {
+            return match len_bytes {
+                [] => Ok(None),
+                [byte] if *byte >= 0x80 => Ok(Some(Header {
+                    is_list: false,
+                    len: 0,
+                    byte: Some(*byte),
+                })),
+                _ => Err(AppSW::TxParsingFail),
+            }
+        }
+        0x80..=0xb7 => (false, first - 0x80, 0),
+        0xb8..=0xbf => (false, 0, first - 0xb7),
+        0xc0..=0xf7 => (true, first - 0xc0, 0),
+        0xf8..=0xff => (true, 0, first - 0xf7),
+    };
+    // Lengths longer than 4 bytes cannot fit in the length of a transaction
+    if len_len > 4 {
+        return Err(AppSW::TxParsingFail);
+    }
+    if len_bytes.len() < len_len as usize {
+        return Ok(None);
+    }
+
+    let len = len_bytes
+        .iter()
+        .fold(short_len as usize, |len, byte| len << 8 | *byte as usize);
+    if len_len > 0 && (len_bytes[0] == 0 || len < 56) {
+        return Err(AppSW::TxParsingFail);
+    }
+    Ok(Some(Header {
+        is_list,
+        len,
+        byte: None,
+    }))
+}
+
+/// Make sure that an item can be the value of a field, or the tag or the
+/// version if it has no kind, before reading it.
+fn check_item(kind: Option<FieldKind>, header: &Header) -> Result<(), AppSW> {
+    let (error, max_len) = match kind {
+        Some(FieldKind::Bytes | FieldKind::Payload) => (AppSW::TxInvalidPayload, usize::MAX),
+        Some(FieldKind::Id) if !header.is_list && header.len > 33 => {
+            return Err(AppSW::TxBadAddressLength)
+        }
+        Some(FieldKind::Int) => (AppSW::TxParsingFail, 32),
+        Some(FieldKind::Id) => (AppSW::TxParsingFail, 33),
+        None => (AppSW::TxParsingFail, 1),
+    };
+    if header.is_list {
+        return Err(error);
+    }
+    if header.len > max_len {
+        return Err(AppSW::TxParsingFail);
+    }
+    Ok(())
+}
+
+/// Number of bytes of an item that are kept to decode it.
+fn max_kept_len(kind: Option<FieldKind>) -> usize {
+    match kind {
+        None => 1,
+        Some(FieldKind::Int) => 32,
+        Some(FieldKind::Id) => 33,
+        Some(FieldKind::Bytes) => 0,
+        Some(FieldKind::Payload) => MAX_PAYLOAD_LEN,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tx::{rlp_bytes, rlp_id, rlp_int, rlp_list, ParsedTx, TxKind};
+    use std::vec::Vec;
+
+    fn spend_tx(payload: &[u8]) -> Vec<u8> {
+        rlp_list(
+            &[
+                rlp_int(12),
+                rlp_int(1),
+                rlp_id(1, 1),
+                rlp_id(1, 1),
+                rlp_int(20),
+                rlp_int(20_000_000_000_000),
+                rlp_int(0),
+                rlp_int(7),
+                rlp_bytes(payload),
+            ]
+            .concat(),
+        )
+    }
+
+    /// ContractCreateTx of a FATE 3 contract with `code_len` bytes of code.
+    fn contract_create_tx(code_len: usize) -> Vec<u8> {
+        rlp_list(
+            &[
+                rlp_int(42),
+                rlp_int(1),
+                rlp_id(1, 1),
+                rlp_int(3),
+                rlp_bytes(&std::vec![0xfe; code_len]),
+                rlp_int(8 << 16 | 3),
+                rlp_int(1_000_000_000_000_000),
+                rlp_int(0),
+                rlp_int(0),
+                rlp_int(5),
+                rlp_int(100_000),
+                rlp_int(1_000_000_000),
+                rlp_bytes(&std::vec![0x2b; 1000]),
+            ]
+            .concat(),
+        )
+    }
+
+    /// Decode a transaction fed in chunks of `chunk_len` bytes.
+    fn decode_in_chunks(tx: &[u8], chunk_len: usize) -> Result<DecodedTx, AppSW> {
+        let mut stream = TxStream::new(tx.len(), false);
+        for chunk in tx.chunks(chunk_len) {
+            stream.feed(chunk)?;
+            assert!(stream.item.len() <= MAX_PAYLOAD_LEN);
+        }
+        stream.finish()
+    }
+
+    #[test]
+    fn chunk_boundaries() {
+        let tx = spend_tx(b"test");
+        let parsed = ParsedTx::from_decoded(&decode_in_chunks(&tx, tx.len()).unwrap()).unwrap();
+        assert_eq!(parsed.nonce, Some(7.into()));
+        assert_eq!(parsed.payload, "test");
+
+        // Every split of the transaction in two chunks, and one byte at a time
+        for split in 0..tx.len() {
+            let mut stream = TxStream::new(tx.len(), false);
+            stream.feed(&tx[..split]).unwrap();
+            stream.feed(&tx[split..]).unwrap();
+            let decoded = stream.finish().unwrap();
+            assert_eq!(ParsedTx::from_decoded(&decoded).unwrap(), parsed);
+        }
+        let decoded = decode_in_chunks(&tx, 1).unwrap();
+        assert_eq!(ParsedTx::from_decoded(&decoded).unwrap(), parsed);
+    }
+
+    #[test]
+    fn large_contract_create() {
+        // Far more than the heap of the app, with a length on 2 bytes
+        let tx = contract_create_tx(20_000);
+        let decoded = decode_in_chunks(&tx, 250).unwrap();
+        assert_eq!(decoded.len, tx.len());
+        // Only the length of the code and of the call data is kept
+        assert_eq!(decoded.bytes("code"), Ok((&[][..], 20_000)));
+        assert_eq!(decoded.bytes("call_data"), Ok((&[][..], 1000)));
+
+        let parsed = ParsedTx::from_decoded(&decoded).unwrap();
+        assert_eq!(parsed.kind, TxKind::ContractCreate);
+        assert_eq!(parsed.vm, Some("FATE 3 (Ceres)"));
+        assert_eq!(parsed.gas, 100_000.into());
+        assert_eq!(parsed.nonce, Some(3.into()));
+    }
+
+    #[test]
+    fn long_payload() {
+        let tx = spend_tx(&[b'a'; 1000]);
+        let decoded = decode_in_chunks(&tx, 100).unwrap();
+        let (kept, len) = decoded.bytes("payload").unwrap();
+        assert_eq!((kept.len(), len), (MAX_PAYLOAD_LEN, 1000));
+
+        let payload = ParsedTx::from_decoded(&decoded).unwrap().payload;
+        assert!(payload.starts_with("aaaa"));
+        assert!(payload.ends_with("a... (1000 bytes)"));
+    }
+
+    #[test]
+    fn unknown_layout() {
+        // NameClaimTx, refused once its version is received
+        let tx = rlp_list(
+            &[
+                rlp_int(0x20),
+                rlp_int(2),
+                rlp_id(1, 1),
+                rlp_bytes(&[0; 300]),
+            ]
+            .concat(),
+        );
+        let mut stream = TxStream::new(tx.len(), false);
+        assert_eq!(stream.feed(&tx[..6]), Err(AppSW::TxUnsupportedTag));
+
+        // Skipped until the end when it can be blind-signed
+        let mut stream = TxStream::new(tx.len(), true);
+        for chunk in tx.chunks(100) {
+            stream.feed(chunk).unwrap();
+        }
+        assert_eq!(stream.tx_type(), Some((0x20, 2)));
+        assert_eq!(stream.finish().err(), Some(AppSW::TxUnsupportedTag));
+
+        // Only the tag and the version are needed
+        let mut stream = TxStream::new(3, true);
+        stream.feed(&[0xc2, 0x20, 0x02]).unwrap();
+        assert_eq!(stream.finish().err(), Some(AppSW::TxUnsupportedTag));
+        let mut stream = TxStream::new(2, true);
+        assert_eq!(stream.feed(&[0xc1, 0x20]), Err(AppSW::TxParsingFail));
+        assert_eq!(stream.tx_type(), None);
+    }
+
+    #[test]
+    fn lengths() {
+        let tx = spend_tx(b"test");
+
+        // Not all of the transaction was received
+        let mut stream = TxStream::new(tx.len(), false);
+        stream.feed(&tx[..tx.len() - 1]).unwrap();
+        assert_eq!(stream.finish().err(), Some(AppSW::TxParsingFail));
+        assert_eq!(stream.feed(&[0, 0]), Err(AppSW::TxLengthMismatch));
+
+        // The list is longer or shorter than the transaction
+        let mut stream = TxStream::new(tx.len() - 1, false);
+        assert_eq!(stream.feed(&tx[..2]), Err(AppSW::TxParsingFail));
+        let mut stream = TxStream::new(tx.len() + 1, false);
+        assert_eq!(stream.feed(&tx[..2]), Err(AppSW::TxLengthMismatch));
+
+        // A field goes beyond the end of the list
+        let mut tx = tx;
+        let len = tx.len();
+        tx[len - 5] = 0x85;
+        let mut stream = TxStream::new(tx.len(), false);
+        assert_eq!(stream.feed(&tx), Err(AppSW::TxParsingFail));
+    }
+
+    #[test]
+    fn headers() {
+        assert_eq!(decode_header(&[]), Ok(None));
+        assert_eq!(
+            decode_header(&[0x05]),
+            Ok(Some(Header {
+                is_list: false,
+                len: 0,
+                byte: Some(5)
+            }))
+        );
+        assert_eq!(decode_header(&[0xb9, 0x12]), Ok(None));
+        assert_eq!(
+            decode_header(&[0xb9, 0x12, 0x34])
+                .unwrap()
+                .map(|header| header.len),
+            Some(0x1234)
+        );
+        assert_eq!(
+            decode_header(&[0xf8, 0x60])
+                .unwrap()
+                .map(|header| header.is_list),
+            Some(true)
+        );
+        assert_eq!(decode_header(&[0xbc]), Err(AppSW::TxParsingFail));
+    }
+
+    #[test]
+    fn non_canonical_headers() {
+        // A single byte of 0x80 or more follows 0x81
+        assert_eq!(decode_header(&[0x81]), Ok(None));
+        assert_eq!(
+            decode_header(&[0x81, 0x80]),
+            Ok(Some(Header {
+                is_list: false,
+                len: 0,
+                byte: Some(0x80)
+            }))
+        );
+        // A byte below 0x80 is its own header, and long lengths are only used
+        // from 56 bytes, without leading zeros
+        for header in [
+            &[0x81, 0x05][..],
+            &[0xb8, 0x37],
+            &[0xb9, 0x00, 0x40],
+            &[0xf8, 0x10],
+            &[0xf9, 0x00, 0x40],
+        ] {
+            assert_eq!(decode_header(header), Err(AppSW::TxParsingFail));
+        }
+
+        // The amount of a SpendTx, as a single byte after 0x81
+        let spend_fields = |amount: &[u8]| {
+            [
+                rlp_int(12),
+                rlp_int(1),
+                rlp_id(1, 1),
+                rlp_id(1, 1),
+                amount.to_vec(),
+                rlp_int(20_000_000_000_000),
+                rlp_int(0),
+                rlp_int(7),
+                rlp_bytes(b"test"),
+            ]
+            .concat()
+        };
+        let prefixed = rlp_list(&spend_fields(&[0x81, 20]));
+        assert_eq!(
+            decode_in_chunks(&prefixed, 10).err(),
+            Some(AppSW::TxParsingFail)
+        );
+        let amount = rlp_list(&spend_fields(&[0x81, 200]));
+        let decoded = decode_in_chunks(&amount, 10).unwrap();
+        assert_eq!(ParsedTx::from_decoded(&decoded).unwrap().amount, 200.into());
+
+        // A list length on 2 bytes, with a leading zero
+        let fields = spend_fields(&rlp_int(20));
+        let long = [&[0xf9, 0x00, fields.len() as u8][..], &fields].concat();
+        assert_eq!(
+            decode_in_chunks(&long, 10).err(),
+            Some(AppSW::TxParsingFail)
+        );
+    }
+}
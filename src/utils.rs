@@ -1,49 +1,10 @@
-use alloc::string::{String, ToString};
-use alloc::vec::Vec;
-use core::fmt;
+use ledger_device_sdk::ecc::{make_bip32_path, CurvesId, ECPrivateKey, ECPublicKey, Ed25519};
+use ledger_device_sdk::hash::{blake2::Blake2b_256, sha2::Sha2_256, HashInit};
 
-use ledger_device_sdk::ecc::{make_bip32_path, ECPrivateKey, Ed25519};
-use ledger_device_sdk::hash::{sha2::Sha2_256, HashInit};
-
-pub enum AeEncoding {
-    AccountAddress,
-    Name,
-    Commitment,
-    OracleAddress,
-    ContractAddress,
-    Channel,
-}
-
-impl fmt::Display for AeEncoding {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        use AeEncoding::*;
-
-        match self {
-            AccountAddress => write!(f, "ak"),
-            Name => write!(f, "nm"),
-            Commitment => write!(f, "cm"),
-            OracleAddress => write!(f, "ok"),
-            ContractAddress => write!(f, "ct"),
-            Channel => write!(f, "ch"),
-        }
-    }
-}
-
-pub fn varuint_encode(n: usize) -> Vec<u8> {
-    let mut output = Vec::new();
-
-    if n <= 0xFC {
-        output.push(n as u8);
-    } else if n <= 0xFFFF {
-        output.push(0xFD);
-        output.extend((n as u16).to_le_bytes());
-    } else {
-        output.push(0xFE);
-        output.extend((n as u32).to_le_bytes());
-    }
-
-    output
-}
+/// Key that signs the descriptors of networks, as an uncompressed secp256k1
+/// public key in hex. `build.rs` requires it, and only gives the test key to
+/// builds with the `test_network_key` feature.
+const NETWORK_DESCRIPTOR_KEY: &str = env!("NETWORK_DESCRIPTOR_KEY");
 
 pub fn get_private_key(account_number: u32) -> ECPrivateKey<32, 'E'> {
     const ALLOWED_PATH_LEN: usize = 5;
@@ -54,6 +15,36 @@ pub fn get_private_key(account_number: u32) -> ECPrivateKey<32, 'E'> {
     Ed25519::derive_from_path_slip10(&path)
 }
 
+/// Derive the Ed25519 public key of an account in the 32 bytes format used by
+/// Aeternity addresses.
+pub fn get_public_key(account_number: u32) -> Option<[u8; 32]> {
+    let pk = get_private_key(account_number).public_key().ok()?;
+
+    // From RFC 8032 ("Key Generation" section):
+    // Link: https://datatracker.ietf.org/doc/html/rfc8032#section-5.1.5
+    //
+    // 4.  The public key A is the encoding of the point [s]B.  First,
+    //     encode the y-coordinate (in the range 0 <= y < p) as a little-
+    //     endian string of 32 octets.  The most significant bit of the
+    //     final octet is always zero.  To form the encoding of the point
+    //     [s]B, copy the least significant bit of the x coordinate to the
+    //     most significant bit of the final octet.  The result is the
+    //     public key.
+    //
+    // The ledger library implements the first 3 steps and then return the
+    // public key in an uncompressed format (0x04 byte followed by 32 bytes
+    // for x and 32 bytes for y).
+    let mut pk1: [u8; 32] = pk.pubkey[33..65].try_into().ok()?;
+    // Reverse to make it little-endian
+    pk1.reverse();
+    // Copy the least significant bit
+    if (pk.pubkey[32] & 1) != 0 {
+        pk1[31] |= 0x80;
+    }
+
+    Some(pk1)
+}
+
 pub fn sign(account_number: u32, data: &[u8]) -> Option<[u8; 64]> {
     get_private_key(account_number)
         .sign(data)
@@ -61,27 +52,49 @@ pub fn sign(account_number: u32, data: &[u8]) -> Option<[u8; 64]> {
         .ok()
 }
 
-pub fn to_ae_string(pubkey: &[u8], prefix: AeEncoding) -> String {
-    let pk = [pubkey, &make_check(pubkey)].concat();
+pub fn blake2b_256(input: &[u8]) -> Option<[u8; 32]> {
+    let mut hasher = Blake2b_256::new();
+    let mut output: [u8; 32] = [0; 32];
+    hasher.hash(input, &mut output).ok().map(|_| output)
+}
+
+/// Compute the id of an AENS name, which is the Blake2b hash of the name.
+///
+/// Names are case insensitive, so the name is lowercased before hashing.
+/// Only ASCII names ending with `.chain` are accepted.
+pub fn name_id(name: &str) -> Option<[u8; 32]> {
+    let name = name.to_ascii_lowercase();
+    let label = name.strip_suffix(".chain")?;
 
-    let mut output = prefix.to_string();
-    output.push('_');
-    // The output buffer is resizeable, so it's fine to use unwrap here
-    let _ = bs58::encode(pk).onto(&mut output).unwrap();
+    let valid_label = !label.is_empty()
+        && label
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-');
+    if !valid_label {
+        return None;
+    }
 
-    output
+    blake2b_256(name.as_bytes())
 }
 
-fn make_check(input: &[u8]) -> [u8; 4] {
-    let digest = sha256(&sha256(input));
-    *digest
-        .first_chunk::<4>()
-        .expect("SHA-256 digest must be 32 bytes")
-}
+/// Check the DER-encoded ECDSA signature of a network descriptor, made with
+/// the trusted key over the SHA-256 hash of the descriptor.
+pub fn verify_network_descriptor(data: &[u8], signature: &[u8]) -> bool {
+    let Some(pubkey) = hex::decode(NETWORK_DESCRIPTOR_KEY)
+        .ok()
+        .and_then(|key| <[u8; 65]>::try_from(key).ok())
+    else {
+        return false;
+    };
+    let mut hash = [0; 32];
+    if Sha2_256::new().hash(data, &mut hash).is_err() {
+        return false;
+    }
 
-fn sha256(input: &[u8]) -> [u8; 32] {
-    let mut hasher = Sha2_256::new();
-    let mut output: [u8; 32] = [0; 32];
-    let _ = hasher.hash(input, &mut output);
-    output
+    let key = ECPublicKey::<65, 'W'> {
+        curve: CurvesId::Secp256k1,
+        keylength: pubkey.len(),
+        pubkey,
+    };
+    key.verify((signature, signature.len() as u32), &hash)
 }
@@ -0,0 +1,46 @@
+use core::str::FromStr;
+
+/// Parse a version of the form "major.minor.patch", where each part fits in a
+/// byte.
+pub fn parse_version_string(input: &str) -> Option<(u8, u8, u8)> {
+    // Split the input string by '.'.
+    // Input should be of the form "major.minor.patch",
+    // where "major", "minor", and "patch" are integers.
+    let mut parts = input.split('.');
+    let major = u8::from_str(parts.next()?).ok()?;
+    let minor = u8::from_str(parts.next()?).ok()?;
+    let patch = u8::from_str(parts.next()?).ok()?;
+    Some((major, minor, patch))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    #[test]
+    fn versions() {
+        assert_eq!(parse_version_string("1.0.0"), Some((1, 0, 0)));
+        assert_eq!(parse_version_string("0.12.255"), Some((0, 12, 255)));
+        assert!(parse_version_string(env!("CARGO_PKG_VERSION")).is_some());
+
+        assert_eq!(parse_version_string(""), None);
+        assert_eq!(parse_version_string("1.0"), None);
+        assert_eq!(parse_version_string("1.0.256"), None);
+        assert_eq!(parse_version_string("1.-1.0"), None);
+        assert_eq!(parse_version_string("a.b.c"), None);
+    }
+
+    proptest! {
+        #[test]
+        fn round_trip(major: u8, minor: u8, patch: u8) {
+            let version = format!("{}.{}.{}", major, minor, patch);
+            prop_assert_eq!(parse_version_string(&version), Some((major, minor, patch)));
+        }
+
+        #[test]
+        fn never_panics(input in "\\PC*") {
+            let _ = parse_version_string(&input);
+        }
+    }
+}
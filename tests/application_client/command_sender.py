@@ -26,6 +26,14 @@ class InsType(IntEnum):
     SIGN_TX = 0x04
     GET_VERSION = 0x06
     SIGN_MSG = 0x08
+    SIGN_DATA = 0x0A
+    SIGN_TYPED_DATA = 0x0C
+    SIGN_DELEGATION = 0x0E
+    GET_APP_CONFIGURATION = 0x10
+    REGISTER_ADDRESS = 0x12
+    PROVIDE_NETWORK = 0x14
+    ABORT = 0x16
+    SIGN_TX_BATCH = 0x18
 
 
 class Errors(IntEnum):
@@ -43,10 +51,43 @@ class Errors(IntEnum):
     SW_TX_HASH_FAIL = 0xB006
     SW_BAD_STATE = 0xB007
     SW_SIGNATURE_FAIL = 0xB008
+    SW_TX_UNSUPPORTED_TAG = 0xB010
+    SW_TX_UNSUPPORTED_VERSION = 0xB011
+    SW_TX_BAD_ADDRESS_PREFIX = 0xB012
+    SW_TX_BAD_ADDRESS_LENGTH = 0xB013
+    SW_TX_INVALID_NETWORK_ID = 0xB014
+    SW_TX_LENGTH_MISMATCH = 0xB015
+    SW_TX_UNEXPECTED_CHUNK = 0xB016
+    SW_TX_INVALID_PAYLOAD = 0xB017
+    SW_TX_NAME_MISMATCH = 0xB018
+    SW_TX_COST_OVERFLOW = 0xB019
+    SW_TX_UNSUPPORTED_VM = 0xB01A
+    SW_TX_UNKNOWN_VM = 0xB01B
+    SW_TX_BLIND_SIGNING_DISABLED = 0xB01C
     SW_MSG_WRONG_LENGTH = (0xB100,)
     SW_MSG_HASH_FAIL = (0xB101,)
     SW_MSG_SIGN_FAIL = (0xB102,)
+    SW_MSG_PARSING_FAIL = 0xB103
     SW_GET_ADDRESS_PARSING_FAIL = (0xB200,)
+    SW_DATA_WRONG_LENGTH = 0xB300
+    SW_DATA_SIGN_FAIL = 0xB301
+    SW_DATA_SIGNING_DISABLED = 0xB302
+    SW_DATA_HASH_FAIL = 0xB303
+    SW_DATA_PARSING_FAIL = 0xB304
+    SW_TYPED_DATA_WRONG_LENGTH = 0xB400
+    SW_TYPED_DATA_PARSING_FAIL = 0xB401
+    SW_TYPED_DATA_HASH_FAIL = 0xB402
+    SW_TYPED_DATA_SIGN_FAIL = 0xB403
+    SW_DELEGATION_PARSING_FAIL = 0xB500
+    SW_DELEGATION_SIGN_FAIL = 0xB501
+    SW_SWAP_CHECK_FAIL = 0xB600
+    SW_ADDRESS_BOOK_PARSING_FAIL = 0xB700
+    SW_ADDRESS_BOOK_FULL = 0xB701
+    SW_NETWORK_PARSING_FAIL = 0xB800
+    SW_NETWORK_SIGNATURE_FAIL = 0xB801
+    SW_BATCH_PARSING_FAIL = 0xB900
+    SW_BATCH_NONCE_FAIL = 0xB901
+    SW_ADDRESS_BOOK_FAIL = 0xB702
 
 
 def split_message(message: bytes, max_size: int) -> List[bytes]:
@@ -60,6 +101,9 @@ class CommandSender:
     def get_version(self) -> RAPDU:
         return self.backend.exchange(cla=CLA, ins=InsType.GET_VERSION)
 
+    def get_app_configuration(self) -> RAPDU:
+        return self.backend.exchange(cla=CLA, ins=InsType.GET_APP_CONFIGURATION)
+
     def get_address(self, account_number: bytes) -> RAPDU:
         return self.backend.exchange(
             cla=CLA,
@@ -82,6 +126,28 @@ class CommandSender:
         ) as response:
             yield response
 
+    @contextmanager
+    def register_address(self, label: str, address: str) -> Generator[None, None, None]:
+        data = (
+            bytes([len(label)]) + label.encode() + bytes([len(address)]) + address.encode()
+        )
+        with self.backend.exchange_async(
+            cla=CLA, ins=InsType.REGISTER_ADDRESS, p1=0, p2=0, data=data
+        ) as response:
+            yield response
+
+    def provide_network(
+        self, network_id: str, name: str, ticker: str, decimals: int, signature: bytes
+    ) -> RAPDU:
+        data = b"".join(bytes([len(s)]) + s.encode() for s in [network_id, name, ticker])
+        return self.backend.exchange(
+            cla=CLA,
+            ins=InsType.PROVIDE_NETWORK,
+            p1=0,
+            p2=0,
+            data=data + bytes([decimals]) + signature,
+        )
+
     #@contextmanager
     #def sign_tx(self, path: str, transaction: bytes) -> Generator[None, None, None]:
     #    self.backend.exchange(cla=CLA,
@@ -23,6 +23,44 @@ def unpack_get_version_response(response: bytes) -> Tuple[int, int, int]:
     return (major, minor, patch)
 
 
+# Unpack from response:
+# response = MAJOR (1)
+#            MINOR (1)
+#            PATCH (1)
+#            name_len (1)
+#            name (var)
+#            model_len (1)
+#            model (var)
+#            settings (1)
+#            features (2)
+#            max_message_len (2)
+#            max_raw_data_len (2)
+#            max_typed_data_len (2)
+#            tx_tags_count (1)
+#            tx_tags (var)
+def unpack_get_app_configuration_response(response: bytes) -> dict:
+    version = unpack("BBB", response[:3])
+    response, _, name = pop_size_prefixed_buf_from_buf(response[3:])
+    response, _, model = pop_size_prefixed_buf_from_buf(response)
+    settings, features, max_message_len, max_raw_data_len, max_typed_data_len = unpack(
+        ">BHHHH", response[:9]
+    )
+    response, _, tx_tags = pop_size_prefixed_buf_from_buf(response[9:])
+
+    assert len(response) == 0
+    return {
+        "version": version,
+        "name": name.decode(),
+        "model": model.decode(),
+        "settings": settings,
+        "features": features,
+        "max_message_len": max_message_len,
+        "max_raw_data_len": max_raw_data_len,
+        "max_typed_data_len": max_typed_data_len,
+        "tx_tags": list(tx_tags),
+    }
+
+
 # Unpack from response:
 # response = pub_key_len (1)
 #            pub_key (var)
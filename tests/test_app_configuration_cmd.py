@@ -0,0 +1,19 @@
+import tomli
+from application_client.command_sender import CommandSender
+from application_client.response_unpacker import unpack_get_app_configuration_response
+
+
+# In this test we check that the configuration reported by the app matches the build
+def test_app_configuration(backend, firmware):
+    with open("Cargo.toml", "rb") as f:
+        data = tomli.load(f)
+    version = tuple(map(int, data["package"]["version"].split(".")))
+    client = CommandSender(backend)
+    rapdu = client.get_app_configuration()
+    config = unpack_get_app_configuration_response(rapdu.data)
+    assert config["version"] == version
+    assert config["name"] == "Aeternity"
+    assert config["model"] == {"nanosp": "nanosplus"}.get(firmware.device, firmware.device)
+    # Blind signing and expert mode are disabled by default
+    assert config["settings"] == 0
+    assert config["tx_tags"] == [0x0C, 0x2A, 0x2B, 0x51]
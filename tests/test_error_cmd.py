@@ -1,7 +1,9 @@
+import hashlib
+
 import pytest
 
 from ragger.error import ExceptionRAPDU
-from application_client.command_sender import CLA, InsType, P1, P2, Errors
+from application_client.command_sender import CLA, CommandSender, InsType, P1, P2, Errors
 
 
 # Ensure the app returns an error when a bad CLA is used
@@ -41,11 +43,301 @@ def test_wrong_data_length(backend):
 
 
 # Ensure there is no state confusion when trying wrong APDU sequences
-# def test_invalid_state(backend):
-#     with pytest.raises(ExceptionRAPDU) as e:
-#         backend.exchange(cla=CLA,
-#                          ins=InsType.SIGN_TX,
-#                          p1=P1.P1_START + 1,  # Try to continue a flow instead of start a new one
-#                          p2=P2.P2_MORE,
-#                          data=b"abcde")  # data is not parsed in this case
-#     assert e.value.status == Errors.SW_BAD_STATE
+def test_invalid_state(backend):
+    # Try to continue a flow instead of starting a new one
+    with pytest.raises(ExceptionRAPDU) as e:
+        backend.exchange(cla=CLA, ins=InsType.SIGN_TX, p1=P1.P1_MORE, p2=P2, data=b"abcde")
+    assert e.value.status == Errors.SW_BAD_STATE
+
+    # Abort always succeeds, and leaves nothing to continue
+    backend.exchange(cla=CLA, ins=InsType.ABORT)
+    with pytest.raises(ExceptionRAPDU) as e:
+        backend.exchange(cla=CLA, ins=InsType.SIGN_TX, p1=P1.P1_MORE, p2=P2, data=b"abcde")
+    assert e.value.status == Errors.SW_BAD_STATE
+
+
+# Ensure the app refuses typed data chunks that do not follow a first chunk
+def test_sign_typed_data_without_first_chunk(backend):
+    with pytest.raises(ExceptionRAPDU) as e:
+        backend.exchange(
+            cla=CLA,
+            ins=InsType.SIGN_TYPED_DATA,
+            p1=P1.P1_MORE,
+            p2=P2,
+            data=b"abcde",
+        )
+    assert e.value.status == Errors.SW_TYPED_DATA_WRONG_LENGTH
+
+
+# Ensure the app refuses delegations with an unknown type
+def test_sign_delegation_wrong_type(backend):
+    with pytest.raises(ExceptionRAPDU) as e:
+        backend.exchange(cla=CLA, ins=InsType.SIGN_DELEGATION, p1=0x03, p2=P2)
+    assert e.value.status == Errors.SW_WRONG_P1P2
+
+
+# Ensure the app refuses to sign data while blind signing is disabled in the settings
+def test_sign_data_disabled(backend):
+    data = b"hello"
+    with pytest.raises(ExceptionRAPDU) as e:
+        backend.exchange(
+            cla=CLA,
+            ins=InsType.SIGN_DATA,
+            p1=P1.P1_START,
+            p2=P2,
+            data=(0).to_bytes(4, "big") + len(data).to_bytes(4, "big") + data,
+        )
+    assert e.value.status == Errors.SW_DATA_SIGNING_DISABLED
+
+
+# Ensure the app refuses messages with a truncated header or a wrong length
+def test_sign_msg_wrong_length(backend):
+    with pytest.raises(ExceptionRAPDU) as e:
+        backend.exchange(cla=CLA, ins=InsType.SIGN_MSG, p1=P1.P1_START, p2=P2, data=b"\x00\x00")
+    assert e.value.status == Errors.SW_MSG_PARSING_FAIL
+    with pytest.raises(ExceptionRAPDU) as e:
+        backend.exchange(
+            cla=CLA,
+            ins=InsType.SIGN_MSG,
+            p1=P1.P1_START,
+            p2=P2,
+            data=(0).to_bytes(4, "big") + (6).to_bytes(4, "big") + b"hello",
+        )
+    assert e.value.status == Errors.SW_MSG_WRONG_LENGTH
+
+
+# Ensure the app tells apart unsupported transactions, bad headers and stray chunks
+def test_sign_tx_errors(backend):
+    def sign_tx_first_chunk(tx: bytes, network_id: bytes = b"ae_mainnet") -> bytes:
+        return (
+            (0).to_bytes(4, "big")
+            + len(tx).to_bytes(4, "big")
+            + bytes([0, len(network_id)])
+            + network_id
+            + tx
+        )
+
+    # A list with the tag and version of a NameClaimTx
+    with pytest.raises(ExceptionRAPDU) as e:
+        backend.exchange(
+            cla=CLA,
+            ins=InsType.SIGN_TX,
+            p1=P1.P1_START,
+            p2=P2,
+            data=sign_tx_first_chunk(bytes([0xC2, 0x20, 0x02])),
+        )
+    assert e.value.status == Errors.SW_TX_UNSUPPORTED_TAG
+
+    # A SpendTx with version 2
+    with pytest.raises(ExceptionRAPDU) as e:
+        backend.exchange(
+            cla=CLA,
+            ins=InsType.SIGN_TX,
+            p1=P1.P1_START,
+            p2=P2,
+            data=sign_tx_first_chunk(bytes([0xC2, 0x0C, 0x02])),
+        )
+    assert e.value.status == Errors.SW_TX_UNSUPPORTED_VERSION
+
+    with pytest.raises(ExceptionRAPDU) as e:
+        backend.exchange(
+            cla=CLA,
+            ins=InsType.SIGN_TX,
+            p1=P1.P1_START,
+            p2=P2,
+            data=sign_tx_first_chunk(bytes([0xC2, 0x0C, 0x01]), network_id=b""),
+        )
+    assert e.value.status == Errors.SW_TX_INVALID_NETWORK_ID
+
+    with pytest.raises(ExceptionRAPDU) as e:
+        backend.exchange(cla=CLA, ins=InsType.SIGN_TX, p1=P1.P1_MORE, p2=P2, data=b"abcde")
+    assert e.value.status == Errors.SW_BAD_STATE
+
+
+def rlp(item) -> bytes:
+    if isinstance(item, int):
+        item = item.to_bytes((item.bit_length() + 7) // 8, "big")
+    if isinstance(item, list):
+        payload, offset = b"".join(rlp(i) for i in item), 0xC0
+    elif len(item) == 1 and item[0] < 0x80:
+        return item
+    else:
+        payload, offset = item, 0x80
+    if len(payload) < 56:
+        return bytes([offset + len(payload)]) + payload
+    length = len(payload).to_bytes((len(payload).bit_length() + 7) // 8, "big")
+    return bytes([offset + 55 + len(length)]) + length + payload
+
+
+# Ensure the app refuses to sign a spend to an AENS name when the plaintext
+# name sent by the host is not the name of the recipient
+def test_sign_tx_name_mismatch(backend):
+    name_hash = hashlib.blake2b(b"bob.chain", digest_size=32).digest()
+    tx = rlp([12, 1, b"\x01" + bytes(32), b"\x02" + name_hash, 1, 2, 0, 1, b""])
+    for name in [b"alice.chain", b"bob"]:
+        data = (
+            (0).to_bytes(4, "big")
+            + len(tx).to_bytes(4, "big")
+            + bytes([0x80, len(b"ae_mainnet")])
+            + b"ae_mainnet"
+            + bytes([len(name)])
+            + name
+            + tx
+        )
+        with pytest.raises(ExceptionRAPDU) as e:
+            backend.exchange(cla=CLA, ins=InsType.SIGN_TX, p1=P1.P1_START, p2=P2, data=data)
+        assert e.value.status == Errors.SW_TX_NAME_MISMATCH
+
+
+# Ensure the app refuses to send parts of a signed transaction when none was signed
+def test_get_signed_tx_chunk_without_signed_tx(backend):
+    with pytest.raises(ExceptionRAPDU) as e:
+        backend.exchange(cla=CLA, ins=InsType.SIGN_TX, p1=0x01, p2=P2)
+    assert e.value.status == Errors.SW_BAD_STATE
+
+
+# Ensure the app rejects addresses and labels that cannot be registered,
+# before asking the user to confirm them
+def test_register_address_errors(backend):
+    address = "ak_2swhLkgBPeeADxVTAVCJnZLY5NZtCFiM93JxsEaMuC59euuFRQ"
+
+    def register(label: bytes, address: bytes) -> bytes:
+        return bytes([len(label)]) + label + bytes([len(address)]) + address
+
+    for data in [
+        register(b"", address.encode()),
+        register(b"A label that is far too long", address.encode()),
+        register(b"Treasury", address[:-1].encode()),
+        register(b"Treasury", b"nm_" + address[3:].encode()),
+        register(b"Treasury", address.encode()) + b"\x00",
+    ]:
+        with pytest.raises(ExceptionRAPDU) as e:
+            backend.exchange(cla=CLA, ins=InsType.REGISTER_ADDRESS, data=data)
+        assert e.value.status == Errors.SW_ADDRESS_BOOK_PARSING_FAIL
+
+
+# Ensure the app refuses a call to a contract of a VM that cannot be used
+# anymore, instead of showing it like a FATE contract call, and tells it apart
+# from a VM of a later hard fork
+def test_sign_tx_unsupported_vm(backend):
+    caller, contract = b"\x01" + bytes(32), b"\x05" + bytes(32)
+    # ContractCallTx with the ABI of the AEVM, then with an ABI after the FATE one
+    for abi_version, status in [(1, Errors.SW_TX_UNSUPPORTED_VM), (4, Errors.SW_TX_UNKNOWN_VM)]:
+        tx = rlp([43, 1, caller, 1, contract, abi_version, 10**15, 0, 0, 10**5, 10**9, b""])
+        data = (
+            (0).to_bytes(4, "big")
+            + len(tx).to_bytes(4, "big")
+            + bytes([0, len(b"ae_mainnet")])
+            + b"ae_mainnet"
+            + tx
+        )
+        with pytest.raises(ExceptionRAPDU) as e:
+            backend.exchange(cla=CLA, ins=InsType.SIGN_TX, p1=P1.P1_START, p2=P2, data=data)
+        assert e.value.status == status
+
+
+# Ensure the app refuses a GA meta transaction while blind signing is disabled,
+# since its inner transaction cannot be shown
+def test_sign_ga_meta_tx_blind_signing_disabled(backend):
+    ga_id = b"\x01" + bytes(32)
+    inner_tx = rlp([12, 1])
+    tx = rlp([81, 2, ga_id, b"auth", 3, 10**14, 5 * 10**4, 10**9, inner_tx])
+    data = (
+        (0).to_bytes(4, "big")
+        + len(tx).to_bytes(4, "big")
+        + bytes([0, len(b"ae_mainnet")])
+        + b"ae_mainnet"
+        + tx
+    )
+    with pytest.raises(ExceptionRAPDU) as e:
+        backend.exchange(cla=CLA, ins=InsType.SIGN_TX, p1=P1.P1_START, p2=P2, data=data)
+    assert e.value.status == Errors.SW_TX_BLIND_SIGNING_DISABLED
+
+
+# Ensure the app decodes a transaction sent over several chunks, and checks it
+# once the last chunk is received
+def test_sign_tx_in_chunks(backend):
+    caller, contract = b"\x01" + bytes(32), b"\x05" + bytes(32)
+    # ContractCallTx with the ABI of the AEVM and a long call data
+    tx = rlp([43, 1, caller, 1, contract, 1, 10**15, 0, 0, 10**5, 10**9, bytes(1000)])
+    header = (
+        (0).to_bytes(4, "big")
+        + len(tx).to_bytes(4, "big")
+        + bytes([0, len(b"ae_mainnet")])
+        + b"ae_mainnet"
+    )
+    first_len = 255 - len(header)
+    chunks = [tx[first_len:][i:i + 255] for i in range(0, len(tx) - first_len, 255)]
+
+    first_chunk = header + tx[:first_len]
+    backend.exchange(cla=CLA, ins=InsType.SIGN_TX, p1=P1.P1_START, p2=P2, data=first_chunk)
+    for chunk in chunks[:-1]:
+        backend.exchange(cla=CLA, ins=InsType.SIGN_TX, p1=P1.P1_MORE, p2=P2, data=chunk)
+    with pytest.raises(ExceptionRAPDU) as e:
+        backend.exchange(cla=CLA, ins=InsType.SIGN_TX, p1=P1.P1_MORE, p2=P2, data=chunks[-1])
+    assert e.value.status == Errors.SW_TX_UNSUPPORTED_VM
+
+    # The chunks before the last one must be full
+    backend.exchange(cla=CLA, ins=InsType.SIGN_TX, p1=P1.P1_START, p2=P2, data=first_chunk)
+    with pytest.raises(ExceptionRAPDU) as e:
+        backend.exchange(cla=CLA, ins=InsType.SIGN_TX, p1=P1.P1_MORE, p2=P2, data=chunks[0][:100])
+    assert e.value.status == Errors.SW_TX_UNEXPECTED_CHUNK
+
+    # A transaction sent back signed, or with its hash, must be kept whole,
+    # which limits its length
+    for p2 in [0x01, 0x02]:
+        with pytest.raises(ExceptionRAPDU) as e:
+            backend.exchange(cla=CLA, ins=InsType.SIGN_TX, p1=P1.P1_START, p2=p2, data=first_chunk)
+        assert e.value.status == Errors.SW_WRONG_TX_LENGTH
+
+
+# Ensure the app checks the header of a batch, and refuses transactions of a
+# batch that are not SpendTx or whose nonces do not increase
+def test_sign_tx_batch_errors(backend):
+    def send(p1: int, data: bytes) -> None:
+        backend.exchange(cla=CLA, ins=InsType.SIGN_TX_BATCH, p1=p1, p2=P2, data=data)
+
+    def batch_header(tx_count: int) -> bytes:
+        return (0).to_bytes(4, "big") + bytes([tx_count, len(b"ae_mainnet")]) + b"ae_mainnet"
+
+    def batch_tx(tx: bytes) -> bytes:
+        return len(tx).to_bytes(4, "big") + tx
+
+    def spend(nonce: int) -> bytes:
+        account = b"\x01" + bytes(32)
+        return rlp([12, 1, account, account, 1, 2 * 10**13, 0, nonce, b""])
+
+    for tx_count in [0, 17]:
+        with pytest.raises(ExceptionRAPDU) as e:
+            send(P1.P1_START, batch_header(tx_count))
+        assert e.value.status == Errors.SW_BATCH_PARSING_FAIL
+
+    send(P1.P1_START, batch_header(2))
+    send(P1.P1_MORE, batch_tx(spend(5)))
+    with pytest.raises(ExceptionRAPDU) as e:
+        send(P1.P1_MORE, batch_tx(spend(5)))
+    assert e.value.status == Errors.SW_BATCH_NONCE_FAIL
+
+    # The failure ended the batch
+    with pytest.raises(ExceptionRAPDU) as e:
+        send(P1.P1_MORE, batch_tx(spend(6)))
+    assert e.value.status == Errors.SW_BAD_STATE
+
+    # A list with the tag and version of a NameClaimTx
+    send(P1.P1_START, batch_header(1))
+    with pytest.raises(ExceptionRAPDU) as e:
+        send(P1.P1_MORE, batch_tx(bytes([0xC2, 0x20, 0x02])))
+    assert e.value.status == Errors.SW_TX_UNSUPPORTED_TAG
+
+
+# Ensure the app refuses network descriptors that redefine a known network or
+# that are not signed by its trusted key
+def test_provide_network_errors(backend):
+    client = CommandSender(backend)
+    with pytest.raises(ExceptionRAPDU) as e:
+        client.provide_network("ae_mainnet", "Mainnet", "HCT", 18, bytes(70))
+    assert e.value.status == Errors.SW_NETWORK_PARSING_FAIL
+
+    with pytest.raises(ExceptionRAPDU) as e:
+        client.provide_network("my_hc", "My Hyperchain", "HCT", 18, bytes(70))
+    assert e.value.status == Errors.SW_NETWORK_SIGNATURE_FAIL
@@ -0,0 +1,31 @@
+import pytest
+
+from ragger.error import ExceptionRAPDU
+from application_client.command_sender import CLA, CommandSender, InsType, Errors
+
+# Test key of the network descriptors, only trusted by the app built with the
+# test_network_key feature:
+#   04ef931422c0b054f16930b53714bb5d215dccf043edc8768c4022d08313233b
+#   64f89fb2b09660d7242fbcec321a2b3dfa4d706006c90e262d623ca409d0362064
+#
+# Descriptor of "my_hc", "My Hyperchain", "HCT" with 18 decimals, signed with
+# `openssl dgst -sha256 -sign`
+DESCRIPTOR = bytes.fromhex("056d795f68630d4d79204879706572636861696e0348435412")
+SIGNATURE = bytes.fromhex(
+    "3044022100bd47d2d6141a208930e3ef9177ed984f91a6a3246b81a58b11d841d49a00e690"
+    "021f76bd02ebd8e717b9815bc96f86a71911bd9c6163175e63b56c805980aacb4a"
+)
+
+
+# In this test we check that the app accepts a network descriptor signed by
+# the trusted key, and only with its own signature
+def test_provide_network(backend):
+    rapdu = backend.exchange(
+        cla=CLA, ins=InsType.PROVIDE_NETWORK, p1=0, p2=0, data=DESCRIPTOR + SIGNATURE
+    )
+    assert rapdu.data == b""
+
+    # The signature covers the decimals
+    with pytest.raises(ExceptionRAPDU) as e:
+        CommandSender(backend).provide_network("my_hc", "My Hyperchain", "HCT", 9, SIGNATURE)
+    assert e.value.status == Errors.SW_NETWORK_SIGNATURE_FAIL